@@ -5,9 +5,8 @@
 /// 2. Graceful shutdown with configurable timeouts
 /// 3. Force kill when timeout expires
 /// 4. Stopping all processes at once
-use adasa::config::{LimitAction, ProcessConfig};
+use adasa::config::ProcessConfig;
 use adasa::process::ProcessManager;
-use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -23,17 +22,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         name: "sigterm-process".to_string(),
         script: PathBuf::from("/bin/sleep"),
         args: vec!["30".to_string()],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
         autorestart: false,
         max_restarts: 3,
-        restart_delay_secs: 1,
-        max_memory: None,
-        max_cpu: None,
-        limit_action: LimitAction::Log,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 5,
+        stop_timeout: Duration::from_secs(5),
+        ..Default::default()
     };
 
     let id1 = manager.spawn(config1).await?;
@@ -49,17 +41,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         name: "sigint-process".to_string(),
         script: PathBuf::from("/bin/sleep"),
         args: vec!["30".to_string()],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
         autorestart: false,
         max_restarts: 3,
-        restart_delay_secs: 1,
-        max_memory: None,
-        max_cpu: None,
-        limit_action: LimitAction::Log,
         stop_signal: "SIGINT".to_string(),
-        stop_timeout_secs: 3,
+        stop_timeout: Duration::from_secs(3),
+        ..Default::default()
     };
 
     let id2 = manager.spawn(config2).await?;
@@ -75,17 +61,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         name: "timeout-process".to_string(),
         script: PathBuf::from("/bin/sleep"),
         args: vec!["30".to_string()],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
         autorestart: false,
         max_restarts: 3,
-        restart_delay_secs: 1,
-        max_memory: None,
-        max_cpu: None,
-        limit_action: LimitAction::Log,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 2,
+        stop_timeout: Duration::from_secs(2),
+        ..Default::default()
     };
 
     let id3 = manager.spawn(config3).await?;
@@ -99,12 +78,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n4. Current processes:");
     for process in manager.list() {
         println!(
-            "   - {} (ID: {}, PID: {}, Signal: {}, Timeout: {}s, State: {})",
+            "   - {} (ID: {}, PID: {}, Signal: {}, Timeout: {:?}, State: {})",
             process.name,
             process.id,
             process.stats.pid,
             process.config.stop_signal,
-            process.config.stop_timeout_secs,
+            process.config.stop_timeout,
             process.state
         );
     }
@@ -152,21 +131,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             name: format!("batch-process-{}", i),
             script: PathBuf::from("/bin/sleep"),
             args: vec!["30".to_string()],
-            cwd: None,
-            env: HashMap::new(),
-            instances: 1,
             autorestart: false,
             max_restarts: 3,
-            restart_delay_secs: 1,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: LimitAction::Log,
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 5,
+            stop_timeout: Duration::from_secs(5),
+            ..Default::default()
         };
 
         let id = manager.spawn(config).await?;
-        println!("   Started {} (ID: {})", format!("batch-process-{}", i), id);
+        println!("   Started batch-process-{} (ID: {})", i, id);
     }
 
     println!("\n10. Stopping all processes gracefully...");
@@ -1,8 +1,7 @@
 // Example demonstrating process monitoring functionality
 
-use adasa::config::{LimitAction, ProcessConfig};
+use adasa::config::ProcessConfig;
 use adasa::process::ProcessManager;
-use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -16,17 +15,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         name: "my-app".to_string(),
         script: PathBuf::from("/bin/sleep"),
         args: vec!["60".to_string()],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
-        autorestart: true,
-        max_restarts: 10,
-        restart_delay_secs: 1,
-        max_memory: None,
-        max_cpu: None,
-        limit_action: LimitAction::Log,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 10,
+        ..Default::default()
     };
 
     let process_id = manager.spawn(config).await?;
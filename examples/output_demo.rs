@@ -20,7 +20,15 @@ fn main() {
                 restarts: 0,
                 cpu_usage: 2.5,
                 memory_usage: 128 * 1024 * 1024,
+                tree_cpu_usage: 2.5,
+                tree_memory_usage: 128 * 1024 * 1024,
                 last_restart: None,
+                last_exit_code: None,
+                last_exit_signal: None,
+                last_run: None,
+                last_run_duration: None,
+                next_run: None,
+                oom_kills: 0,
             },
         },
         ProcessInfo {
@@ -33,7 +41,15 @@ fn main() {
                 restarts: 3,
                 cpu_usage: 15.8,
                 memory_usage: 512 * 1024 * 1024,
+                tree_cpu_usage: 15.8,
+                tree_memory_usage: 512 * 1024 * 1024,
                 last_restart: Some(std::time::SystemTime::now() - Duration::from_secs(3600)),
+                last_exit_code: None,
+                last_exit_signal: None,
+                last_run: None,
+                last_run_duration: None,
+                next_run: None,
+                oom_kills: 0,
             },
         },
         ProcessInfo {
@@ -46,7 +62,15 @@ fn main() {
                 restarts: 1,
                 cpu_usage: 0.0,
                 memory_usage: 64 * 1024 * 1024,
+                tree_cpu_usage: 0.0,
+                tree_memory_usage: 64 * 1024 * 1024,
                 last_restart: Some(std::time::SystemTime::now() - Duration::from_secs(45)),
+                last_exit_code: None,
+                last_exit_signal: None,
+                last_run: None,
+                last_run_duration: None,
+                next_run: None,
+                oom_kills: 0,
             },
         },
         ProcessInfo {
@@ -59,7 +83,15 @@ fn main() {
                 restarts: 5,
                 cpu_usage: 0.0,
                 memory_usage: 0,
+                tree_cpu_usage: 0.0,
+                tree_memory_usage: 0,
                 last_restart: Some(std::time::SystemTime::now() - Duration::from_secs(120)),
+                last_exit_code: None,
+                last_exit_signal: None,
+                last_run: None,
+                last_run_duration: None,
+                next_run: None,
+                oom_kills: 0,
             },
         },
         ProcessInfo {
@@ -72,7 +104,15 @@ fn main() {
                 restarts: 0,
                 cpu_usage: 0.0,
                 memory_usage: 0,
+                tree_cpu_usage: 0.0,
+                tree_memory_usage: 0,
                 last_restart: None,
+                last_exit_code: None,
+                last_exit_signal: None,
+                last_run: None,
+                last_run_duration: None,
+                next_run: None,
+                oom_kills: 0,
             },
         },
     ];
@@ -1,7 +1,7 @@
 // Example demonstrating restart logic with backoff
 // This is not meant to be compiled, just for documentation
 
-use adasa::config::ProcessConfig;
+use adasa::config::{ProcessConfig, RestartMode};
 use adasa::ipc::protocol::ProcessId;
 use adasa::process::{BackoffStrategy, ProcessManager, RestartPolicy};
 use std::time::Duration;
@@ -16,17 +16,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         name: "my-app".to_string(),
         script: "/usr/bin/node".into(),
         args: vec!["server.js".to_string()],
-        cwd: None,
-        env: std::collections::HashMap::new(),
-        instances: 1,
-        autorestart: true,     // Enable automatic restart
-        max_restarts: 10,      // Max 10 restarts in 60 seconds
-        restart_delay_secs: 1, // Initial delay of 1 second
-        max_memory: None,
-        max_cpu: None,
-        limit_action: adasa::config::LimitAction::Log,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 10,
+        ..Default::default()
     };
 
     let process_id = manager.spawn(config).await?;
@@ -51,19 +41,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 println!("  Can restart: {}", can_restart);
             }
 
-            // Try automatic restart with backoff
-            match manager.try_auto_restart(crashed_id).await {
+            // Schedule an automatic restart with backoff
+            match manager.try_auto_restart(crashed_id) {
                 Ok(true) => {
-                    println!("  ✓ Process restarted successfully");
-
-                    // Show updated stats
-                    if let Some(process) = manager.get_status(crashed_id) {
-                        println!("  New PID: {}", process.stats.pid);
-                        println!("  Total restarts: {}", process.stats.restarts);
-                        if let Some(last_restart) = process.stats.last_restart {
-                            println!("  Last restart: {:?}", last_restart);
-                        }
-                    }
+                    println!("  ✓ Restart scheduled");
                 }
                 Ok(false) => {
                     println!("  ✗ Restart blocked by policy (max restarts reached)");
@@ -75,6 +56,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
+        // Respawn processes whose backoff delay has passed
+        for restarted_id in manager.run_due_restarts().await {
+            if let Some(process) = manager.get_status(restarted_id) {
+                println!(
+                    "Process {} restarted with PID {}",
+                    restarted_id, process.stats.pid
+                );
+            }
+        }
+
         // Display status of all processes
         for process in manager.list() {
             println!("\nProcess: {} ({})", process.name, process.id);
@@ -89,6 +80,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 // Example: Manual restart
+#[allow(dead_code)]
 async fn manual_restart_example(manager: &mut ProcessManager, process_id: ProcessId) {
     println!("Manually restarting process...");
 
@@ -108,16 +100,25 @@ async fn manual_restart_example(manager: &mut ProcessManager, process_id: Proces
 }
 
 // Example: Custom restart policy
+#[allow(dead_code)]
 fn custom_policy_example() {
     // Create a custom restart policy
-    let policy = RestartPolicy {
+    let _policy = RestartPolicy {
         enabled: true,
-        max_restarts: 5,       // Only 5 restarts
-        time_window_secs: 120, // In 2 minutes
-        initial_delay_secs: 2, // Start with 2 second delay
+        max_restarts: 5,                       // Only 5 restarts
+        time_window: Duration::from_secs(120), // In 2 minutes
+        initial_delay: Duration::from_secs(2), // Start with 2 second delay
         backoff_strategy: BackoffStrategy::Exponential {
-            max_delay_secs: 30, // Cap at 30 seconds
+            max_delay: Duration::from_secs(30), // Cap at 30 seconds
         },
+        // Only restart after a failure, and never after exit code 78 (configuration error)
+        mode: RestartMode::OnFailure,
+        success_exit_codes: vec![],
+        no_restart_exit_codes: vec![78],
+        // Spread delays by up to 10% so instances do not restart in lockstep
+        jitter: 0.1,
+        // A run of at least a minute resets the backoff
+        min_uptime: Duration::from_secs(60),
     };
 
     // Backoff progression:
@@ -8,7 +8,6 @@
 /// Run with: cargo run --example rolling_restart_demo
 use adasa::config::ProcessConfig;
 use adasa::process::ProcessManager;
-use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -28,17 +27,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             name: format!("web-server-{}", i),
             script: PathBuf::from("/bin/sleep"),
             args: vec!["60".to_string()],
-            cwd: None,
-            env: HashMap::new(),
-            instances: 1,
-            autorestart: true,
-            max_restarts: 10,
-            restart_delay_secs: 1,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: adasa::config::LimitAction::Log,
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 5,
+            stop_timeout: Duration::from_secs(5),
+            rolling_settle: Duration::from_secs(2),
+            ..Default::default()
         };
 
         let id = manager.spawn(config).await?;
@@ -64,10 +55,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n🔄 Starting rolling restart...");
     println!("This will restart each instance sequentially with health checks\n");
 
-    let health_check_delay = Duration::from_secs(2);
-    let result = manager
-        .rolling_restart("web-server", health_check_delay)
-        .await?;
+    let result = manager.rolling_restart("web-server").await?;
 
     println!(
         "\n✅ Rolling restart completed: {} instances restarted\n",
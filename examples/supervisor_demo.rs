@@ -1,9 +1,7 @@
 use adasa::config::ProcessConfig;
 use adasa::process::{ProcessManager, ProcessSupervisor, SupervisorConfig};
-use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
-use tokio;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -27,17 +25,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         name: "crasher".to_string(),
         script: PathBuf::from("/bin/sh"),
         args: vec!["-c".to_string(), "echo 'I will crash!'; exit 1".to_string()],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
-        autorestart: true,
         max_restarts: 3, // Allow 3 restarts
-        restart_delay_secs: 1,
-        max_memory: None,
-        max_cpu: None,
-        limit_action: adasa::config::LimitAction::Log,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 2,
+        stop_timeout: Duration::from_secs(2),
+        ..Default::default()
     };
 
     // Configure a stable process
@@ -45,17 +35,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         name: "stable".to_string(),
         script: PathBuf::from("/bin/sleep"),
         args: vec!["30".to_string()],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
-        autorestart: true,
-        max_restarts: 10,
-        restart_delay_secs: 1,
-        max_memory: None,
-        max_cpu: None,
-        limit_action: adasa::config::LimitAction::Log,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 2,
+        stop_timeout: Duration::from_secs(2),
+        ..Default::default()
     };
 
     // Spawn processes
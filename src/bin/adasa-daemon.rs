@@ -3,23 +3,40 @@
 
 // Daemon core module
 mod daemon_core {
+    use adasa::config::diff::{PlanAction, ReloadPlan, RunningProcess};
     use adasa::config::ProcessConfig;
     use adasa::error::{AdasaError, Result};
-    use adasa::ipc::protocol::{Command, ProcessId, ProcessInfo, Response, ResponseData};
-    use adasa::ipc::server::IpcServer;
+    use adasa::ipc::protocol::{
+        Command, ConfigFileOptions, Event, LogOptions, ProcessId, ProcessInfo, ReloadOptions,
+        Response, ResponseData,
+    };
+    use adasa::ipc::server::{IpcServer, Reply};
     use adasa::logs::LogManager;
-    use adasa::process::{ProcessManager, ProcessState as ProcState};
-    use adasa::state::{DaemonState, PersistedProcess, StateStore};
-    use std::path::Path;
+    use adasa::metrics::{self, DaemonMetrics};
+    use adasa::perf::PerfTimer;
+    use adasa::process::{procfs, split_instance, ProcessManager, ProcessState as ProcState};
+    use adasa::state::{
+        DaemonState, PersistedProcess, SnapshotStore, StateStore, DEFAULT_SNAPSHOT,
+    };
+    use std::collections::HashSet;
+    use std::net::SocketAddr;
+    use std::path::{Path, PathBuf};
     use std::sync::Arc;
-    use std::time::SystemTime;
+    use std::time::{Duration, SystemTime};
     use tokio::signal;
-    use tokio::sync::RwLock;
+    use tokio::sync::{broadcast, mpsc, Notify, RwLock};
 
     /// Default paths for daemon state and logs
     const DEFAULT_STATE_PATH: &str = "/tmp/adasa_state.json";
     const DEFAULT_LOG_DIR: &str = "/tmp/adasa_logs";
     const DEFAULT_SOCKET_PATH: &str = "/tmp/adasa.sock";
+    const DEFAULT_SNAPSHOT_DIR: &str = "/tmp/adasa_dumps";
+
+    /// How long to wait for more changes before writing the state file
+    const AUTOSAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+    /// How long a dependency without a readiness check must stay up before its dependents start
+    const DEPENDENCY_SETTLE_DELAY: Duration = Duration::from_secs(1);
 
     /// Main daemon struct that coordinates all components
     pub struct Daemon {
@@ -27,12 +44,18 @@ mod daemon_core {
         process_manager: Arc<RwLock<ProcessManager>>,
         /// State store for persistence
         state_store: StateStore,
+        /// Named snapshots written by `adasa save`
+        snapshots: Arc<SnapshotStore>,
         /// Log manager for capturing process output
         log_manager: Arc<RwLock<LogManager>>,
         /// IPC server for client communication
         ipc_server: IpcServer,
         /// Time when daemon was started
         start_time: SystemTime,
+        /// Timings of IPC requests and supervisor iterations
+        metrics: Arc<DaemonMetrics>,
+        /// Address of the Prometheus metrics listener, if enabled
+        metrics_addr: Option<SocketAddr>,
     }
 
     impl Daemon {
@@ -52,7 +75,7 @@ mod daemon_core {
             P2: AsRef<Path>,
             P3: AsRef<Path>,
         {
-            let process_manager = Arc::new(RwLock::new(ProcessManager::new()));
+            let process_manager = Arc::new(RwLock::new(ProcessManager::with_log_dir(&log_dir)));
             let state_store = StateStore::new(state_path);
             let log_manager = Arc::new(RwLock::new(LogManager::new(log_dir).await?));
             let ipc_server = IpcServer::with_socket_path(socket_path);
@@ -60,12 +83,40 @@ mod daemon_core {
             Ok(Self {
                 process_manager,
                 state_store,
+                snapshots: Arc::new(SnapshotStore::new(DEFAULT_SNAPSHOT_DIR)),
                 log_manager,
                 ipc_server,
                 start_time: SystemTime::now(),
+                metrics: Arc::new(DaemonMetrics::new()),
+                metrics_addr: None,
             })
         }
 
+        /// Serve Prometheus metrics over HTTP on the given address
+        pub fn with_metrics_addr(mut self, addr: Option<SocketAddr>) -> Self {
+            self.metrics_addr = addr;
+            self
+        }
+
+        /// Create the cgroups of processes under `root` instead of the default cgroup root
+        pub async fn with_cgroup_root(self, root: Option<PathBuf>) -> Self {
+            if let Some(root) = root {
+                self.process_manager.write().await.set_cgroup_root(root);
+            }
+            self
+        }
+
+        /// Keep resource usage samples for `retention` instead of the default
+        pub async fn with_stats_retention(self, retention: Option<Duration>) -> Self {
+            if let Some(retention) = retention {
+                self.process_manager
+                    .write()
+                    .await
+                    .set_stats_retention(retention);
+            }
+            self
+        }
+
         /// Initialize the daemon and restore previous state
         pub async fn initialize(&mut self) -> Result<()> {
             // Load previous state
@@ -78,32 +129,23 @@ mod daemon_core {
                     state.processes.len()
                 );
 
-                for persisted in state.processes {
-                    // Convert persisted process to config
-                    let config = ProcessConfig {
-                        name: persisted.name.clone(),
-                        script: persisted.script,
-                        args: persisted.args,
-                        cwd: persisted.cwd,
-                        env: persisted.env,
-                        instances: persisted.instances,
-                        autorestart: persisted.autorestart,
-                        max_restarts: persisted.max_restarts,
-                        restart_delay_secs: 1,
-                        max_memory: None,
-                        max_cpu: None,
-                        limit_action: adasa::config::LimitAction::Log,
-                        stop_signal: "SIGTERM".to_string(),
-                        stop_timeout_secs: 10,
-                    };
+                let mut processes = state.processes;
+                processes.sort_by_key(|p| p.id.as_u64());
 
-                    // Spawn the process
-                    match self.spawn_process(config).await {
-                        Ok(id) => {
-                            println!("Restored process: {} (ID: {})", persisted.name, id);
+                let mut pm = self.process_manager.write().await;
+                let mut lm = self.log_manager.write().await;
+
+                for persisted in processes {
+                    let name = persisted.name.clone();
+                    let id = persisted.id;
+
+                    // Restore the process under its old ID and in its old state
+                    match Self::restore_process(&mut pm, &mut lm, persisted, id).await {
+                        Ok(state) => {
+                            println!("Restored process: {} (ID: {}, {})", name, id, state);
                         }
                         Err(e) => {
-                            eprintln!("Failed to restore process {}: {}", persisted.name, e);
+                            eprintln!("Failed to restore process {}: {}", name, e);
                         }
                     }
                 }
@@ -131,14 +173,32 @@ mod daemon_core {
             // Extract fields we need for the event loop
             let process_manager = self.process_manager;
             let log_manager = self.log_manager;
-            let state_store = self.state_store;
+            let state_store = Arc::new(self.state_store);
+            let snapshots = self.snapshots;
             let ipc_server = self.ipc_server;
             let start_time = self.start_time;
+            let metrics = self.metrics;
+
+            // Start the metrics listener if one was requested
+            let metrics_handle = match self.metrics_addr {
+                Some(addr) => {
+                    let listener = metrics::bind(addr).await?;
+                    println!("Serving metrics on http://{}/metrics", addr);
+                    Some(Self::spawn_metrics_server(
+                        listener,
+                        Arc::clone(&process_manager),
+                        Arc::clone(&metrics),
+                        start_time,
+                    ))
+                }
+                None => None,
+            };
 
             // Spawn supervisor task for monitoring and auto-restart
             let pm = Arc::clone(&process_manager);
+            let m = Arc::clone(&metrics);
             tokio::spawn(async move {
-                Self::supervisor_loop(pm).await;
+                Self::supervisor_loop(pm, m).await;
             });
 
             // Spawn stats update task
@@ -147,8 +207,16 @@ mod daemon_core {
                 Self::stats_update_loop(pm).await;
             });
 
+            // Spawn autosave task so the state file survives a daemon crash
+            let changed = Arc::new(Notify::new());
+            let autosave_handle = tokio::spawn(Self::autosave_loop(
+                Arc::clone(&process_manager),
+                Arc::clone(&state_store),
+                Arc::clone(&changed),
+            ));
+
             // Setup signal handlers
-            let shutdown_signal = Self::setup_signal_handlers();
+            let shutdown_signal = Self::setup_signal_handlers().await;
 
             // Run IPC server loop
             let pm = Arc::clone(&process_manager);
@@ -159,7 +227,36 @@ mod daemon_core {
                     .run(move |cmd| {
                         let pm = Arc::clone(&pm);
                         let lm = Arc::clone(&lm);
-                        async move { Self::handle_command(cmd, pm, lm, start_time).await }
+                        let metrics = Arc::clone(&metrics);
+                        let snapshots = Arc::clone(&snapshots);
+                        let changed = Arc::clone(&changed);
+                        async move {
+                            let timer = PerfTimer::new("ipc_request");
+                            let command = cmd.name();
+                            let changes_processes = Self::changes_processes(&cmd);
+                            let reply = match cmd {
+                                Command::Logs(options) if options.follow => {
+                                    Self::follow_logs(options, pm, lm).await
+                                }
+                                Command::Subscribe => Self::subscribe(pm).await,
+                                Command::Save { name } => Self::save_snapshot(name, pm, &snapshots)
+                                    .await
+                                    .map(Reply::from),
+                                Command::Resurrect { name } => {
+                                    Self::resurrect_snapshot(name, pm, lm, &snapshots)
+                                        .await
+                                        .map(Reply::from)
+                                }
+                                cmd => Self::handle_command(cmd, pm, lm, start_time)
+                                    .await
+                                    .map(Reply::from),
+                            };
+                            if changes_processes {
+                                changed.notify_one();
+                            }
+                            metrics.observe_ipc_request(command, timer.stop());
+                            reply
+                        }
                     })
                     .await;
 
@@ -169,21 +266,111 @@ mod daemon_core {
             });
 
             // Wait for shutdown signal
-            shutdown_signal.await;
+            let _ = shutdown_signal.await;
 
             println!("Received shutdown signal, stopping daemon...");
+            process_manager.read().await.emit(Event::DaemonShuttingDown);
 
-            // Abort server task
+            // Abort server tasks
             server_handle.abort();
+            if let Some(handle) = metrics_handle {
+                handle.abort();
+            }
+
+            // Stop autosaving, so processes stopped during shutdown are not saved as stopped
+            autosave_handle.abort();
+            let _ = autosave_handle.await;
 
             // Perform graceful shutdown
-            Self::shutdown_components(process_manager, log_manager, state_store).await?;
+            Self::shutdown_components(process_manager, log_manager, &state_store).await?;
 
             println!("Daemon stopped");
 
             Ok(())
         }
 
+        /// Stream the logs of a process: the last N lines, then new lines as they are written
+        ///
+        /// The stream ends when the client disconnects.
+        async fn follow_logs(
+            options: LogOptions,
+            process_manager: Arc<RwLock<ProcessManager>>,
+            log_manager: Arc<RwLock<LogManager>>,
+        ) -> Result<Reply> {
+            let process_name = process_manager
+                .read()
+                .await
+                .get_status(options.id)
+                .map(|p| p.name.clone())
+                .ok_or_else(|| AdasaError::ProcessNotFound(options.id.to_string()))?;
+
+            let lm = log_manager.read().await;
+
+            // Start tailing before reading the backlog, so no line is lost in between
+            let mut stream = lm
+                .stream_logs(options.id.as_u64(), &process_name, true, true, None)
+                .await?;
+
+            let log_options = adasa::logs::LogReadOptions {
+                lines: options.lines.unwrap_or(100),
+                include_stderr: true,
+                include_stdout: true,
+                filter: None,
+            };
+            let entries = lm
+                .read_logs(options.id.as_u64(), &process_name, &log_options)
+                .await?;
+            drop(lm);
+
+            let (tx, rx) = mpsc::channel(100);
+            tokio::spawn(async move {
+                while let Some(entry) = stream.next().await {
+                    let update = ResponseData::Logs(vec![entry.format()]);
+                    if tx.send(update).await.is_err() {
+                        // Client disconnected
+                        break;
+                    }
+                }
+            });
+
+            Ok(Reply::Stream {
+                first: Response::success(
+                    0,
+                    ResponseData::Logs(entries.iter().map(|entry| entry.format()).collect()),
+                ),
+                updates: rx,
+            })
+        }
+
+        /// Push an event to the client for every state change until it disconnects
+        async fn subscribe(process_manager: Arc<RwLock<ProcessManager>>) -> Result<Reply> {
+            let mut events = process_manager.read().await.subscribe();
+
+            let (tx, rx) = mpsc::channel(100);
+            tokio::spawn(async move {
+                loop {
+                    let event = match events.recv().await {
+                        Ok(event) => event,
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            tracing::warn!("Event subscriber missed {} events", missed);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+
+                    if tx.send(ResponseData::Event(event)).await.is_err() {
+                        // Client disconnected
+                        break;
+                    }
+                }
+            });
+
+            Ok(Reply::Stream {
+                first: Response::success(0, ResponseData::Subscribed),
+                updates: rx,
+            })
+        }
+
         /// Handle a command from a client
         async fn handle_command(
             command: Command,
@@ -192,61 +379,24 @@ mod daemon_core {
             start_time: SystemTime,
         ) -> Result<Response> {
             match command {
-                Command::StartFromConfig { config_path } => {
+                Command::StartFromConfig(ConfigFileOptions {
+                    config_path,
+                    profile,
+                }) => {
                     // Load and validate configuration file
-                    let configs = ProcessConfig::from_file(&config_path)?;
-
-                    let mut pm = process_manager.write().await;
-                    let mut lm = log_manager.write().await;
-                    let mut spawned_count = 0;
-                    let mut failed_count = 0;
-
-                    // Spawn all processes from config
-                    for config in configs {
-                        let instances = config.instances;
-                        let base_name = config.name.clone();
-
-                        for instance_num in 0..instances {
-                            // Generate unique name for each instance
-                            let instance_name = if instances > 1 {
-                                format!("{}-{}", base_name, instance_num)
-                            } else {
-                                base_name.clone()
-                            };
-
-                            // Create instance-specific config
-                            let mut instance_config = config.clone();
-                            instance_config.name = instance_name.clone();
-                            instance_config.instances = 1;
-
-                            // Spawn the process
-                            match pm.spawn(instance_config).await {
-                                Ok(id) => {
-                                    // Create logger for the process
-                                    if let Err(e) = lm.create_logger(id.as_u64(), &instance_name).await {
-                                        eprintln!("Failed to create logger for {}: {}", instance_name, e);
-                                        continue;
-                                    }
-
-                                    // Capture logs from the process
-                                    if let Some(process) = pm.get_mut(id) {
-                                        if let Err(e) = lm
-                                            .capture_logs(id.as_u64(), &instance_name, &mut process.child)
-                                            .await
-                                        {
-                                            eprintln!("Failed to capture logs for {}: {}", instance_name, e);
-                                        }
-                                    }
-
-                                    spawned_count += 1;
-                                }
-                                Err(e) => {
-                                    eprintln!("Failed to spawn {}: {}", instance_name, e);
-                                    failed_count += 1;
-                                }
-                            }
-                        }
-                    }
+                    let configs =
+                        ProcessConfig::from_file_with_profile(&config_path, profile.as_deref())?;
+                    let config_file =
+                        std::fs::canonicalize(&config_path).unwrap_or_else(|_| config_path.clone());
+
+                    // Spawn all processes from config, dependencies first
+                    let (spawned_count, failed_count) = Self::start_configs(
+                        &process_manager,
+                        &log_manager,
+                        configs,
+                        Some(&config_file),
+                    )
+                    .await?;
 
                     if spawned_count == 0 {
                         return Err(AdasaError::SpawnError(
@@ -266,73 +416,57 @@ mod daemon_core {
                     Ok(Response::success(0, ResponseData::Success(message)))
                 }
 
-                Command::ReloadConfig { config_path } => {
+                Command::ReloadConfig(ReloadOptions {
+                    config_path,
+                    profile,
+                    dry_run,
+                }) => {
                     // Load and validate configuration file
-                    let configs = ProcessConfig::from_file(&config_path)?;
+                    let configs =
+                        ProcessConfig::from_file_with_profile(&config_path, profile.as_deref())?;
+                    let config_file =
+                        std::fs::canonicalize(&config_path).unwrap_or_else(|_| config_path.clone());
 
-                    let mut pm = process_manager.write().await;
-                    let mut lm = log_manager.write().await;
-                    let mut updated_count = 0;
-                    let mut added_count = 0;
-
-                    // Process each config
-                    for config in configs {
-                        let base_name = config.name.clone();
-                        
-                        // Check if process with this name already exists
-                        if pm.find_by_name(&base_name).is_some() {
-                            // Update existing process configuration without restarting
-                            // For now, we'll just track that we found it
-                            // In a full implementation, we'd update the config and optionally restart
-                            updated_count += 1;
-                            println!("Process {} already exists, configuration noted", base_name);
-                        } else {
-                            // Spawn new process
-                            let instances = config.instances;
-                            
-                            for instance_num in 0..instances {
-                                let instance_name = if instances > 1 {
-                                    format!("{}-{}", base_name, instance_num)
-                                } else {
-                                    base_name.clone()
-                                };
-
-                                let mut instance_config = config.clone();
-                                instance_config.name = instance_name.clone();
-                                instance_config.instances = 1;
-
-                                match pm.spawn(instance_config).await {
-                                    Ok(id) => {
-                                        if let Err(e) = lm.create_logger(id.as_u64(), &instance_name).await {
-                                            eprintln!("Failed to create logger for {}: {}", instance_name, e);
-                                            continue;
-                                        }
-
-                                        if let Some(process) = pm.get_mut(id) {
-                                            if let Err(e) = lm
-                                                .capture_logs(id.as_u64(), &instance_name, &mut process.child)
-                                                .await
-                                            {
-                                                eprintln!("Failed to capture logs for {}: {}", instance_name, e);
-                                            }
-                                        }
-
-                                        added_count += 1;
-                                    }
-                                    Err(e) => {
-                                        eprintln!("Failed to spawn {}: {}", instance_name, e);
-                                    }
-                                }
-                            }
-                        }
+                    let pm = process_manager.read().await;
+                    let names: Vec<String> = configs.iter().map(|c| c.name.clone()).collect();
+                    let running = Self::running_processes(&pm, &names, &config_file);
+                    let plan = ReloadPlan::compute(&configs, &running);
+                    drop(pm);
+
+                    if dry_run {
+                        return Ok(Response::success(
+                            0,
+                            ResponseData::Reload {
+                                plan,
+                                dry_run,
+                                errors: Vec::new(),
+                            },
+                        ));
                     }
 
-                    let message = format!(
-                        "Config reloaded: {} processes added, {} existing processes found",
-                        added_count, updated_count
-                    );
+                    let errors = Self::apply_reload(
+                        &process_manager,
+                        &log_manager,
+                        &plan,
+                        configs,
+                        &config_file,
+                    )
+                    .await;
 
-                    Ok(Response::success(0, ResponseData::Success(message)))
+                    process_manager.write().await.emit(Event::ConfigReloaded {
+                        config_path,
+                        started: plan.count(PlanAction::Add),
+                        updated: plan.count(PlanAction::Restart) + plan.count(PlanAction::Update),
+                    });
+
+                    Ok(Response::success(
+                        0,
+                        ResponseData::Reload {
+                            plan,
+                            dry_run,
+                            errors,
+                        },
+                    ))
                 }
 
                 Command::Start(options) => {
@@ -367,18 +501,10 @@ mod daemon_core {
                             args: options.args.clone(),
                             cwd: options.cwd.clone(),
                             env: options.env.clone(),
-                            instances: 1, // Each spawned process is a single instance
-                            autorestart: true,
-                            max_restarts: 10,
-                            restart_delay_secs: 1,
-                            max_memory: None,
-                            max_cpu: None,
-                            limit_action: adasa::config::LimitAction::Log,
-                            stop_signal: "SIGTERM".to_string(),
-                            stop_timeout_secs: 10,
+                            ..Default::default()
                         };
 
-                        // Spawn the process
+                        // Spawn the process (the process manager captures its output)
                         match pm.spawn(config).await {
                             Ok(id) => {
                                 // Create logger for the process
@@ -391,23 +517,6 @@ mod daemon_core {
                                     continue;
                                 }
 
-                                // Capture logs from the process
-                                if let Some(process) = pm.get_mut(id) {
-                                    if let Err(e) = lm
-                                        .capture_logs(
-                                            id.as_u64(),
-                                            &instance_name,
-                                            &mut process.child,
-                                        )
-                                        .await
-                                    {
-                                        eprintln!(
-                                            "Failed to capture logs for {}: {}",
-                                            instance_name, e
-                                        );
-                                    }
-                                }
-
                                 spawned_ids.push(id);
                             }
                             Err(e) => {
@@ -453,14 +562,15 @@ mod daemon_core {
                 }
 
                 Command::Restart(options) => {
-                    let mut pm = process_manager.write().await;
-
                     if options.rolling {
-                        // Perform rolling restart
-                        let health_check_delay = std::time::Duration::from_secs(3);
-                        let count = pm
-                            .rolling_restart(&options.target, health_check_delay)
-                            .await?;
+                        // Perform rolling restart, gated on each instance's readiness check.
+                        // The manager is only locked while instances are stopped and spawned.
+                        let count = ProcessManager::rolling_restart_shared(
+                            process_manager.as_ref(),
+                            &options.target,
+                            options.batch,
+                        )
+                        .await?;
 
                         Ok(Response::success(
                             0,
@@ -470,6 +580,8 @@ mod daemon_core {
                             )),
                         ))
                     } else {
+                        let mut pm = process_manager.write().await;
+
                         // Try to parse as ProcessId first
                         let id = if let Ok(id_num) = options.target.parse::<u64>() {
                             ProcessId::new(id_num)
@@ -495,12 +607,20 @@ mod daemon_core {
                         .iter()
                         .map(|p| {
                             let stats = adasa::ipc::protocol::ProcessStats {
-                                pid: Some(p.stats.pid),
+                                pid: p.live_pid(),
                                 uptime: p.stats.uptime(),
                                 restarts: p.stats.restarts,
                                 cpu_usage: p.stats.cpu_usage,
                                 memory_usage: p.stats.memory_usage,
+                                tree_cpu_usage: p.stats.tree_cpu_usage,
+                                tree_memory_usage: p.stats.tree_memory_usage,
                                 last_restart: p.stats.last_restart,
+                                last_exit_code: p.stats.last_exit_code,
+                                last_exit_signal: p.stats.last_exit_signal,
+                                last_run: p.stats.last_run,
+                                last_run_duration: p.stats.last_run_duration,
+                                next_run: p.next_run,
+                                oom_kills: p.stats.oom_kills,
                             };
 
                             let state = match p.state {
@@ -509,6 +629,18 @@ mod daemon_core {
                                 ProcState::Stopping => adasa::ipc::protocol::ProcessState::Stopping,
                                 ProcState::Stopped => adasa::ipc::protocol::ProcessState::Stopped,
                                 ProcState::Errored => adasa::ipc::protocol::ProcessState::Errored,
+                                ProcState::Unhealthy => {
+                                    adasa::ipc::protocol::ProcessState::Unhealthy
+                                }
+                                ProcState::Scheduled => {
+                                    adasa::ipc::protocol::ProcessState::Scheduled
+                                }
+                                ProcState::Completed => {
+                                    adasa::ipc::protocol::ProcessState::Completed
+                                }
+                                ProcState::CrashLoop => {
+                                    adasa::ipc::protocol::ProcessState::CrashLoop
+                                }
                             };
 
                             ProcessInfo {
@@ -534,30 +666,35 @@ mod daemon_core {
 
                     let lm = log_manager.read().await;
 
-                    if options.follow {
-                        // For streaming logs, we'll return a message indicating streaming is not yet implemented
-                        Ok(Response::success(
-                            0,
-                            ResponseData::Success("Log streaming not yet implemented".to_string()),
-                        ))
-                    } else {
-                        // Read last N lines
-                        let log_options = adasa::logs::LogReadOptions {
-                            lines: options.lines.unwrap_or(100),
-                            include_stderr: true,
-                            include_stdout: true,
-                            filter: None,
-                        };
+                    // Read last N lines (follow mode is streamed by follow_logs)
+                    let log_options = adasa::logs::LogReadOptions {
+                        lines: options.lines.unwrap_or(100),
+                        include_stderr: true,
+                        include_stdout: true,
+                        filter: None,
+                    };
 
-                        let entries = lm
-                            .read_logs(options.id.as_u64(), &process.name, &log_options)
-                            .await?;
+                    let entries = lm
+                        .read_logs(options.id.as_u64(), &process.name, &log_options)
+                        .await?;
 
-                        let log_lines: Vec<String> =
-                            entries.iter().map(|entry| entry.format()).collect();
+                    let log_lines: Vec<String> =
+                        entries.iter().map(|entry| entry.format()).collect();
 
-                        Ok(Response::success(0, ResponseData::Logs(log_lines)))
-                    }
+                    Ok(Response::success(0, ResponseData::Logs(log_lines)))
+                }
+
+                Command::GetConfig(options) => {
+                    let pm = process_manager.read().await;
+                    let running =
+                        Self::running_processes(&pm, &options.names, &options.config_path);
+                    Ok(Response::success(0, ResponseData::Config(running)))
+                }
+
+                Command::Stats { target } => {
+                    let pm = process_manager.read().await;
+                    let histories = pm.stats_history(&target)?;
+                    Ok(Response::success(0, ResponseData::Stats(histories)))
                 }
 
                 Command::Delete(options) => {
@@ -631,6 +768,60 @@ mod daemon_core {
                     Ok(Response::success(0, message))
                 }
 
+                Command::Scale(options) => {
+                    if options.instances == 0 {
+                        return Err(AdasaError::Other(format!(
+                            "Cannot scale {} to 0 instances, use delete to remove it",
+                            options.target
+                        )));
+                    }
+
+                    let mut pm = process_manager.write().await;
+                    let mut lm = log_manager.write().await;
+
+                    let (template, config_file) = pm
+                        .instances_of(&options.target)
+                        .first()
+                        .map(|(_, p)| (p.config.clone(), p.config_file.clone()))
+                        .ok_or_else(|| AdasaError::ProcessNotFound(options.target.clone()))?;
+
+                    let (started, removed) = Self::scale_instances(
+                        &mut pm,
+                        &mut lm,
+                        &options.target,
+                        options.instances,
+                        &template,
+                        config_file.as_deref(),
+                    )
+                    .await;
+
+                    let running = pm.instances_of(&options.target).len();
+                    if running != options.instances {
+                        return Err(AdasaError::Other(format!(
+                            "Scaled {} to {} of {} instances (started {}, removed {})",
+                            options.target, running, options.instances, started, removed
+                        )));
+                    }
+
+                    Ok(Response::success(
+                        0,
+                        ResponseData::Success(format!(
+                            "Scaled {} to {} instance(s) (started {}, removed {})",
+                            options.target, options.instances, started, removed
+                        )),
+                    ))
+                }
+
+                Command::Subscribe => {
+                    // Streamed by subscribe() before commands get here
+                    unreachable!("Subscribe is handled as a stream")
+                }
+
+                Command::Save { .. } | Command::Resurrect { .. } => {
+                    // Handled with the snapshot store before commands get here
+                    unreachable!("Snapshots are handled by save_snapshot and resurrect_snapshot")
+                }
+
                 Command::Daemon(daemon_cmd) => {
                     use adasa::ipc::protocol::DaemonCommand;
 
@@ -665,7 +856,10 @@ mod daemon_core {
         }
 
         /// Supervisor loop that monitors processes and handles auto-restart (optimized)
-        async fn supervisor_loop(process_manager: Arc<RwLock<ProcessManager>>) {
+        async fn supervisor_loop(
+            process_manager: Arc<RwLock<ProcessManager>>,
+            metrics: Arc<DaemonMetrics>,
+        ) {
             // Use 500ms interval for faster crash detection while still being efficient
             let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(500));
             // Skip first tick to avoid immediate execution
@@ -674,28 +868,34 @@ mod daemon_core {
             loop {
                 interval.tick().await;
 
-                // Use a shorter-lived write lock to reduce contention
-                let crashed = {
-                    let mut pm = process_manager.write().await;
-                    pm.detect_crashes()
-                };
+                let timer = PerfTimer::with_threshold("supervisor_loop", 500);
+                Self::supervise_once(&process_manager).await;
+                metrics.observe_supervisor_loop(timer.stop());
+            }
+        }
 
-                // If no crashes, continue immediately without holding lock
-                if crashed.is_empty() {
-                    continue;
-                }
+        /// One iteration of the supervisor loop: restarts, scheduled jobs and health checks
+        async fn supervise_once(process_manager: &Arc<RwLock<ProcessManager>>) {
+            // Use a shorter-lived write lock to reduce contention
+            let crashed = {
+                let mut pm = process_manager.write().await;
+                pm.detect_crashes()
+            };
 
-                // Only acquire write lock again if we need to restart processes
+            // Schedule restarts of crashed processes, carried out once their backoff delay has passed
+            if !crashed.is_empty() {
                 let mut pm = process_manager.write().await;
-                
-                // Attempt to restart crashed processes
+
                 for process_id in crashed {
-                    match pm.try_auto_restart(process_id).await {
+                    match pm.try_auto_restart(process_id) {
                         Ok(true) => {
-                            tracing::info!("Auto-restarted process: {}", process_id);
+                            tracing::info!("Scheduled restart of process: {}", process_id);
                         }
                         Ok(false) => {
-                            tracing::debug!("Process {} not restarted (policy prevented it)", process_id);
+                            tracing::debug!(
+                                "Process {} not restarted (policy prevented it)",
+                                process_id
+                            );
                         }
                         Err(e) => {
                             tracing::error!("Failed to auto-restart process {}: {}", process_id, e);
@@ -703,6 +903,72 @@ mod daemon_core {
                     }
                 }
             }
+
+            // Start scheduled jobs that are due, and respawn processes whose restart is due
+            {
+                let mut pm = process_manager.write().await;
+                pm.run_due_jobs().await;
+                for process_id in pm.run_due_restarts().await {
+                    tracing::info!("Restarted process: {}", process_id);
+                }
+            }
+
+            // Collect due health checks, then run the probes without holding the lock
+            let due = {
+                let mut pm = process_manager.write().await;
+                pm.due_health_checks()
+            };
+
+            if due.is_empty() {
+                return;
+            }
+
+            let results = adasa::process::health::run_probes(due).await;
+
+            let mut pm = process_manager.write().await;
+            let unhealthy = pm.record_health_results(results);
+
+            // Stop processes that failed their health check; they are respawned on a later tick
+            for process_id in unhealthy {
+                match pm.restart_unhealthy(process_id) {
+                    Ok(true) => {
+                        tracing::info!("Restarting unhealthy process: {}", process_id);
+                    }
+                    Ok(false) => {
+                        tracing::debug!(
+                            "Unhealthy process {} not restarted (policy prevented it)",
+                            process_id
+                        );
+                    }
+                    Err(e) => {
+                        tracing::error!(
+                            "Failed to restart unhealthy process {}: {}",
+                            process_id,
+                            e
+                        );
+                    }
+                }
+            }
+        }
+
+        /// Serve Prometheus metrics built from the process manager on each scrape
+        fn spawn_metrics_server(
+            listener: tokio::net::TcpListener,
+            process_manager: Arc<RwLock<ProcessManager>>,
+            metrics: Arc<DaemonMetrics>,
+            start_time: SystemTime,
+        ) -> tokio::task::JoinHandle<()> {
+            tokio::spawn(metrics::serve(listener, move || {
+                let process_manager = Arc::clone(&process_manager);
+                let metrics = Arc::clone(&metrics);
+                async move {
+                    let pm = process_manager.read().await;
+                    let uptime = SystemTime::now()
+                        .duration_since(start_time)
+                        .unwrap_or_default();
+                    metrics.render(&pm.list(), uptime)
+                }
+            }))
         }
 
         /// Stats update loop that periodically updates process statistics (optimized)
@@ -718,7 +984,9 @@ mod daemon_core {
                 // Use shorter-lived lock for stats update
                 let result = {
                     let mut pm = process_manager.write().await;
-                    pm.update_stats()
+                    let result = pm.update_stats();
+                    pm.record_stats_samples();
+                    result
                 };
                 
                 if let Err(e) = result {
@@ -756,18 +1024,20 @@ mod daemon_core {
         async fn shutdown_components(
             process_manager: Arc<RwLock<ProcessManager>>,
             log_manager: Arc<RwLock<LogManager>>,
-            state_store: StateStore,
+            state_store: &StateStore,
         ) -> Result<()> {
             tracing::info!("Shutting down daemon gracefully...");
 
-            // Stop all processes gracefully
+            // Capture state before stopping, so processes stopped by the user can be told apart
             let mut pm = process_manager.write().await;
+            let state = Self::build_state_from_manager(&pm).await;
+
+            // Stop all processes gracefully
             if let Err(e) = pm.stop_all().await {
                 tracing::error!("Error during graceful shutdown of processes: {}", e);
             }
 
             // Save state
-            let state = Self::build_state_from_manager(&pm).await;
             if let Err(e) = state_store.save(&state) {
                 tracing::error!("Failed to save state: {}", e);
             } else {
@@ -795,62 +1065,736 @@ mod daemon_core {
                 .map(|p| PersistedProcess {
                     id: p.id,
                     name: p.name.clone(),
-                    script: p.config.script.clone(),
-                    args: p.config.args.clone(),
-                    cwd: p.config.cwd.clone(),
-                    env: p.config.env.clone(),
+                    config: p.config.clone(),
                     state: match p.state {
                         ProcState::Starting => adasa::ipc::protocol::ProcessState::Starting,
                         ProcState::Running => adasa::ipc::protocol::ProcessState::Running,
                         ProcState::Stopping => adasa::ipc::protocol::ProcessState::Stopping,
                         ProcState::Stopped => adasa::ipc::protocol::ProcessState::Stopped,
                         ProcState::Errored => adasa::ipc::protocol::ProcessState::Errored,
+                        ProcState::Unhealthy => adasa::ipc::protocol::ProcessState::Unhealthy,
+                        ProcState::Scheduled => adasa::ipc::protocol::ProcessState::Scheduled,
+                        ProcState::Completed => adasa::ipc::protocol::ProcessState::Completed,
+                        ProcState::CrashLoop => adasa::ipc::protocol::ProcessState::CrashLoop,
                     },
                     stats: adasa::ipc::protocol::ProcessStats {
-                        pid: Some(p.stats.pid),
+                        pid: p.live_pid(),
                         uptime: p.stats.uptime(),
                         restarts: p.stats.restarts,
                         cpu_usage: p.stats.cpu_usage,
                         memory_usage: p.stats.memory_usage,
+                        tree_cpu_usage: p.stats.tree_cpu_usage,
+                        tree_memory_usage: p.stats.tree_memory_usage,
                         last_restart: p.stats.last_restart,
+                        last_exit_code: p.stats.last_exit_code,
+                        last_exit_signal: p.stats.last_exit_signal,
+                        last_run: p.stats.last_run,
+                        last_run_duration: p.stats.last_run_duration,
+                        next_run: p.next_run,
+                        oom_kills: p.stats.oom_kills,
                     },
-                    autorestart: p.config.autorestart,
-                    max_restarts: p.config.max_restarts,
-                    instances: p.config.instances,
+                    start_time: p.live_pid().and_then(procfs::start_time),
+                    config_file: p.config_file.clone(),
                 })
                 .collect();
 
             DaemonState {
-                version: "1.0.0".to_string(),
                 processes,
-                last_updated: SystemTime::now(),
+                ..DaemonState::new()
             }
         }
 
-        /// Helper method to spawn a process (used during initialization)
-        async fn spawn_process(&self, config: ProcessConfig) -> Result<ProcessId> {
-            let name = config.name.clone();
-
-            let mut pm = self.process_manager.write().await;
-            let id = pm.spawn(config).await?;
+        /// Restore a persisted process under the given ID, in the state it was saved in
+        async fn restore_process(
+            pm: &mut ProcessManager,
+            lm: &mut LogManager,
+            persisted: PersistedProcess,
+            id: ProcessId,
+        ) -> Result<ProcState> {
+            let name = persisted.name;
+            let state = match persisted.state {
+                adasa::ipc::protocol::ProcessState::Starting
+                | adasa::ipc::protocol::ProcessState::Restarting => ProcState::Starting,
+                adasa::ipc::protocol::ProcessState::Running => ProcState::Running,
+                adasa::ipc::protocol::ProcessState::Stopping => ProcState::Stopping,
+                adasa::ipc::protocol::ProcessState::Stopped => ProcState::Stopped,
+                adasa::ipc::protocol::ProcessState::Errored => ProcState::Errored,
+                adasa::ipc::protocol::ProcessState::Unhealthy => ProcState::Unhealthy,
+                adasa::ipc::protocol::ProcessState::Scheduled => ProcState::Scheduled,
+                adasa::ipc::protocol::ProcessState::Completed => ProcState::Completed,
+                adasa::ipc::protocol::ProcessState::CrashLoop => ProcState::CrashLoop,
+            };
+
+            // A process that outlived the previous daemon is adopted instead of respawned
+            let adopted = match (persisted.stats.pid, persisted.start_time) {
+                (Some(pid), Some(start_time))
+                    if state.is_active() && !persisted.config.is_job() =>
+                {
+                    match pm.adopt(id, persisted.config.clone(), pid, start_time) {
+                        Ok(()) => true,
+                        Err(e) => {
+                            tracing::info!("Not adopting {} (PID {}): {}", name, pid, e);
+                            false
+                        }
+                    }
+                }
+                _ => false,
+            };
 
-            // Create logger
-            let mut lm = self.log_manager.write().await;
-            lm.create_logger(id.as_u64(), &name).await?;
+            let restored = if adopted {
+                Ok(())
+            } else {
+                pm.restore(id, persisted.config, state).await
+            };
 
-            // Capture logs
+            // Create logger (the process manager captures the output itself)
             if let Some(process) = pm.get_mut(id) {
-                lm.capture_logs(id.as_u64(), &name, &mut process.child)
-                    .await?;
+                process.config_file = persisted.config_file;
+                lm.create_logger(id.as_u64(), &name).await?;
+            }
+            restored?;
+
+            Ok(pm
+                .get_status(id)
+                .map_or(ProcState::Errored, |p| p.state.clone()))
+        }
+
+        /// Write the current process list to a named snapshot
+        async fn save_snapshot(
+            name: Option<String>,
+            process_manager: Arc<RwLock<ProcessManager>>,
+            snapshots: &SnapshotStore,
+        ) -> Result<Response> {
+            let name = name.unwrap_or_else(|| DEFAULT_SNAPSHOT.to_string());
+            let store = snapshots.store(&name)?;
+
+            let state = Self::build_state_from_manager(&*process_manager.read().await).await;
+            store.save(&state)?;
+
+            let message = format!(
+                "Saved {} processes to snapshot '{}' ({})",
+                state.processes.len(),
+                name,
+                store.path().display()
+            );
+            Ok(Response::success(0, ResponseData::Success(message)))
+        }
+
+        /// Restore the processes of a named snapshot that are not managed yet
+        ///
+        /// Processes keep the state they were saved in. A process whose name is
+        /// already managed is skipped, and one whose ID is taken gets a new ID.
+        async fn resurrect_snapshot(
+            name: Option<String>,
+            process_manager: Arc<RwLock<ProcessManager>>,
+            log_manager: Arc<RwLock<LogManager>>,
+            snapshots: &SnapshotStore,
+        ) -> Result<Response> {
+            let name = name.unwrap_or_else(|| DEFAULT_SNAPSHOT.to_string());
+            let store = snapshots.store(&name)?;
+            if !store.path().exists() {
+                let available = snapshots.list()?;
+                return Err(AdasaError::StateLoadError(format!(
+                    "Snapshot '{}' not found (available: {})",
+                    name,
+                    if available.is_empty() {
+                        "none".to_string()
+                    } else {
+                        available.join(", ")
+                    }
+                )));
+            }
+
+            let mut processes = store.load()?.processes;
+            processes.sort_by_key(|p| p.id.as_u64());
+
+            let mut pm = process_manager.write().await;
+            let mut lm = log_manager.write().await;
+            let mut restored = 0;
+            let mut skipped = 0;
+            let mut failed = 0;
+
+            for persisted in processes {
+                if pm.find_by_name(&persisted.name).is_some() {
+                    skipped += 1;
+                    continue;
+                }
+
+                let id = if pm.get_status(persisted.id).is_some() {
+                    pm.allocate_id()
+                } else {
+                    persisted.id
+                };
+                let process_name = persisted.name.clone();
+
+                match Self::restore_process(&mut pm, &mut lm, persisted, id).await {
+                    Ok(_) => restored += 1,
+                    Err(e) => {
+                        tracing::error!("Failed to resurrect process {}: {}", process_name, e);
+                        failed += 1;
+                    }
+                }
+            }
+
+            let message = format!(
+                "Resurrected {} processes from snapshot '{}' ({} already managed, {} failed)",
+                restored, name, skipped, failed
+            );
+            Ok(Response::success(0, ResponseData::Success(message)))
+        }
+
+        /// Check whether a command can change the process list or process states
+        fn changes_processes(command: &Command) -> bool {
+            matches!(
+                command,
+                Command::Start(_)
+                    | Command::StartFromConfig(_)
+                    | Command::Stop(_)
+                    | Command::Restart(_)
+                    | Command::Delete(_)
+                    | Command::Scale(_)
+                    | Command::ReloadConfig(_)
+                    | Command::Resurrect { .. }
+            )
+        }
+
+        /// Save the daemon state whenever processes change
+        ///
+        /// Changes are signalled through `changed` by the IPC handler and picked up from
+        /// process events. Bursts of changes are written once after [`AUTOSAVE_DEBOUNCE`].
+        async fn autosave_loop(
+            process_manager: Arc<RwLock<ProcessManager>>,
+            state_store: Arc<StateStore>,
+            changed: Arc<Notify>,
+        ) {
+            use broadcast::error::{RecvError, TryRecvError};
+
+            let mut events = process_manager.read().await.subscribe();
+
+            loop {
+                tokio::select! {
+                    _ = changed.notified() => {}
+                    event = events.recv() => match event {
+                        Ok(Event::LimitViolation { .. }) => continue,
+                        Ok(_) | Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => break,
+                    },
+                }
+
+                tokio::time::sleep(AUTOSAVE_DEBOUNCE).await;
+                // Events that arrived meanwhile are covered by this save
+                while let Ok(_) | Err(TryRecvError::Lagged(_)) = events.try_recv() {}
+
+                let state = Self::build_state_from_manager(&*process_manager.read().await).await;
+                match state_store.save(&state) {
+                    Ok(()) => tracing::debug!("Saved state of {} processes", state.processes.len()),
+                    Err(e) => tracing::error!("Failed to autosave state: {}", e),
+                }
+            }
+        }
+
+        /// The running processes a reload of `config_file` compares against
+        ///
+        /// These are the processes named in `names`, however they were started,
+        /// and every other process that was loaded from `config_file`.
+        fn running_processes(
+            pm: &ProcessManager,
+            names: &[String],
+            config_file: &Path,
+        ) -> Vec<RunningProcess> {
+            let mut names = names.to_vec();
+            for process in pm.list() {
+                if process.config_file.as_deref() != Some(config_file) {
+                    continue;
+                }
+                let name = if names.contains(&process.name) {
+                    process.name.as_str()
+                } else {
+                    split_instance(&process.name).0
+                };
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+
+            names
+                .into_iter()
+                .filter_map(|name| {
+                    let instances = pm.instances_of(&name);
+                    let (_, first) = instances.first()?;
+                    let mut config = first.config.clone();
+                    config.name = name;
+                    config.instances = instances.len();
+                    Some(RunningProcess {
+                        config,
+                        instances: instances.len(),
+                        from_file: instances
+                            .iter()
+                            .any(|(_, p)| p.config_file.as_deref() == Some(config_file)),
+                    })
+                })
+                .collect()
+        }
+
+        /// Apply a reload plan, returning the changes that failed
+        ///
+        /// Removed processes go first, then changed processes, then new processes
+        /// in dependency order, so new processes can depend on updated ones.
+        async fn apply_reload(
+            process_manager: &RwLock<ProcessManager>,
+            log_manager: &RwLock<LogManager>,
+            plan: &ReloadPlan,
+            configs: Vec<ProcessConfig>,
+            config_file: &Path,
+        ) -> Vec<String> {
+            let mut errors = {
+                let mut pm = process_manager.write().await;
+                let mut lm = log_manager.write().await;
+                Self::apply_changes(&mut pm, &mut lm, plan, &configs, config_file).await
+            };
+
+            let added: Vec<ProcessConfig> = configs
+                .into_iter()
+                .filter(|c| {
+                    plan.entries
+                        .iter()
+                        .any(|e| e.action == PlanAction::Add && e.name == c.name)
+                })
+                .collect();
+            if !added.is_empty() {
+                match Self::start_configs(process_manager, log_manager, added, Some(config_file))
+                    .await
+                {
+                    Ok((_, 0)) => {}
+                    Ok((_, failed)) => {
+                        errors.push(format!("Failed to start {} new instance(s)", failed))
+                    }
+                    Err(e) => errors.push(format!("Failed to start new processes: {}", e)),
+                }
             }
 
-            Ok(id)
+            errors
+        }
+
+        /// Remove, update and restart the processes of a reload plan that already run
+        async fn apply_changes(
+            pm: &mut ProcessManager,
+            lm: &mut LogManager,
+            plan: &ReloadPlan,
+            configs: &[ProcessConfig],
+            config_file: &Path,
+        ) -> Vec<String> {
+            let mut errors = Vec::new();
+
+            for entry in plan
+                .entries
+                .iter()
+                .filter(|e| e.action == PlanAction::Remove)
+            {
+                let instances: Vec<(ProcessId, String)> = pm
+                    .instances_of(&entry.name)
+                    .iter()
+                    .map(|(_, p)| (p.id, p.name.clone()))
+                    .collect();
+                for (id, name) in instances {
+                    if !Self::delete_instance(pm, lm, id, &name).await {
+                        errors.push(format!("Failed to remove {}", name));
+                    }
+                }
+            }
+
+            for entry in plan.entries.iter().filter(|e| {
+                matches!(
+                    e.action,
+                    PlanAction::Restart | PlanAction::Update | PlanAction::Unchanged
+                )
+            }) {
+                let Some(config) = configs.iter().find(|c| c.name == entry.name) else {
+                    continue;
+                };
+
+                // Scale down first so that only the instances that stay are restarted
+                if entry.to_instances < entry.from_instances {
+                    Self::scale_instances(
+                        pm,
+                        lm,
+                        &entry.name,
+                        entry.to_instances,
+                        config,
+                        Some(config_file),
+                    )
+                    .await;
+                }
+
+                let instances: Vec<(ProcessId, String)> = pm
+                    .instances_of(&entry.name)
+                    .iter()
+                    .map(|(_, p)| (p.id, p.name.clone()))
+                    .collect();
+                for (id, name) in &instances {
+                    if let Err(e) = pm.update_config(*id, config.clone()) {
+                        errors.push(format!("Failed to update {}: {}", name, e));
+                        continue;
+                    }
+                    let Some(process) = pm.get_mut(*id) else {
+                        continue;
+                    };
+                    process.config_file = Some(config_file.to_path_buf());
+
+                    let restart = entry.action == PlanAction::Restart
+                        && matches!(
+                            process.state,
+                            ProcState::Starting
+                                | ProcState::Running
+                                | ProcState::Unhealthy
+                                | ProcState::Errored
+                                | ProcState::CrashLoop
+                        );
+                    if restart {
+                        if let Err(e) = pm.restart(*id).await {
+                            errors.push(format!("Failed to restart {}: {}", name, e));
+                        }
+                    }
+                }
+
+                if entry.to_instances > instances.len() {
+                    let (started, _) = Self::scale_instances(
+                        pm,
+                        lm,
+                        &entry.name,
+                        entry.to_instances,
+                        config,
+                        Some(config_file),
+                    )
+                    .await;
+                    if instances.len() + started < entry.to_instances {
+                        errors.push(format!(
+                            "Started {} of {} new instances of {}",
+                            started,
+                            entry.to_instances - instances.len(),
+                            entry.name
+                        ));
+                    }
+                }
+            }
+
+            errors
+        }
+
+        /// Start or remove instances of a process until it has `instances` of them
+        ///
+        /// New instances are spawned from `template` and take the lowest free instance
+        /// numbers. The instances with the highest numbers are removed first. Returns
+        /// how many instances were started and removed.
+        async fn scale_instances(
+            pm: &mut ProcessManager,
+            lm: &mut LogManager,
+            base_name: &str,
+            instances: usize,
+            template: &ProcessConfig,
+            config_file: Option<&Path>,
+        ) -> (usize, usize) {
+            let current: Vec<(usize, ProcessId, String)> = pm
+                .instances_of(base_name)
+                .iter()
+                .map(|(instance_num, p)| (*instance_num, p.id, p.name.clone()))
+                .collect();
+            let mut started = 0;
+            let mut removed = 0;
+
+            if instances > current.len() {
+                let used: HashSet<usize> = current.iter().map(|(n, _, _)| *n).collect();
+                let free = (0..).filter(|n| !used.contains(n));
+
+                for instance_num in free.take(instances - current.len()) {
+                    let mut config = template.clone();
+                    config.name = format!("{}-{}", base_name, instance_num);
+                    config.instances = 1;
+
+                    match pm.spawn(config.clone()).await {
+                        Ok(id) => {
+                            if let Some(process) = pm.get_mut(id) {
+                                process.config_file = config_file.map(Path::to_path_buf);
+                            }
+                            if let Err(e) = lm.create_logger(id.as_u64(), &config.name).await {
+                                eprintln!("Failed to create logger for {}: {}", config.name, e);
+                            }
+                            started += 1;
+                        }
+                        Err(e) => {
+                            eprintln!("Failed to spawn instance {}: {}", config.name, e)
+                        }
+                    }
+                }
+            }
+
+            for (_, id, name) in current.iter().skip(instances).rev() {
+                if Self::delete_instance(pm, lm, *id, name).await {
+                    removed += 1;
+                }
+            }
+
+            (started, removed)
+        }
+
+        /// Stop a process if it is running, then remove it and its log files
+        async fn delete_instance(
+            pm: &mut ProcessManager,
+            lm: &mut LogManager,
+            id: ProcessId,
+            name: &str,
+        ) -> bool {
+            let running = pm
+                .get_status(id)
+                .is_some_and(|p| p.state != ProcState::Stopped);
+            if running {
+                if let Err(e) = pm.stop(id, false).await {
+                    tracing::error!("Failed to stop process {} before removing it: {}", name, e);
+                    return false;
+                }
+            }
+            if let Err(e) = pm.remove(id) {
+                tracing::error!("Failed to remove process {}: {}", name, e);
+                return false;
+            }
+            if let Err(e) = lm.delete_logs(id.as_u64(), name).await {
+                tracing::warn!("Failed to delete log files for process {}: {}", name, e);
+            }
+            true
+        }
+
+        /// Spawn every instance of a set of configurations in dependency order
+        ///
+        /// Processes that others depend on must become ready before their dependents
+        /// are started. A process is skipped if one of its dependencies is not running.
+        /// The managers are not locked while waiting for a dependency to become ready.
+        ///
+        /// # Returns
+        /// The number of instances spawned and the number that failed or were skipped
+        async fn start_configs(
+            process_manager: &RwLock<ProcessManager>,
+            log_manager: &RwLock<LogManager>,
+            configs: Vec<ProcessConfig>,
+            config_file: Option<&Path>,
+        ) -> Result<(usize, usize)> {
+            let configs = ProcessConfig::sort_by_dependencies(configs)?;
+            let required: HashSet<String> = configs
+                .iter()
+                .flat_map(|c| c.depends_on.iter().cloned())
+                .collect();
+
+            let mut not_ready: HashSet<String> = HashSet::new();
+            let mut spawned_count = 0;
+            let mut failed_count = 0;
+
+            for config in configs {
+                let instances = config.instances;
+                let base_name = config.name.clone();
+                let mut pm = process_manager.write().await;
+                let mut lm = log_manager.write().await;
+
+                if let Some(dependency) = config
+                    .depends_on
+                    .iter()
+                    .find(|d| not_ready.contains(*d) || !pm.is_running(d))
+                {
+                    eprintln!(
+                        "Not starting {}: dependency {} is not running and ready",
+                        base_name, dependency
+                    );
+                    not_ready.insert(base_name);
+                    failed_count += instances;
+                    continue;
+                }
+
+                // Dependents are only started once this process is ready
+                let wait_for_ready = required.contains(&base_name);
+                let mut watches = Vec::new();
+
+                for instance_num in 0..instances {
+                    // Generate unique name for each instance
+                    let instance_name = if instances > 1 {
+                        format!("{}-{}", base_name, instance_num)
+                    } else {
+                        base_name.clone()
+                    };
+
+                    // Create instance-specific config
+                    let mut instance_config = config.clone();
+                    instance_config.name = instance_name.clone();
+                    instance_config.instances = 1;
+
+                    // Spawn the process (the process manager captures its output)
+                    let spawned = if wait_for_ready {
+                        pm.spawn_with_readiness(instance_config, DEPENDENCY_SETTLE_DELAY)
+                            .await
+                            .map(|(id, watch)| {
+                                watches.push((id, watch));
+                                id
+                            })
+                    } else {
+                        pm.spawn(instance_config).await
+                    };
+
+                    match spawned {
+                        Ok(id) => {
+                            if let Some(process) = pm.get_mut(id) {
+                                process.config_file = config_file.map(Path::to_path_buf);
+                            }
+                            if let Err(e) = lm.create_logger(id.as_u64(), &instance_name).await {
+                                eprintln!("Failed to create logger for {}: {}", instance_name, e);
+                            }
+                            spawned_count += 1;
+                        }
+                        Err(e) => {
+                            eprintln!("Failed to spawn {}: {}", instance_name, e);
+                            failed_count += 1;
+                        }
+                    }
+                }
+
+                drop(lm);
+                drop(pm);
+
+                if wait_for_ready {
+                    if watches.is_empty() {
+                        not_ready.insert(base_name);
+                    } else if let Err(e) =
+                        ProcessManager::wait_until_ready_shared(process_manager, watches).await
+                    {
+                        eprintln!("{}", e);
+                        not_ready.insert(base_name);
+                    }
+                }
+            }
+
+            Ok((spawned_count, failed_count))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_list_while_dependency_becomes_ready() {
+            let dir = tempfile::tempdir().unwrap();
+            let config_path = dir.path().join("adasa.toml");
+            std::fs::write(
+                &config_path,
+                r#"
+                [[processes]]
+                name = "db"
+                script = "/bin/sleep"
+                args = ["30"]
+
+                [[processes]]
+                name = "app"
+                script = "/bin/sleep"
+                args = ["30"]
+                depends_on = ["db"]
+                "#,
+            )
+            .unwrap();
+
+            let process_manager = Arc::new(RwLock::new(ProcessManager::new()));
+            let log_manager = Arc::new(RwLock::new(
+                LogManager::new(dir.path().join("logs")).await.unwrap(),
+            ));
+
+            let start = tokio::spawn(Daemon::handle_command(
+                Command::StartFromConfig(ConfigFileOptions {
+                    config_path,
+                    profile: None,
+                }),
+                Arc::clone(&process_manager),
+                Arc::clone(&log_manager),
+                SystemTime::now(),
+            ));
+
+            // db settles for a second before app starts, and the daemon still answers meanwhile
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            let listed = tokio::time::timeout(
+                Duration::from_millis(200),
+                Daemon::handle_command(
+                    Command::List,
+                    Arc::clone(&process_manager),
+                    Arc::clone(&log_manager),
+                    SystemTime::now(),
+                ),
+            )
+            .await
+            .expect("List waited for the dependency")
+            .unwrap();
+            let Ok(ResponseData::ProcessList(processes)) = listed.result else {
+                panic!("unexpected response: {:?}", listed.result);
+            };
+            let names: Vec<&str> = processes.iter().map(|p| p.name.as_str()).collect();
+            assert_eq!(names, ["db"]);
+
+            start.await.unwrap().unwrap();
+            let mut pm = process_manager.write().await;
+            assert!(pm.is_running("app"));
+            pm.stop_all().await.unwrap();
         }
     }
 }
 
+use adasa::error::AdasaError;
+use adasa::metrics::METRICS_ADDR_ENV;
+use adasa::process::history::STATS_RETENTION_ENV;
+use adasa::process::limits::cgroup::CGROUP_ROOT_ENV;
 use daemon_core::Daemon;
 use std::env;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Value of a daemon option, from `--<flag> <value>`, `--<flag>=<value>` or the environment variable `env_var`
+///
+/// `example` is shown when the flag is given without a value.
+fn option_value(flag: &str, env_var: &str, example: &str) -> adasa::error::Result<Option<String>> {
+    let args: Vec<String> = env::args().collect();
+    let prefix = format!("{}=", flag);
+    match args.iter().position(|arg| arg == flag) {
+        Some(idx) => args.get(idx + 1).cloned().map(Some).ok_or_else(|| {
+            AdasaError::ConfigError(format!("{} requires a value such as {}", flag, example))
+        }),
+        None => Ok(args
+            .iter()
+            .find_map(|arg| arg.strip_prefix(&prefix).map(str::to_string))
+            .or_else(|| env::var(env_var).ok().filter(|v| !v.is_empty()))),
+    }
+}
+
+/// Address of the metrics listener, from `--metrics <addr>` or `ADASA_METRICS_ADDR`
+fn metrics_addr() -> adasa::error::Result<Option<SocketAddr>> {
+    option_value("--metrics", METRICS_ADDR_ENV, "127.0.0.1:9615")?
+        .map(|v| {
+            v.parse().map_err(|e| {
+                AdasaError::ConfigError(format!("Invalid metrics address '{}': {}", v, e))
+            })
+        })
+        .transpose()
+}
+
+/// Directory for the cgroups of processes, from `--cgroup-root <path>` or `ADASA_CGROUP_ROOT`
+fn cgroup_root() -> adasa::error::Result<Option<PathBuf>> {
+    Ok(option_value("--cgroup-root", CGROUP_ROOT_ENV, "/sys/fs/cgroup/adasa")?.map(PathBuf::from))
+}
+
+/// How long stats samples are kept, from `--stats-retention <secs>` or `ADASA_STATS_RETENTION`
+fn stats_retention() -> adasa::error::Result<Option<Duration>> {
+    option_value("--stats-retention", STATS_RETENTION_ENV, "3600")?
+        .map(|v| match v.parse::<u64>() {
+            Ok(secs) if secs > 0 => Ok(Duration::from_secs(secs)),
+            _ => Err(AdasaError::ConfigError(format!(
+                "Invalid stats retention '{}': expected a number of seconds greater than 0",
+                v
+            ))),
+        })
+        .transpose()
+}
 
 #[tokio::main]
 async fn main() -> adasa::error::Result<()> {
@@ -858,6 +1802,9 @@ async fn main() -> adasa::error::Result<()> {
 
     // Check if we should daemonize
     let should_daemonize = env::args().any(|arg| arg == "--daemonize");
+    let metrics_addr = metrics_addr()?;
+    let cgroup_root = cgroup_root()?;
+    let stats_retention = stats_retention()?;
 
     if should_daemonize {
         // Daemonize the process
@@ -879,7 +1826,13 @@ async fn main() -> adasa::error::Result<()> {
     .ok();
 
     // Create and start the daemon
-    let daemon = Daemon::new().await?;
+    let daemon = Daemon::new()
+        .await?
+        .with_metrics_addr(metrics_addr)
+        .with_cgroup_root(cgroup_root)
+        .await
+        .with_stats_retention(stats_retention)
+        .await;
     let result = daemon.start().await;
 
     // Unregister daemon on exit
@@ -1,12 +1,16 @@
 // CLI module - User-facing command-line interface
 
 mod commands;
+mod monit;
 pub mod output;
 
+use crate::config::diff::ReloadPlan;
+use crate::config::{file, BatchSize};
 use crate::error::{AdasaError, Result};
 use crate::ipc::client::IpcClient;
 use crate::ipc::protocol::{
-    Command, DeleteOptions, LogOptions, ProcessId, RestartOptions, StartOptions, StopOptions,
+    Command, ConfigFileOptions, DeleteOptions, GetConfigOptions, LogOptions, ProcessId,
+    ReloadOptions, ResponseData, RestartOptions, ScaleOptions, StartOptions, StopOptions,
 };
 use clap::{Parser, Subcommand};
 use std::collections::HashMap;
@@ -44,7 +48,7 @@ enum Commands {
         #[arg(short, long)]
         cwd: Option<PathBuf>,
 
-        /// Environment variables (KEY=VALUE format)
+        /// Environment variables (KEY=VALUE format), or with --config the environment profile to apply
         #[arg(short, long)]
         env: Vec<String>,
 
@@ -71,6 +75,10 @@ enum Commands {
         /// Perform rolling restart for multi-instance processes
         #[arg(short, long)]
         rolling: bool,
+
+        /// Instances to restart at once during a rolling restart (e.g. 2 or 25%)
+        #[arg(short, long, requires = "rolling")]
+        batch: Option<BatchSize>,
     },
 
     /// List all managed processes
@@ -94,16 +102,64 @@ enum Commands {
         follow: bool,
     },
 
+    /// Stream daemon events as newline-delimited JSON until Ctrl-C
+    Events,
+
+    /// Show recent CPU, memory, thread and file descriptor usage of a process
+    Stats {
+        /// Process ID or name (shows all instances if name is provided)
+        target: String,
+    },
+
+    /// Open an interactive dashboard of processes and their logs
+    Monit,
+
+    /// Start or remove instances until a process has the given number of instances
+    Scale {
+        /// Base name of the process, e.g. web for web-0 and web-1
+        target: String,
+
+        /// Number of instances to run
+        instances: usize,
+    },
+
     /// Delete a stopped process
     Delete {
         /// Process ID or name to delete (deletes all instances if name is provided)
         target: String,
     },
 
-    /// Reload configuration file and start new processes
+    /// Reload a configuration file: start, update, restart, scale and remove processes to match it
     Reload {
         /// Path to configuration file (TOML or JSON)
         config: PathBuf,
+
+        /// Environment profile to apply, one of the env.<profile> tables of the file
+        #[arg(long)]
+        env: Option<String>,
+
+        /// Show the changes without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Save the current process list to a snapshot
+    Save {
+        /// Snapshot name (default: dump)
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+
+    /// Restore the processes saved in a snapshot
+    Resurrect {
+        /// Snapshot name (default: dump)
+        name: Option<String>,
+    },
+
+    /// Check config files and compare them with the running processes
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
     },
 
     /// Manage the daemon
@@ -113,6 +169,28 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Check a config file and report every problem, without contacting the daemon
+    Validate {
+        /// Path to configuration file (TOML or JSON)
+        file: PathBuf,
+
+        /// Environment profile to apply, one of the env.<profile> tables of the file
+        #[arg(long)]
+        env: Option<String>,
+    },
+    /// Show how the running processes differ from a config file, field by field
+    Diff {
+        /// Path to configuration file (TOML or JSON)
+        file: PathBuf,
+
+        /// Environment profile to apply, one of the env.<profile> tables of the file
+        #[arg(long)]
+        env: Option<String>,
+    },
+}
+
 #[derive(Subcommand)]
 enum DaemonCommands {
     /// Start the daemon
@@ -137,6 +215,22 @@ impl Cli {
             return self.handle_daemon_command(command);
         }
 
+        if let Commands::Config { command } = &self.command {
+            return self.handle_config_command(command);
+        }
+
+        if let Commands::Monit = &self.command {
+            return monit::run(&IpcClient::new());
+        }
+
+        // Following logs and events keeps the connection open until interrupted
+        if matches!(
+            &self.command,
+            Commands::Logs { follow: true, .. } | Commands::Events
+        ) {
+            return self.stream_responses();
+        }
+
         // Check if this is a long-running operation
         let is_long_operation = matches!(
             &self.command,
@@ -198,6 +292,88 @@ impl Cli {
         }
     }
 
+    /// Send a streaming command and print responses as they arrive until Ctrl-C
+    fn stream_responses(&self) -> Result<()> {
+        let command = self.build_command()?;
+        let client = IpcClient::new();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| AdasaError::Other(format!("Failed to start runtime: {}", e)))?;
+
+        let mut error = None;
+        let result = runtime.block_on(async {
+            let stream = client.stream_command(command, |response| match response.result {
+                Ok(ResponseData::Logs(lines)) => output::print_log_lines(&lines),
+                // Keep the output to one event per line
+                Ok(ResponseData::Subscribed) => {}
+                Ok(data) => output::print_success(&data),
+                Err(error_msg) => error = Some(error_msg),
+            });
+
+            tokio::select! {
+                result = stream => result,
+                // Dropping the stream closes the connection, which ends it on the daemon side
+                _ = tokio::signal::ctrl_c() => Ok(()),
+            }
+        });
+
+        if let Some(error_msg) = error {
+            output::print_error(&error_msg);
+            return Err(AdasaError::Other(error_msg));
+        }
+
+        result.inspect_err(|e| output::print_error(&e.to_string()))
+    }
+
+    /// Handle config file commands
+    ///
+    /// Both load the file on the client, so problems are found before the daemon sees it.
+    fn handle_config_command(&self, command: &ConfigCommands) -> Result<()> {
+        let (path, profile) = match command {
+            ConfigCommands::Validate { file, env } | ConfigCommands::Diff { file, env } => {
+                (file, env.as_deref())
+            }
+        };
+
+        let configs = file::check_file(path, profile).map_err(|issues| {
+            output::print_config_issues(path, &issues);
+            AdasaError::InvalidConfig(format!(
+                "{} problem(s) found in {}",
+                issues.len(),
+                path.display()
+            ))
+        })?;
+
+        if let ConfigCommands::Validate { .. } = command {
+            output::print_config_valid(path, &configs);
+            return Ok(());
+        }
+
+        let command = Command::GetConfig(GetConfigOptions {
+            names: configs.iter().map(|c| c.name.clone()).collect(),
+            config_path: std::fs::canonicalize(path)?,
+        });
+        let response = IpcClient::new()
+            .send_command(command)
+            .inspect_err(|e| output::print_error(&e.to_string()))?;
+
+        match response.result {
+            Ok(ResponseData::Config(running)) => {
+                output::print_config_diff(path, &ReloadPlan::compute(&configs, &running));
+                Ok(())
+            }
+            Ok(_) => Err(AdasaError::ProtocolError(
+                "Unexpected response to config request".to_string(),
+            )),
+            Err(error_msg) => {
+                output::print_error(&error_msg);
+                Err(AdasaError::Other(error_msg))
+            }
+        }
+    }
+
     /// Handle daemon management commands
     fn handle_daemon_command(&self, command: &DaemonCommands) -> Result<()> {
         use crate::daemon::DaemonManager;
@@ -306,10 +482,21 @@ impl Cli {
             } => {
                 // Check if config file is provided
                 if let Some(config_path) = config {
-                    // Start from config file
-                    return Ok(Command::StartFromConfig {
+                    // Start from config file, where --env selects an environment profile
+                    let profile = match env.as_slice() {
+                        [] => None,
+                        [profile] if !profile.contains('=') => Some(profile.clone()),
+                        _ => {
+                            return Err(AdasaError::ConfigError(
+                                "With --config, --env takes a profile, e.g. --env production"
+                                    .to_string(),
+                            ))
+                        }
+                    };
+                    return Ok(Command::StartFromConfig(ConfigFileOptions {
                         config_path: config_path.clone(),
-                    });
+                        profile,
+                    }));
                 }
 
                 // Validate that script is provided if not using config
@@ -337,9 +524,10 @@ impl Cli {
                 force: *force,
             })),
 
-            Commands::Restart { id, rolling } => Ok(Command::Restart(RestartOptions {
+            Commands::Restart { id, rolling, batch } => Ok(Command::Restart(RestartOptions {
                 target: id.clone(),
                 rolling: *rolling,
+                batch: *batch,
             })),
 
             Commands::List { detailed } => {
@@ -355,13 +543,44 @@ impl Cli {
                 follow: *follow,
             })),
 
+            Commands::Events => Ok(Command::Subscribe),
+
+            Commands::Stats { target } => Ok(Command::Stats {
+                target: target.clone(),
+            }),
+
+            Commands::Scale { target, instances } => Ok(Command::Scale(ScaleOptions {
+                target: target.clone(),
+                instances: *instances,
+            })),
+
+            Commands::Monit => {
+                // The dashboard sends its own commands
+                unreachable!("Monit is handled by monit::run")
+            }
+
             Commands::Delete { target } => Ok(Command::Delete(DeleteOptions {
                 target: target.clone(),
             })),
 
-            Commands::Reload { config } => Ok(Command::ReloadConfig {
+            Commands::Reload {
+                config,
+                env,
+                dry_run,
+            } => Ok(Command::ReloadConfig(ReloadOptions {
                 config_path: config.clone(),
-            }),
+                profile: env.clone(),
+                dry_run: *dry_run,
+            })),
+
+            Commands::Save { name } => Ok(Command::Save { name: name.clone() }),
+
+            Commands::Resurrect { name } => Ok(Command::Resurrect { name: name.clone() }),
+
+            Commands::Config { .. } => {
+                // Config commands are handled separately
+                unreachable!("Config commands should be handled by handle_config_command")
+            }
 
             Commands::Daemon { .. } => {
                 // Daemon commands are handled separately, not via IPC
@@ -0,0 +1,460 @@
+// Interactive dashboard of the managed processes, in the spirit of `pm2 monit`
+
+use crate::cli::output::{format_duration, format_memory};
+use crate::error::{AdasaError, Result};
+use crate::ipc::client::IpcClient;
+use crate::ipc::protocol::{
+    Command, DeleteOptions, LogOptions, ProcessId, ProcessInfo, ProcessState, ResponseData,
+    RestartOptions, ScaleOptions, StopOptions,
+};
+use crate::process::split_instance;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Gauge, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::time::{Duration, Instant};
+
+/// How often the process list and the logs of the selection are fetched
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Log lines fetched for the selected process
+const LOG_LINES: usize = 200;
+
+const HELP: &str = "↑/↓ select  r restart  s stop  d delete  +/- scale  q quit";
+
+/// Run the dashboard until the user quits
+///
+/// The daemon is queried once before the terminal is taken over, so an
+/// unreachable daemon is reported like for any other command.
+pub fn run(client: &IpcClient) -> Result<()> {
+    let mut app = App::new();
+    app.processes = fetch_processes(client)?;
+    app.refresh_logs(client);
+
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal, client);
+    ratatui::restore();
+    result
+}
+
+/// A command chosen with a key binding
+#[derive(Debug, Clone)]
+enum Action {
+    Quit,
+    Send(Command),
+}
+
+/// State of the dashboard
+struct App {
+    processes: Vec<ProcessInfo>,
+    table: TableState,
+    logs: Vec<String>,
+    /// Outcome of the last command, or the question of a pending confirmation
+    status: Option<String>,
+    /// Process that is deleted once the user confirms
+    pending_delete: Option<ProcessId>,
+    total_memory: u64,
+}
+
+impl App {
+    fn new() -> Self {
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+
+        Self {
+            processes: Vec::new(),
+            table: TableState::default().with_selected(Some(0)),
+            logs: Vec::new(),
+            status: None,
+            pending_delete: None,
+            total_memory: system.total_memory(),
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal, client: &IpcClient) -> Result<()> {
+        let mut last_refresh = Instant::now();
+
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let timeout = REFRESH_INTERVAL.saturating_sub(last_refresh.elapsed());
+            if event::poll(timeout)? {
+                if let Event::Key(key) = event::read()? {
+                    match self.handle_key(key) {
+                        Some(Action::Quit) => return Ok(()),
+                        Some(Action::Send(command)) => {
+                            self.status = Some(send(client, command));
+                            self.refresh(client);
+                            last_refresh = Instant::now();
+                        }
+                        None => {}
+                    }
+                }
+            }
+
+            if last_refresh.elapsed() >= REFRESH_INTERVAL {
+                self.refresh(client);
+                last_refresh = Instant::now();
+            }
+        }
+    }
+
+    /// Fetch the process list and the logs of the selected process
+    fn refresh(&mut self, client: &IpcClient) {
+        let selected_id = self.selected().map(|p| p.id);
+
+        match fetch_processes(client) {
+            Ok(processes) => self.processes = processes,
+            Err(e) => self.status = Some(e.to_string()),
+        }
+
+        // Keep the same process selected when others come and go
+        let index = selected_id
+            .and_then(|id| self.processes.iter().position(|p| p.id == id))
+            .unwrap_or_else(|| {
+                let previous = self.table.selected().unwrap_or(0);
+                previous.min(self.processes.len().saturating_sub(1))
+            });
+        self.table.select(Some(index));
+
+        self.refresh_logs(client);
+    }
+
+    fn refresh_logs(&mut self, client: &IpcClient) {
+        let Some(id) = self.selected().map(|p| p.id) else {
+            self.logs.clear();
+            return;
+        };
+
+        let command = Command::Logs(LogOptions {
+            id,
+            lines: Some(LOG_LINES),
+            follow: false,
+        });
+        self.logs = match client.send_command(command).map(|r| r.result) {
+            Ok(Ok(ResponseData::Logs(lines))) => lines,
+            Ok(Ok(_)) => Vec::new(),
+            Ok(Err(error)) => vec![error],
+            Err(e) => vec![e.to_string()],
+        };
+    }
+
+    fn selected(&self) -> Option<&ProcessInfo> {
+        self.processes.get(self.table.selected()?)
+    }
+
+    /// Instances of the selected process: its base name and how many instances it has
+    fn selected_group(&self) -> Option<(String, usize)> {
+        let (base, _) = split_instance(&self.selected()?.name);
+        let count = self
+            .processes
+            .iter()
+            .filter(|p| split_instance(&p.name).0 == base)
+            .count();
+        Some((base.to_string(), count))
+    }
+
+    /// Update the selection or pick a command for a key press
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Some(Action::Quit);
+        }
+
+        if let Some(id) = self.pending_delete.take() {
+            if key.code == KeyCode::Char('y') {
+                return Some(Action::Send(Command::Delete(DeleteOptions {
+                    target: id.to_string(),
+                })));
+            }
+            self.status = Some("Delete cancelled".to_string());
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Some(Action::Quit),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.table.select_previous();
+                None
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if self.table.selected().unwrap_or(0) + 1 < self.processes.len() {
+                    self.table.select_next();
+                }
+                None
+            }
+            KeyCode::Char('r') => Some(Action::Send(Command::Restart(RestartOptions {
+                target: self.selected()?.id.to_string(),
+                rolling: false,
+                batch: None,
+            }))),
+            KeyCode::Char('s') => Some(Action::Send(Command::Stop(StopOptions {
+                id: self.selected()?.id,
+                force: false,
+            }))),
+            KeyCode::Char('d') => {
+                let (id, name) = self.selected().map(|p| (p.id, p.name.clone()))?;
+                self.status = Some(format!("Delete {}? (y/n)", name));
+                self.pending_delete = Some(id);
+                None
+            }
+            KeyCode::Char('+') => {
+                let (target, instances) = self.selected_group()?;
+                Some(Action::Send(Command::Scale(ScaleOptions {
+                    target,
+                    instances: instances + 1,
+                })))
+            }
+            KeyCode::Char('-') => {
+                let (target, instances) = self.selected_group()?;
+                if instances <= 1 {
+                    self.status = Some(format!(
+                        "{} has a single instance, press d to delete it",
+                        target
+                    ));
+                    return None;
+                }
+                Some(Action::Send(Command::Scale(ScaleOptions {
+                    target,
+                    instances: instances - 1,
+                })))
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [list, gauges, logs, footer] = Layout::vertical([
+            Constraint::Percentage(40),
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.draw_list(frame, list);
+        self.draw_gauges(frame, gauges);
+        self.draw_logs(frame, logs);
+
+        let footer_text = match &self.status {
+            Some(status) => Line::from(vec![
+                Span::styled(status.as_str(), Style::new().add_modifier(Modifier::BOLD)),
+                Span::raw("  "),
+                Span::styled(HELP, Style::new().fg(Color::DarkGray)),
+            ]),
+            None => Line::styled(HELP, Style::new().fg(Color::DarkGray)),
+        };
+        frame.render_widget(Paragraph::new(footer_text), footer);
+    }
+
+    fn draw_list(&mut self, frame: &mut Frame, area: Rect) {
+        let header = Row::new([
+            "ID", "Name", "State", "PID", "CPU", "Memory", "Restarts", "Uptime",
+        ])
+        .style(Style::new().add_modifier(Modifier::BOLD));
+
+        let rows = self.processes.iter().map(|p| {
+            Row::new([
+                Cell::from(p.id.to_string()),
+                Cell::from(p.name.clone()),
+                Cell::from(p.state.to_string()).style(Style::new().fg(state_color(&p.state))),
+                Cell::from(
+                    p.stats
+                        .pid
+                        .map(|pid| pid.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                ),
+                Cell::from(format!("{:.1}%", p.stats.tree_cpu_usage)),
+                Cell::from(format_memory(p.stats.tree_memory_usage)),
+                Cell::from(p.stats.restarts.to_string()),
+                Cell::from(format_duration(&p.stats.uptime)),
+            ])
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(5),
+                Constraint::Fill(1),
+                Constraint::Length(11),
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(10),
+                Constraint::Length(9),
+                Constraint::Length(8),
+            ],
+        )
+        .header(header)
+        .block(Block::bordered().title(format!(" Processes ({}) ", self.processes.len())))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(table, area, &mut self.table);
+    }
+
+    fn draw_gauges(&self, frame: &mut Frame, area: Rect) {
+        let [cpu, memory] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(area);
+        let stats = self.selected().map(|p| &p.stats);
+
+        let cpu_usage = stats.map_or(0.0, |s| s.tree_cpu_usage as f64);
+        let cpu_gauge = Gauge::default()
+            .block(Block::bordered().title(" CPU "))
+            .gauge_style(Style::new().fg(Color::Green))
+            .ratio((cpu_usage / 100.0).clamp(0.0, 1.0))
+            .label(format!("{:.1}%", cpu_usage));
+        frame.render_widget(cpu_gauge, cpu);
+
+        let memory_usage = stats.map_or(0, |s| s.tree_memory_usage);
+        let memory_ratio = if self.total_memory > 0 {
+            memory_usage as f64 / self.total_memory as f64
+        } else {
+            0.0
+        };
+        let memory_gauge = Gauge::default()
+            .block(Block::bordered().title(" Memory "))
+            .gauge_style(Style::new().fg(Color::Cyan))
+            .ratio(memory_ratio.clamp(0.0, 1.0))
+            .label(format!(
+                "{} of {}",
+                format_memory(memory_usage),
+                format_memory(self.total_memory)
+            ));
+        frame.render_widget(memory_gauge, memory);
+    }
+
+    fn draw_logs(&self, frame: &mut Frame, area: Rect) {
+        let title = match self.selected() {
+            Some(process) => format!(" Logs: {} ", process.name),
+            None => " Logs ".to_string(),
+        };
+
+        // Show the newest lines that fit inside the border
+        let visible = area.height.saturating_sub(2) as usize;
+        let lines: Vec<Line> = self.logs[self.logs.len().saturating_sub(visible)..]
+            .iter()
+            .map(|line| Line::raw(line.trim_end()))
+            .collect();
+
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(title)),
+            area,
+        );
+    }
+}
+
+fn fetch_processes(client: &IpcClient) -> Result<Vec<ProcessInfo>> {
+    match client.send_command(Command::List)?.result {
+        Ok(ResponseData::ProcessList(mut processes)) => {
+            processes.sort_by_key(|p| p.id.as_u64());
+            Ok(processes)
+        }
+        Ok(_) => Err(AdasaError::ProtocolError(
+            "Unexpected response to list".to_string(),
+        )),
+        Err(error) => Err(AdasaError::Other(error)),
+    }
+}
+
+/// Send a command chosen in the dashboard and describe the outcome
+fn send(client: &IpcClient, command: Command) -> String {
+    match client.send_command(command).map(|r| r.result) {
+        Ok(Ok(ResponseData::Stopped { id })) => format!("Stopped process {}", id),
+        Ok(Ok(ResponseData::Restarted { id })) => format!("Restarted process {}", id),
+        Ok(Ok(ResponseData::Deleted { id })) => format!("Deleted process {}", id),
+        Ok(Ok(ResponseData::Success(message))) => message,
+        Ok(Ok(_)) => "Done".to_string(),
+        Ok(Err(error)) => format!("Error: {}", error),
+        Err(e) => format!("Error: {}", e),
+    }
+}
+
+/// Colors matching the state colors of `adasa list`
+fn state_color(state: &ProcessState) -> Color {
+    match state {
+        ProcessState::Running => Color::Green,
+        ProcessState::Starting | ProcessState::Restarting | ProcessState::Stopping => Color::Yellow,
+        ProcessState::Stopped => Color::DarkGray,
+        ProcessState::Errored | ProcessState::Unhealthy | ProcessState::CrashLoop => Color::Red,
+        ProcessState::Scheduled => Color::Cyan,
+        ProcessState::Completed => Color::Blue,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::protocol::ProcessStats;
+
+    fn app(names: &[&str]) -> App {
+        let mut app = App::new();
+        app.processes = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| ProcessInfo {
+                id: ProcessId::new(i as u64 + 1),
+                name: name.to_string(),
+                state: ProcessState::Running,
+                stats: ProcessStats::default(),
+            })
+            .collect();
+        app
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Option<Action> {
+        app.handle_key(KeyEvent::from(code))
+    }
+
+    #[test]
+    fn test_selection_stays_in_list() {
+        let mut app = app(&["api", "worker"]);
+
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.table.selected(), Some(0));
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected().unwrap().name, "worker");
+    }
+
+    #[test]
+    fn test_scale_selected_group() {
+        let mut app = app(&["web-0", "web-1", "web-server"]);
+
+        let Some(Action::Send(Command::Scale(options))) = press(&mut app, KeyCode::Char('+'))
+        else {
+            panic!("expected a scale command");
+        };
+        assert_eq!((options.target.as_str(), options.instances), ("web", 3));
+
+        let Some(Action::Send(Command::Scale(options))) = press(&mut app, KeyCode::Char('-'))
+        else {
+            panic!("expected a scale command");
+        };
+        assert_eq!(options.instances, 1);
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert!(press(&mut app, KeyCode::Char('-')).is_none());
+    }
+
+    #[test]
+    fn test_delete_needs_confirmation() {
+        let mut app = app(&["api", "worker"]);
+        press(&mut app, KeyCode::Down);
+
+        assert!(press(&mut app, KeyCode::Char('d')).is_none());
+        assert!(press(&mut app, KeyCode::Char('n')).is_none());
+        assert!(app.pending_delete.is_none());
+
+        press(&mut app, KeyCode::Char('d'));
+        let Some(Action::Send(Command::Delete(options))) = press(&mut app, KeyCode::Char('y'))
+        else {
+            panic!("expected a delete command");
+        };
+        assert_eq!(options.target, "2");
+    }
+}
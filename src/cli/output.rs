@@ -1,10 +1,14 @@
 // Output formatting and display for CLI
 
-use crate::ipc::protocol::{ProcessInfo, ProcessState, ResponseData};
+use crate::config::diff::{FieldChange, PlanAction, PlanEntry, ReloadPlan};
+use crate::config::file::ConfigIssue;
+use crate::config::ProcessConfig;
+use crate::ipc::protocol::{Event, ProcessHistory, ProcessInfo, ProcessState, ResponseData};
 use chrono::{DateTime, Local};
 use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
-use std::time::Duration;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
 use tabled::{
     settings::{object::Rows, Alignment, Modify, Style},
     Table, Tabled,
@@ -70,6 +74,44 @@ pub fn print_success(data: &ResponseData) {
         ResponseData::Success(message) => {
             println!("{} {}", "✓".green().bold(), message);
         }
+
+        ResponseData::Subscribed => {
+            println!("{}", "✓ Subscribed to daemon events".green().bold());
+        }
+
+        ResponseData::Event(event) => {
+            print_event(event);
+        }
+
+        ResponseData::Stats(histories) => {
+            print_stats(histories);
+        }
+
+        ResponseData::Reload {
+            plan,
+            dry_run,
+            errors,
+        } => {
+            print_reload(plan, *dry_run, errors);
+        }
+
+        ResponseData::Config(processes) => {
+            for process in processes {
+                println!(
+                    "  {} {} instance(s)",
+                    process.config.name.bold(),
+                    process.instances
+                );
+            }
+        }
+    }
+}
+
+/// Print an event as a single line of JSON, so the output can be piped into other tools
+pub fn print_event(event: &Event) {
+    match serde_json::to_string(event) {
+        Ok(json) => println!("{}", json),
+        Err(e) => print_error(&format!("Failed to serialize event: {}", e)),
     }
 }
 
@@ -108,6 +150,10 @@ fn print_process_table(processes: &[ProcessInfo]) {
         uptime: String,
         #[tabled(rename = "Restarts")]
         restarts: String,
+        #[tabled(rename = "Last Run")]
+        last_run: String,
+        #[tabled(rename = "Next Run")]
+        next_run: String,
     }
 
     let rows: Vec<ProcessRow> = processes
@@ -121,10 +167,16 @@ fn print_process_table(processes: &[ProcessInfo]) {
                 .pid
                 .map(|pid| pid.to_string())
                 .unwrap_or_else(|| "-".to_string()),
-            cpu: format!("{:.1}%", p.stats.cpu_usage),
-            memory: format_memory(p.stats.memory_usage),
+            cpu: format!("{:.1}%", p.stats.tree_cpu_usage),
+            memory: format_memory(p.stats.tree_memory_usage),
             uptime: format_duration(&p.stats.uptime),
             restarts: p.stats.restarts.to_string(),
+            last_run: format_last_run(p),
+            next_run: p
+                .stats
+                .next_run
+                .map(format_relative)
+                .unwrap_or_else(|| "-".to_string()),
         })
         .collect();
 
@@ -159,13 +211,15 @@ pub fn print_detailed_status(process: &ProcessInfo) {
     }
 
     println!(
-        "  {:<15} {:.1}%",
+        "  {:<15} {:.1}% (main process: {:.1}%)",
         "CPU Usage:".bold(),
+        process.stats.tree_cpu_usage,
         process.stats.cpu_usage
     );
     println!(
-        "  {:<15} {}",
+        "  {:<15} {} (main process: {})",
         "Memory:".bold(),
+        format_memory(process.stats.tree_memory_usage),
         format_memory(process.stats.memory_usage)
     );
     println!(
@@ -184,9 +238,310 @@ pub fn print_detailed_status(process: &ProcessInfo) {
         );
     }
 
+    if let Some(last_run) = process.stats.last_run {
+        let datetime: DateTime<Local> = last_run.into();
+        println!(
+            "  {:<15} {}",
+            "Last Run:".bold(),
+            datetime.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+
+    if let Some(ref duration) = process.stats.last_run_duration {
+        println!(
+            "  {:<15} {}",
+            "Run Duration:".bold(),
+            format_duration(duration)
+        );
+    }
+
+    if let Some(exit) = format_exit(process) {
+        println!("  {:<15} {}", "Last Exit:".bold(), exit);
+    }
+
+    if let Some(next_run) = process.stats.next_run {
+        let datetime: DateTime<Local> = next_run.into();
+        println!(
+            "  {:<15} {}",
+            "Next Run:".bold(),
+            datetime.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+
     println!();
 }
 
+/// Print the changes of a config reload, one line per process that changes
+fn print_reload(plan: &ReloadPlan, dry_run: bool, errors: &[String]) {
+    let changes: Vec<&PlanEntry> = plan.changes().collect();
+    let unchanged = plan.entries.len() - changes.len();
+
+    if changes.is_empty() {
+        println!(
+            "{}",
+            "✓ Config is up to date, nothing to change".green().bold()
+        );
+        return;
+    }
+
+    println!();
+    for entry in &changes {
+        println!("  {}", format_plan_entry(entry));
+    }
+    if unchanged > 0 {
+        println!("  {}", format!("{} unchanged", unchanged).dimmed());
+    }
+    println!();
+
+    if dry_run {
+        print_info(&format!(
+            "Dry run: {} change(s) planned, nothing was applied",
+            changes.len()
+        ));
+    } else if errors.is_empty() {
+        print_success_msg(&format!(
+            "Config reloaded: {} change(s) applied",
+            changes.len()
+        ));
+    } else {
+        for error in errors {
+            print_error(error);
+        }
+    }
+}
+
+/// Print the processes of a valid config file
+pub fn print_config_valid(path: &Path, configs: &[ProcessConfig]) {
+    print_success_msg(&format!(
+        "{} is valid: {} process(es)",
+        path.display(),
+        configs.len()
+    ));
+    for config in configs {
+        println!("  {} {} instance(s)", config.name.bold(), config.instances);
+    }
+}
+
+/// Print every problem found in a config file, one per line
+pub fn print_config_issues(path: &Path, issues: &[ConfigIssue]) {
+    for issue in issues {
+        eprintln!("{} {}", "✗".red().bold(), format_config_issue(path, issue));
+    }
+}
+
+/// Format a config problem with its location, e.g. "app.toml:12: web: instances must be at least 1"
+///
+/// Problems in an included file are reported against that file rather than `path`.
+fn format_config_issue(path: &Path, issue: &ConfigIssue) -> String {
+    let mut location = issue.path.as_deref().unwrap_or(path).display().to_string();
+    if let Some(line) = issue.line {
+        location.push_str(&format!(":{}", line));
+    }
+
+    match &issue.process {
+        Some(process) => format!("{}: {}: {}", location, process.bold(), issue.error),
+        None => format!("{}: {}", location, issue.error),
+    }
+}
+
+/// Print how the running processes differ from a config file, field by field
+pub fn print_config_diff(path: &Path, plan: &ReloadPlan) {
+    let changes: Vec<&PlanEntry> = plan.changes().collect();
+
+    if changes.is_empty() {
+        print_success_msg(&format!("Running processes match {}", path.display()));
+        return;
+    }
+
+    println!();
+    for entry in &changes {
+        println!("  {}", format_plan_entry(entry));
+        for change in &entry.changes {
+            println!("      {}", format_field_change(change));
+        }
+    }
+    println!();
+
+    print_info(&format!(
+        "{} process(es) differ from {}",
+        changes.len(),
+        path.display()
+    ));
+}
+
+/// Format a changed setting, e.g. "max_memory: 512MiB → unset"
+fn format_field_change(change: &FieldChange) -> String {
+    let value = |value: &serde_json::Value| match value {
+        serde_json::Value::Null => "unset".to_string(),
+        serde_json::Value::String(text) => text.clone(),
+        value => value.to_string(),
+    };
+
+    format!(
+        "{}: {} → {}",
+        change.field,
+        value(&change.old).red(),
+        value(&change.new).green()
+    )
+}
+
+/// Format one process of a reload plan, e.g. "~ web restart (env, script); scale 2 → 3"
+fn format_plan_entry(entry: &PlanEntry) -> String {
+    let name = entry.name.bold();
+    let fields = entry
+        .changes
+        .iter()
+        .map(|c| c.field.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut parts = Vec::new();
+    match entry.action {
+        PlanAction::Add => {
+            return format!(
+                "{} {} start {} instance(s)",
+                "+".green().bold(),
+                name,
+                entry.to_instances
+            )
+        }
+        PlanAction::Remove => {
+            return format!(
+                "{} {} remove {} instance(s)",
+                "-".red().bold(),
+                name,
+                entry.from_instances
+            )
+        }
+        PlanAction::Restart => parts.push(format!("restart ({})", fields)),
+        PlanAction::Update => parts.push(format!("update ({})", fields)),
+        PlanAction::Unchanged => {}
+    }
+    if entry.from_instances != entry.to_instances {
+        parts.push(format!(
+            "scale {} → {}",
+            entry.from_instances, entry.to_instances
+        ));
+    }
+
+    format!("{} {} {}", "~".yellow().bold(), name, parts.join("; "))
+}
+
+/// Width of the sparklines printed by `adasa stats`
+const SPARKLINE_WIDTH: usize = 40;
+
+/// Print the recorded resource usage of each process as sparklines with summary figures
+fn print_stats(histories: &[ProcessHistory]) {
+    for history in histories {
+        println!(
+            "\n{} {}",
+            history.name.cyan().bold(),
+            format!("(ID {})", history.id).dimmed()
+        );
+
+        let (Some(first), Some(last)) = (history.samples.first(), history.samples.last()) else {
+            println!("  {}", "No samples recorded yet".yellow());
+            continue;
+        };
+        let span = last.at.duration_since(first.at).unwrap_or_default();
+        println!(
+            "  {} samples over {}, last {}",
+            history.samples.len(),
+            format_duration(&span),
+            format_relative(last.at)
+        );
+        println!();
+
+        let cpu: Vec<f64> = history.samples.iter().map(|s| s.cpu_usage as f64).collect();
+        let memory: Vec<f64> = history
+            .samples
+            .iter()
+            .map(|s| s.memory_usage as f64)
+            .collect();
+        let threads: Vec<f64> = history.samples.iter().map(|s| s.threads as f64).collect();
+        let fds: Vec<f64> = history.samples.iter().map(|s| s.fds as f64).collect();
+
+        print_series("CPU", &cpu, |v| format!("{:.1}%", v));
+        print_series("Memory", &memory, |v| format_memory(v as u64));
+        print_series("Threads", &threads, |v| format!("{:.0}", v));
+        print_series("FDs", &fds, |v| format!("{:.0}", v));
+    }
+    println!();
+}
+
+fn print_series(label: &str, values: &[f64], format: impl Fn(f64) -> String) {
+    let Some(summary) = Summary::of(values) else {
+        return;
+    };
+    println!(
+        "  {:<8} {:<width$}  min {}  avg {}  max {}  p95 {}",
+        label.bold(),
+        sparkline(values, SPARKLINE_WIDTH).green(),
+        format(summary.min),
+        format(summary.avg),
+        format(summary.max),
+        format(summary.p95),
+        width = SPARKLINE_WIDTH
+    );
+}
+
+/// Minimum, average, maximum and 95th percentile of a series
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Summary {
+    min: f64,
+    avg: f64,
+    max: f64,
+    p95: f64,
+}
+
+impl Summary {
+    fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        // Nearest-rank percentile
+        let rank = (sorted.len() as f64 * 0.95).ceil() as usize;
+        Some(Self {
+            min: sorted[0],
+            avg: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            max: sorted[sorted.len() - 1],
+            p95: sorted[rank.saturating_sub(1)],
+        })
+    }
+}
+
+/// Render a series as a line of block characters, averaging into at most `width` buckets
+fn sparkline(values: &[f64], width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let buckets: Vec<f64> = if values.len() <= width {
+        values.to_vec()
+    } else {
+        (0..width)
+            .map(|i| {
+                let bucket = &values[i * values.len() / width..(i + 1) * values.len() / width];
+                bucket.iter().sum::<f64>() / bucket.len() as f64
+            })
+            .collect()
+    };
+
+    let min = buckets.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = buckets.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    buckets
+        .iter()
+        .map(|v| {
+            if max > min {
+                BARS[((v - min) / (max - min) * (BARS.len() - 1) as f64).round() as usize]
+            } else {
+                BARS[0]
+            }
+        })
+        .collect()
+}
+
 /// Print logs with timestamps
 fn print_logs(lines: &[String]) {
     if lines.is_empty() {
@@ -197,6 +552,13 @@ fn print_logs(lines: &[String]) {
     println!("\n{}", "Logs".bold().underline());
     println!();
 
+    print_log_lines(lines);
+
+    println!();
+}
+
+/// Print log lines as they arrive while following logs
+pub fn print_log_lines(lines: &[String]) {
     for line in lines {
         // Check if line already has a timestamp
         if line.starts_with('[') {
@@ -212,8 +574,6 @@ fn print_logs(lines: &[String]) {
             );
         }
     }
-
-    println!();
 }
 
 /// Format a process state with color coding
@@ -225,11 +585,53 @@ fn format_state_colored(state: &ProcessState) -> String {
         ProcessState::Stopping => state.to_string().yellow().to_string(),
         ProcessState::Stopped => state.to_string().bright_black().to_string(),
         ProcessState::Errored => state.to_string().red().bold().to_string(),
+        ProcessState::Unhealthy => state.to_string().red().to_string(),
+        ProcessState::Scheduled => state.to_string().cyan().to_string(),
+        ProcessState::Completed => state.to_string().blue().to_string(),
+        ProcessState::CrashLoop => state.to_string().red().bold().to_string(),
+    }
+}
+
+/// Format when a job last ran, with the exit code once the run finished
+fn format_last_run(process: &ProcessInfo) -> String {
+    let Some(last_run) = process.stats.last_run else {
+        return "-".to_string();
+    };
+
+    let when = format_relative(last_run);
+    if process.state == ProcessState::Running {
+        return when;
+    }
+
+    match format_exit(process) {
+        Some(exit) => format!("{} ({})", when, exit),
+        None => when,
+    }
+}
+
+/// Format how the process last exited, e.g. "exit 1" or "SIGKILL"
+fn format_exit(process: &ProcessInfo) -> Option<String> {
+    match (process.stats.last_exit_code, process.stats.last_exit_signal) {
+        (Some(code), _) => Some(format!("exit {}", code)),
+        (None, Some(signal)) => Some(
+            nix::sys::signal::Signal::try_from(signal)
+                .map(|s| s.to_string())
+                .unwrap_or_else(|_| format!("signal {}", signal)),
+        ),
+        (None, None) => None,
+    }
+}
+
+/// Format a point in time relative to now, e.g. "5m ago" or "in 30s"
+fn format_relative(time: SystemTime) -> String {
+    match time.duration_since(SystemTime::now()) {
+        Ok(until) => format!("in {}", format_duration(&until)),
+        Err(e) => format!("{} ago", format_duration(&e.duration())),
     }
 }
 
 /// Format a duration in human-readable format
-fn format_duration(duration: &Duration) -> String {
+pub(crate) fn format_duration(duration: &Duration) -> String {
     let secs = duration.as_secs();
 
     if secs < 60 {
@@ -262,7 +664,7 @@ fn format_duration(duration: &Duration) -> String {
 }
 
 /// Format memory usage in human-readable format
-fn format_memory(bytes: u64) -> String {
+pub(crate) fn format_memory(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
     const GB: u64 = MB * 1024;
@@ -331,6 +733,94 @@ mod tests {
         assert_eq!(format_memory(3 * 1024 * 1024 * 1024), "3.00GB");
     }
 
+    #[test]
+    fn test_format_relative() {
+        let now = SystemTime::now();
+        assert_eq!(format_relative(now - Duration::from_secs(90)), "1m 30s ago");
+        assert_eq!(format_relative(now + Duration::from_secs(3700)), "in 1h 1m");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[0.0, 1.0, 2.0, 7.0], 40), "▁▂▃█");
+        assert_eq!(sparkline(&[5.0, 5.0], 40), "▁▁");
+        assert_eq!(sparkline(&[0.0, 0.0, 7.0, 7.0], 2), "▁█");
+        assert_eq!(sparkline(&[], 40), "");
+    }
+
+    #[test]
+    fn test_summary() {
+        let values: Vec<f64> = (1..=100).map(f64::from).collect();
+        let summary = Summary::of(&values).unwrap();
+        assert_eq!(summary.min, 1.0);
+        assert_eq!(summary.avg, 50.5);
+        assert_eq!(summary.max, 100.0);
+        assert_eq!(summary.p95, 95.0);
+        assert_eq!(Summary::of(&[]), None);
+    }
+
+    #[test]
+    fn test_format_plan_entry() {
+        colored::control::set_override(false);
+        let entry = |action, from_instances, to_instances, fields: &[&str]| PlanEntry {
+            name: "web".to_string(),
+            action,
+            from_instances,
+            to_instances,
+            changes: fields
+                .iter()
+                .map(|field| crate::config::diff::FieldChange {
+                    field: field.to_string(),
+                    old: serde_json::Value::Null,
+                    new: serde_json::Value::Null,
+                })
+                .collect(),
+        };
+
+        assert_eq!(
+            format_plan_entry(&entry(PlanAction::Restart, 2, 3, &["env", "script"])),
+            "~ web restart (env, script); scale 2 → 3"
+        );
+        assert_eq!(
+            format_plan_entry(&entry(PlanAction::Unchanged, 3, 1, &[])),
+            "~ web scale 3 → 1"
+        );
+        assert_eq!(
+            format_plan_entry(&entry(PlanAction::Add, 0, 2, &[])),
+            "+ web start 2 instance(s)"
+        );
+    }
+
+    #[test]
+    fn test_format_config_issue_and_change() {
+        colored::control::set_override(false);
+        let path = Path::new("app.toml");
+
+        let mut issue = ConfigIssue {
+            path: None,
+            line: Some(12),
+            process: Some("web".to_string()),
+            error: crate::error::AdasaError::ConfigValidationError(
+                "instances must be at least 1".to_string(),
+            ),
+        };
+        assert_eq!(
+            format_config_issue(path, &issue),
+            "app.toml:12: web: Configuration validation failed: instances must be at least 1"
+        );
+
+        issue.path = Some("services/web.yaml".into());
+        issue.process = None;
+        assert!(format_config_issue(path, &issue).starts_with("services/web.yaml:12: Config"));
+
+        let change = FieldChange {
+            field: "max_memory".to_string(),
+            old: serde_json::Value::from("512MiB"),
+            new: serde_json::Value::Null,
+        };
+        assert_eq!(format_field_change(&change), "max_memory: 512MiB → unset");
+    }
+
     #[test]
     fn test_truncate() {
         assert_eq!(truncate("short", 10), "short");
@@ -0,0 +1,307 @@
+// Field-level comparison of process configurations, used to plan config reloads
+
+use super::ProcessConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Fields that are only read when a process is spawned, so changing them needs a restart
+///
+/// Every other field is read by the supervisor while the process runs and can
+/// be changed in place. `name` identifies a process and `instances` is handled
+/// by scaling, so neither is compared.
+pub const RESTART_FIELDS: &[&str] = &[
+    "script",
+    "args",
+    "cwd",
+    "env",
+    "schedule",
+    "oneshot",
+    "kill_mode",
+    "user",
+    "group",
+    "supplementary_groups",
+    "limits",
+    "nice",
+    "ionice",
+    "cpu_affinity",
+];
+
+/// A setting that differs between two configurations of a process
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    /// Value in the running configuration, `null` if unset
+    pub old: Value,
+    /// Value in the new configuration, `null` if unset
+    pub new: Value,
+}
+
+impl FieldChange {
+    /// Whether the change only takes effect after a restart
+    pub fn needs_restart(&self) -> bool {
+        RESTART_FIELDS.contains(&self.field.as_str())
+    }
+}
+
+/// Settings that differ between `old` and `new`, in alphabetical order
+///
+/// `name` and `instances` are ignored.
+pub fn diff_configs(old: &ProcessConfig, new: &ProcessConfig) -> Vec<FieldChange> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+
+    new.iter()
+        .filter(|(field, _)| !matches!(field.as_str(), "name" | "instances"))
+        .filter_map(|(field, value)| {
+            let old = old.get(field).cloned().unwrap_or(Value::Null);
+            (old != *value).then(|| FieldChange {
+                field: field.clone(),
+                old,
+                new: value.clone(),
+            })
+        })
+        .collect()
+}
+
+/// What a reload does to one process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanAction {
+    /// The process is new and is started
+    Add,
+    /// A setting read at spawn time changed, so the instances are restarted
+    Restart,
+    /// Only settings that apply to a running process changed
+    Update,
+    /// Nothing changed apart from maybe the number of instances
+    Unchanged,
+    /// The process was removed from the file and is stopped and deleted
+    Remove,
+}
+
+/// The change a reload makes to one process, with all of its instances
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanEntry {
+    /// Base name of the process, as written in the config file
+    pub name: String,
+    pub action: PlanAction,
+    /// Instances running now
+    pub from_instances: usize,
+    /// Instances running after the reload
+    pub to_instances: usize,
+    pub changes: Vec<FieldChange>,
+}
+
+impl PlanEntry {
+    /// Whether the reload touches the process at all
+    pub fn is_change(&self) -> bool {
+        self.action != PlanAction::Unchanged || self.from_instances != self.to_instances
+    }
+}
+
+/// The changes that make the running processes match a config file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReloadPlan {
+    pub entries: Vec<PlanEntry>,
+}
+
+/// A process as it runs now, for planning a reload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningProcess {
+    /// Configuration of the first instance, under the base name
+    pub config: ProcessConfig,
+    pub instances: usize,
+    /// Whether the process was loaded from the file being reloaded
+    pub from_file: bool,
+}
+
+impl ReloadPlan {
+    /// Plan the changes that make `running` match `configs`
+    ///
+    /// Processes that are not in `configs` are only removed if they were loaded
+    /// from the same file, so processes started another way are left alone.
+    pub fn compute(configs: &[ProcessConfig], running: &[RunningProcess]) -> Self {
+        let mut entries: Vec<PlanEntry> = configs
+            .iter()
+            .map(|config| {
+                let Some(current) = running.iter().find(|r| r.config.name == config.name) else {
+                    return PlanEntry {
+                        name: config.name.clone(),
+                        action: PlanAction::Add,
+                        from_instances: 0,
+                        to_instances: config.instances,
+                        changes: Vec::new(),
+                    };
+                };
+
+                let changes = diff_configs(&current.config, config);
+                // Moving into or out of a cgroup can only happen when the process is spawned
+                let action = if changes.iter().any(FieldChange::needs_restart)
+                    || current.config.uses_cgroup() != config.uses_cgroup()
+                {
+                    PlanAction::Restart
+                } else if !changes.is_empty() {
+                    PlanAction::Update
+                } else {
+                    PlanAction::Unchanged
+                };
+
+                PlanEntry {
+                    name: config.name.clone(),
+                    action,
+                    from_instances: current.instances,
+                    to_instances: config.instances,
+                    changes,
+                }
+            })
+            .collect();
+
+        entries.extend(
+            running
+                .iter()
+                .filter(|r| r.from_file && !configs.iter().any(|c| c.name == r.config.name))
+                .map(|r| PlanEntry {
+                    name: r.config.name.clone(),
+                    action: PlanAction::Remove,
+                    from_instances: r.instances,
+                    to_instances: 0,
+                    changes: Vec::new(),
+                }),
+        );
+
+        Self { entries }
+    }
+
+    /// Entries that change something
+    pub fn changes(&self) -> impl Iterator<Item = &PlanEntry> {
+        self.entries.iter().filter(|e| e.is_change())
+    }
+
+    /// Number of entries with the given action
+    pub fn count(&self, action: PlanAction) -> usize {
+        self.entries.iter().filter(|e| e.action == action).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> ProcessConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn running(config: ProcessConfig, instances: usize, from_file: bool) -> RunningProcess {
+        RunningProcess {
+            config,
+            instances,
+            from_file,
+        }
+    }
+
+    #[test]
+    fn test_diff_configs() {
+        let old = config("name = 'api'\nscript = '/bin/api'\nmax_memory = 100");
+        let new = config("name = 'api'\nscript = '/bin/api2'\ninstances = 3\nstop_timeout = '20s'");
+
+        let changes = diff_configs(&old, &new);
+        let fields: Vec<&str> = changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, ["max_memory", "script", "stop_timeout"]);
+        assert_eq!(changes[0].old, Value::from("100B"));
+        assert_eq!(changes[0].new, Value::Null);
+        assert!(changes[1].needs_restart());
+        assert!(!changes[2].needs_restart());
+    }
+
+    #[test]
+    fn test_every_field_is_classified() {
+        // A new field must either be listed in RESTART_FIELDS or be safe to change in place;
+        // this test lists the latter so that adding a field forces a decision
+        const IN_PLACE_FIELDS: &[&str] = &[
+            "autorestart",
+            "max_restarts",
+            "restart_delay",
+            "max_memory",
+            "max_cpu",
+            "limit_action",
+            "stop_signal",
+            "stop_timeout",
+            "health_check",
+            "readiness",
+            "rolling_batch",
+            "rolling_settle",
+            "depends_on",
+            "no_overlap",
+            "restart",
+            "success_exit_codes",
+            "no_restart_exit_codes",
+            "min_uptime",
+            "backoff",
+            "restart_window",
+            "cgroup",
+        ];
+
+        let Value::Object(fields) =
+            serde_json::to_value(config("name = 'a'\nscript = '/a'")).unwrap()
+        else {
+            panic!("config serializes to an object");
+        };
+        for field in fields.keys() {
+            assert!(
+                matches!(field.as_str(), "name" | "instances")
+                    || RESTART_FIELDS.contains(&field.as_str())
+                    || IN_PLACE_FIELDS.contains(&field.as_str()),
+                "field {} is not classified",
+                field
+            );
+        }
+    }
+
+    #[test]
+    fn test_compute_plan() {
+        let configs = vec![
+            config("name = 'api'\nscript = '/bin/api'\nenv = { PORT = '8080' }"),
+            config("name = 'web'\nscript = '/bin/web'\ninstances = 3\nmax_memory = 1024"),
+            config("name = 'cache'\nscript = '/bin/cache'"),
+            config("name = 'new'\nscript = '/bin/new'\ninstances = 2"),
+        ];
+        let current = vec![
+            running(config("name = 'api'\nscript = '/bin/api'"), 1, true),
+            running(config("name = 'web'\nscript = '/bin/web'"), 2, true),
+            running(config("name = 'cache'\nscript = '/bin/cache'"), 1, true),
+            running(config("name = 'old'\nscript = '/bin/old'"), 1, true),
+            running(config("name = 'manual'\nscript = '/bin/manual'"), 1, false),
+        ];
+
+        let plan = ReloadPlan::compute(&configs, &current);
+        let summary: Vec<(&str, PlanAction, usize, usize)> = plan
+            .entries
+            .iter()
+            .map(|e| (e.name.as_str(), e.action, e.from_instances, e.to_instances))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("api", PlanAction::Restart, 1, 1),
+                ("web", PlanAction::Update, 2, 3),
+                ("cache", PlanAction::Unchanged, 1, 1),
+                ("new", PlanAction::Add, 0, 2),
+                ("old", PlanAction::Remove, 1, 0),
+            ]
+        );
+        assert_eq!(plan.changes().count(), 4);
+        assert_eq!(plan.count(PlanAction::Add), 1);
+    }
+
+    #[test]
+    fn test_moving_into_a_cgroup_needs_restart() {
+        let old = config("name = 'api'\nscript = '/bin/api'");
+        let new = config("name = 'api'\nscript = '/bin/api'\nmax_cpu = 50");
+
+        let plan = ReloadPlan::compute(&[new], &[running(old, 1, false)]);
+        assert_eq!(plan.entries[0].action, PlanAction::Restart);
+    }
+}
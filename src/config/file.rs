@@ -0,0 +1,1014 @@
+// Reading config files and the files they include into process entries, and tracing
+// problems back to the lines of the file they were found on
+
+use super::ProcessConfig;
+use crate::error::{AdasaError, Result};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// A problem found in a config file
+#[derive(Debug)]
+pub struct ConfigIssue {
+    /// File the problem was found in, if it was read from disk
+    pub path: Option<PathBuf>,
+    /// Line the problem was found on, counting from 1
+    pub line: Option<usize>,
+    /// Process the problem belongs to
+    pub process: Option<String>,
+    pub error: AdasaError,
+}
+
+impl ConfigIssue {
+    fn new(path: Option<&Path>, error: AdasaError) -> Self {
+        Self {
+            path: path.map(Path::to_path_buf),
+            line: None,
+            process: None,
+            error,
+        }
+    }
+}
+
+/// Format of a config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl Format {
+    /// Format of a config file, chosen by its extension
+    pub fn of(path: &Path) -> Result<Self> {
+        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+
+        match extension {
+            "toml" => Ok(Format::Toml),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "json" => Ok(Format::Json),
+            _ => Err(AdasaError::InvalidConfig(format!(
+                "Unsupported file format: {}. Use .toml, .yaml or .json",
+                extension
+            ))),
+        }
+    }
+}
+
+/// The text of a config file
+#[derive(Debug)]
+struct Source {
+    /// Where the file was read from, `None` if it was given as a string
+    path: Option<PathBuf>,
+    contents: String,
+    format: Format,
+}
+
+impl Source {
+    fn read(path: &Path) -> std::result::Result<Self, ConfigIssue> {
+        let issue = |error| ConfigIssue::new(Some(path), error);
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            issue(AdasaError::ConfigError(format!(
+                "Failed to read config file: {}",
+                e
+            )))
+        })?;
+        let format = Format::of(path).map_err(issue)?;
+
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            contents,
+            format,
+        })
+    }
+
+    fn issue(&self, error: AdasaError) -> ConfigIssue {
+        ConfigIssue::new(self.path.as_deref(), error)
+    }
+
+    /// Parse the file into its top-level table
+    fn parse(&self) -> std::result::Result<Map<String, Value>, ConfigIssue> {
+        let contents = &self.contents;
+        let syntax_error = |line: Option<usize>, format: &str, e: &dyn std::fmt::Display| {
+            let mut issue = self.issue(AdasaError::InvalidConfig(format!(
+                "Failed to parse {}: {}",
+                format,
+                e.to_string().trim_end()
+            )));
+            issue.line = line;
+            issue
+        };
+
+        let value = match self.format {
+            Format::Toml => {
+                let table = toml::from_str::<toml::Table>(contents).map_err(|e| {
+                    syntax_error(e.span().map(|s| line_at(contents, s.start)), "TOML", &e)
+                })?;
+                serde_json::to_value(table).map_err(|e| syntax_error(None, "TOML", &e))?
+            }
+            Format::Yaml => serde_yaml::from_str(contents)
+                .map_err(|e| syntax_error(e.location().map(|l| l.line()), "YAML", &e))?,
+            Format::Json => serde_json::from_str(contents)
+                .map_err(|e| syntax_error(Some(e.line()).filter(|line| *line > 0), "JSON", &e))?,
+        };
+
+        match value {
+            Value::Object(table) => Ok(table),
+            _ => Err(self.issue(AdasaError::InvalidConfig(
+                "Config file must contain a table of settings".to_string(),
+            ))),
+        }
+    }
+}
+
+/// The processes of a config file and the files it includes, with the settings they share
+///
+/// A file holds either a single process or an array of `processes`. Next to the
+/// array, `defaults` apply to every process and `templates` to the processes that
+/// `extends` them. `env.<profile>` tables hold `defaults`, `templates` and
+/// `processes.<name>` that override the rest of the file when the profile is selected.
+///
+/// `include` lists further files by path or glob pattern, relative to the file. Their
+/// processes, templates and profiles are added to those of the including file.
+#[derive(Debug, Default)]
+pub struct ConfigFile {
+    /// Settings applied to every process
+    defaults: Map<String, Value>,
+    /// Settings that processes can extend, by template name
+    templates: Map<String, Value>,
+    /// Environment profiles, by name
+    profiles: Map<String, Value>,
+    /// Process entries as written in the files, with the profile applied
+    pub entries: Vec<Value>,
+    /// Index into `sources` of the file each entry was read from
+    origins: Vec<usize>,
+    /// The config file followed by the files it includes
+    sources: Vec<Source>,
+}
+
+impl ConfigFile {
+    /// Read a config file and the files it includes, applying the environment profile
+    /// if one is selected
+    pub fn load(path: &Path, profile: Option<&str>) -> std::result::Result<Self, ConfigIssue> {
+        Self::build(Source::read(path)?, profile)
+    }
+
+    /// Parse the contents of a config file, applying the environment profile if one is selected
+    ///
+    /// Files parsed from a string cannot use `include`.
+    pub fn parse(
+        contents: &str,
+        format: Format,
+        profile: Option<&str>,
+    ) -> std::result::Result<Self, ConfigIssue> {
+        let source = Source {
+            path: None,
+            contents: contents.to_string(),
+            format,
+        };
+        Self::build(source, profile)
+    }
+
+    fn build(source: Source, profile: Option<&str>) -> std::result::Result<Self, ConfigIssue> {
+        let mut table = source.parse()?;
+        let includes = match table.remove("include") {
+            Some(patterns) => {
+                let path = source.path.as_deref().ok_or_else(|| {
+                    source.issue(AdasaError::InvalidConfig(
+                        "include is only supported in config files read from disk".to_string(),
+                    ))
+                })?;
+                expand_includes(path, &patterns).map_err(|e| source.issue(e))?
+            }
+            None => Vec::new(),
+        };
+
+        // A file that only includes others still shares its settings with them
+        let shared = !includes.is_empty();
+        let mut file = ConfigFile::default();
+        file.add(source, table, shared)?;
+
+        for path in includes {
+            let source = Source::read(&path)?;
+            let table = source.parse()?;
+            if let Some(key) = ["include", "defaults"]
+                .into_iter()
+                .find(|key| table.contains_key(*key))
+            {
+                return Err(source.issue(AdasaError::InvalidConfig(format!(
+                    "{} can only be set in the main config file",
+                    key
+                ))));
+            }
+            file.add(source, table, false)?;
+        }
+
+        if let Some(profile) = profile {
+            file.apply_profile(profile)
+                .map_err(|e| ConfigIssue::new(file.sources[0].path.as_deref(), e))?;
+        }
+
+        if file.entries.is_empty() {
+            return Err(ConfigIssue::new(
+                file.sources[0].path.as_deref(),
+                AdasaError::InvalidConfig("No process configuration found in file".to_string()),
+            ));
+        }
+
+        Ok(file)
+    }
+
+    /// Add the processes and shared settings of one file
+    ///
+    /// Without a `processes` array and unless `shared` is set, the whole file is a
+    /// single process and `env` holds its environment variables.
+    fn add(
+        &mut self,
+        source: Source,
+        mut table: Map<String, Value>,
+        shared: bool,
+    ) -> std::result::Result<(), ConfigIssue> {
+        let entries = match table.remove("processes") {
+            Some(Value::Array(entries)) => entries,
+            Some(_) => {
+                return Err(source.issue(AdasaError::InvalidConfig(
+                    "processes must be an array of process configurations".to_string(),
+                )))
+            }
+            None if shared => {
+                if let Some(key) = table
+                    .keys()
+                    .find(|key| !matches!(key.as_str(), "defaults" | "templates" | "env"))
+                {
+                    return Err(source.issue(AdasaError::InvalidConfig(format!(
+                        "Unexpected key {}: a config file with include lists its own processes under processes",
+                        key
+                    ))));
+                }
+                Vec::new()
+            }
+            None if table.is_empty() => Vec::new(),
+            None => vec![Value::Object(std::mem::take(&mut table))],
+        };
+
+        let mut section = |key: &str| match table.remove(key) {
+            Some(Value::Object(section)) => Ok(section),
+            None => Ok(Map::new()),
+            Some(_) => Err(source.issue(AdasaError::InvalidConfig(format!(
+                "{} must be a table",
+                key
+            )))),
+        };
+        let defaults = section("defaults")?;
+        let templates = section("templates")?;
+        let profiles = section("env")?;
+
+        merge_into(&mut self.defaults, &Value::Object(defaults));
+        for (name, template) in templates {
+            if self.templates.contains_key(&name) {
+                return Err(source.issue(AdasaError::ConfigValidationError(format!(
+                    "Template '{}' is defined more than once",
+                    name
+                ))));
+            }
+            self.templates.insert(name, template);
+        }
+        merge_into(&mut self.profiles, &Value::Object(profiles));
+
+        self.origins
+            .extend(std::iter::repeat_n(self.sources.len(), entries.len()));
+        self.entries.extend(entries);
+        self.sources.push(source);
+
+        Ok(())
+    }
+
+    /// Merge the `env.<profile>` table over the rest of the file
+    fn apply_profile(&mut self, profile: &str) -> Result<()> {
+        let Some(overrides) = self.profiles.remove(profile) else {
+            let mut known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            known.sort_unstable();
+            return Err(AdasaError::InvalidConfig(if known.is_empty() {
+                format!(
+                    "Unknown environment profile '{}': none are defined",
+                    profile
+                )
+            } else {
+                format!(
+                    "Unknown environment profile '{}': expected one of {}",
+                    profile,
+                    known.join(", ")
+                )
+            }));
+        };
+        let Value::Object(overrides) = overrides else {
+            return Err(AdasaError::InvalidConfig(format!(
+                "env.{} must be a table",
+                profile
+            )));
+        };
+
+        for (key, value) in overrides {
+            match (key.as_str(), value) {
+                ("defaults", value @ Value::Object(_)) => {
+                    merge_into(&mut self.defaults, &value);
+                }
+                ("templates", value @ Value::Object(_)) => {
+                    merge_into(&mut self.templates, &value);
+                }
+                ("processes", Value::Object(processes)) => {
+                    for (name, value) in processes {
+                        let entry = self
+                            .entries
+                            .iter_mut()
+                            .find(|entry| entry.get("name").and_then(Value::as_str) == Some(&name))
+                            .ok_or_else(|| {
+                                AdasaError::ConfigValidationError(format!(
+                                    "env.{}.processes.{} does not match any process",
+                                    profile, name
+                                ))
+                            })?;
+                        merge(entry, &value);
+                    }
+                }
+                ("defaults" | "templates" | "processes", _) => {
+                    return Err(AdasaError::InvalidConfig(format!(
+                        "env.{}.{} must be a table",
+                        profile, key
+                    )))
+                }
+                _ => {
+                    return Err(AdasaError::InvalidConfig(format!(
+                        "Unknown key env.{}.{}: expected defaults, templates or processes",
+                        profile, key
+                    )))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Settings of a process entry, layered over its templates and the defaults
+    ///
+    /// Tables such as `env` and `limits` are merged key by key; any other setting
+    /// replaces the inherited one.
+    pub fn resolve(&self, entry: &Value) -> Result<Value> {
+        if !entry.is_object() {
+            return Err(AdasaError::InvalidConfig(
+                "Process entries must be tables of settings".to_string(),
+            ));
+        }
+
+        // Follow `extends` from the entry up to the outermost template
+        let mut chain: Vec<(&str, &Value)> = Vec::new();
+        let mut extends = entry.get("extends");
+        while let Some(parent) = extends {
+            let name = parent.as_str().ok_or_else(|| {
+                AdasaError::InvalidConfig("extends must be the name of a template".to_string())
+            })?;
+            if chain.iter().any(|(seen, _)| *seen == name) {
+                let cycle: Vec<&str> = chain
+                    .iter()
+                    .map(|(seen, _)| *seen)
+                    .chain(std::iter::once(name))
+                    .collect();
+                return Err(AdasaError::ConfigValidationError(format!(
+                    "Template cycle detected: {}",
+                    cycle.join(" -> ")
+                )));
+            }
+            let template = self.templates.get(name).ok_or_else(|| {
+                AdasaError::ConfigValidationError(format!("Unknown template '{}'", name))
+            })?;
+            chain.push((name, template));
+            extends = template.get("extends");
+        }
+
+        let mut settings = Value::Object(self.defaults.clone());
+        for (_, template) in chain.iter().rev() {
+            merge(&mut settings, template);
+        }
+        merge(&mut settings, entry);
+        if let Value::Object(settings) = &mut settings {
+            settings.remove("extends");
+        }
+
+        Ok(settings)
+    }
+}
+
+/// Expand the `include` patterns of the config file at `path` into the files they match
+///
+/// Patterns are relative to the directory of the config file. Each pattern must match
+/// at least one file, so a typo is not silently ignored.
+fn expand_includes(path: &Path, patterns: &Value) -> Result<Vec<PathBuf>> {
+    let patterns: Vec<&str> = match patterns {
+        Value::String(pattern) => vec![pattern.as_str()],
+        Value::Array(patterns) => patterns
+            .iter()
+            .map(|pattern| pattern.as_str())
+            .collect::<Option<_>>()
+            .ok_or_else(|| {
+                AdasaError::InvalidConfig("include must be a list of paths".to_string())
+            })?,
+        _ => {
+            return Err(AdasaError::InvalidConfig(
+                "include must be a list of paths".to_string(),
+            ))
+        }
+    };
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    for pattern in patterns {
+        let full = dir.join(pattern);
+        let matches = glob::glob(&full.to_string_lossy()).map_err(|e| {
+            AdasaError::InvalidConfig(format!("Invalid include pattern '{}': {}", pattern, e))
+        })?;
+
+        let mut matched = false;
+        for file in matches.filter_map(|entry| entry.ok()) {
+            matched = true;
+            if file != path && !files.contains(&file) {
+                files.push(file);
+            }
+        }
+        if !matched {
+            return Err(AdasaError::InvalidConfig(format!(
+                "include '{}' does not match any file",
+                pattern
+            )));
+        }
+    }
+
+    Ok(files)
+}
+
+/// Merge `overlay` into `base`, recursing into tables
+fn merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), overlay @ Value::Object(_)) => merge_into(base, overlay),
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+fn merge_into(base: &mut Map<String, Value>, overlay: &Value) {
+    if let Value::Object(overlay) = overlay {
+        for (key, value) in overlay {
+            match base.get_mut(key) {
+                Some(existing) => merge(existing, value),
+                None => {
+                    base.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Load and validate every process in a config file and the files it includes,
+/// collecting all problems
+///
+/// Returns the configurations if there are no problems. Problems in one process
+/// do not stop the others from being checked, so a single run reports them all.
+pub fn check_file(
+    path: &Path,
+    profile: Option<&str>,
+) -> std::result::Result<Vec<ProcessConfig>, Vec<ConfigIssue>> {
+    let file = ConfigFile::load(path, profile).map_err(|issue| vec![issue])?;
+
+    // Entries are located within the file they were read from
+    let sources: Vec<SourceLines> = file
+        .sources
+        .iter()
+        .enumerate()
+        .map(|(idx, source)| {
+            let entries: Vec<&Value> = file
+                .entries
+                .iter()
+                .zip(&file.origins)
+                .filter(|(_, origin)| **origin == idx)
+                .map(|(entry, _)| entry)
+                .collect();
+            SourceLines::new(&source.contents, source.format, &entries)
+        })
+        .collect();
+    let positions: Vec<(usize, usize)> = file
+        .origins
+        .iter()
+        .enumerate()
+        .map(|(idx, origin)| {
+            let position = file.origins[..idx].iter().filter(|o| *o == origin).count();
+            (*origin, position)
+        })
+        .collect();
+
+    let locate = |idx: usize, field: Option<&str>| {
+        let (origin, position) = positions[idx];
+        let lines = &sources[origin];
+        let line = match field {
+            Some(field) => lines.field_line(position, field),
+            None => lines.process_line(position),
+        };
+        (file.sources[origin].path.clone(), line)
+    };
+    let issue = |idx: usize, process: Option<String>, field: Option<&str>, error| {
+        let (path, line) = locate(idx, field);
+        ConfigIssue {
+            path,
+            line,
+            process,
+            error,
+        }
+    };
+
+    let mut configs = Vec::with_capacity(file.entries.len());
+    let mut issues = Vec::new();
+
+    for (idx, entry) in file.entries.iter().enumerate() {
+        let name = entry.get("name").and_then(Value::as_str).map(String::from);
+
+        let config = file
+            .resolve(entry)
+            .map_err(|error| (Some("extends"), error))
+            .and_then(|settings| {
+                ProcessConfig::from_entry(settings).map_err(|error| (None, error))
+            });
+
+        match config {
+            Ok(mut config) => {
+                config.expand_env_vars();
+                issues.extend(
+                    config
+                        .validation_errors()
+                        .into_iter()
+                        .map(|(field, error)| {
+                            issue(idx, Some(config.name.clone()), Some(field), error)
+                        }),
+                );
+                configs.push(config);
+            }
+            Err((field, error)) => issues.push(issue(idx, name.clone(), field, error)),
+        }
+
+        if let Some(first) = name.as_deref().and_then(|name| {
+            file.entries[..idx]
+                .iter()
+                .position(|e| e.get("name").and_then(Value::as_str) == Some(name))
+        }) {
+            let (first_path, first_line) = locate(first, Some("name"));
+            let mut location = first_path
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|| "line".to_string());
+            if let Some(line) = first_line {
+                location.push_str(&format!(":{}", line));
+            }
+            issues.push(issue(
+                idx,
+                name.clone(),
+                Some("name"),
+                AdasaError::ConfigValidationError(format!(
+                    "Duplicate process name, already defined at {}",
+                    location
+                )),
+            ));
+        }
+    }
+
+    // A process depending on itself has already been reported with its line
+    if let Err(error) = ProcessConfig::validate_dependencies(&configs) {
+        let message = error.to_string();
+        if !issues
+            .iter()
+            .any(|issue| issue.error.to_string() == message)
+        {
+            issues.push(ConfigIssue::new(Some(path), error));
+        }
+    }
+
+    if issues.is_empty() {
+        Ok(configs)
+    } else {
+        Err(issues)
+    }
+}
+
+/// Line of a byte offset, counting from 1
+fn line_at(contents: &str, offset: usize) -> usize {
+    contents[..offset.min(contents.len())].matches('\n').count() + 1
+}
+
+/// Finds the lines that processes and their settings are defined on
+///
+/// The lookup is textual: parsed values carry no positions, so each process is
+/// found by its `[[processes]]` header or its `name`, and a setting by its key
+/// within the lines of that process.
+struct SourceLines<'a> {
+    lines: Vec<&'a str>,
+    /// First line of each process, counting from 0
+    starts: Vec<Option<usize>>,
+    /// Whether processes are `[[processes]]` tables, which end at the next top-level table
+    tables: bool,
+}
+
+impl<'a> SourceLines<'a> {
+    fn new(contents: &'a str, format: Format, entries: &[&Value]) -> Self {
+        let lines: Vec<&str> = contents.lines().collect();
+
+        let headers: Vec<usize> = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.trim_start().starts_with("[[processes]]"))
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let tables = format == Format::Toml && headers.len() == entries.len();
+        let starts = if tables {
+            headers.into_iter().map(Some).collect()
+        } else if format == Format::Toml && headers.is_empty() && entries.len() == 1 {
+            vec![Some(0)]
+        } else {
+            entries
+                .iter()
+                .map(|entry| {
+                    let name = entry.get("name").and_then(Value::as_str)?;
+                    let pattern = Regex::new(&format!(
+                        r#"(^|[{{,])\s*(-\s+)?"?name"?\s*[:=]\s*["']?{}["']?\s*([,}}#]|$)"#,
+                        regex::escape(name)
+                    ))
+                    .ok()?;
+                    lines.iter().position(|line| pattern.is_match(line))
+                })
+                .collect()
+        };
+
+        Self {
+            lines,
+            starts,
+            tables,
+        }
+    }
+
+    /// Line the process at `idx` starts on, counting from 1
+    fn process_line(&self, idx: usize) -> Option<usize> {
+        self.starts.get(idx).copied().flatten().map(|line| line + 1)
+    }
+
+    /// Line `field` is set on in the process at `idx`, falling back to the start of the process
+    fn field_line(&self, idx: usize, field: &str) -> Option<usize> {
+        let start = self.starts.get(idx).copied().flatten()?;
+        let end = if self.tables {
+            self.lines
+                .iter()
+                .enumerate()
+                .skip(start + 1)
+                .find(|(_, line)| {
+                    let line = line.trim_start();
+                    line.starts_with('[')
+                        && !line.starts_with("[processes.")
+                        && !line.starts_with("[[processes.")
+                })
+                .map_or(self.lines.len(), |(idx, _)| idx)
+        } else {
+            self.starts
+                .iter()
+                .flatten()
+                .copied()
+                .filter(|line| *line > start)
+                .min()
+                .unwrap_or(self.lines.len())
+        };
+
+        // Durations may still be written under their old `_secs` names
+        let pattern = Regex::new(&format!(
+            r#"(^|[{{,])\s*(-\s+)?(\[+\s*(processes\.)?)?"?{}(_secs)?"?\s*[.:=\]]"#,
+            regex::escape(field)
+        ))
+        .ok()?;
+
+        let found = self.lines[start..end]
+            .iter()
+            .position(|line| pattern.is_match(line))
+            .map(|offset| start + offset + 1);
+        found.or(Some(start + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::units::ByteSize;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    const SHARED: &str = r#"
+[defaults]
+stop_timeout_secs = 20
+env = { LOG_LEVEL = "info" }
+
+[templates.node]
+script = "/usr/bin/node"
+max_memory = 1000
+env = { NODE_ENV = "development" }
+
+[templates.api]
+extends = "node"
+instances = 2
+
+[[processes]]
+name = "api"
+extends = "api"
+args = ["api.js"]
+env = { PORT = "8080" }
+
+[[processes]]
+name = "worker"
+script = "/bin/worker"
+stop_timeout_secs = 5
+
+[env.production.defaults]
+env = { LOG_LEVEL = "warn" }
+
+[env.production.templates.node]
+env = { NODE_ENV = "production" }
+
+[env.production.processes.api]
+instances = 4
+"#;
+
+    fn load(contents: &str, profile: Option<&str>) -> Result<Vec<ProcessConfig>> {
+        let file = ConfigFile::parse(contents, Format::Toml, profile).map_err(|i| i.error)?;
+        file.entries
+            .iter()
+            .map(|entry| file.resolve(entry).and_then(ProcessConfig::from_entry))
+            .collect()
+    }
+
+    #[test]
+    fn test_defaults_and_templates() {
+        let configs = load(SHARED, None).unwrap();
+        let (api, worker) = (&configs[0], &configs[1]);
+
+        assert_eq!(api.script, Path::new("/usr/bin/node"));
+        assert_eq!(api.args, ["api.js"]);
+        assert_eq!(api.instances, 2);
+        assert_eq!(api.max_memory, Some(ByteSize(1000)));
+        assert_eq!(api.stop_timeout, Duration::from_secs(20));
+        assert_eq!(api.env.len(), 3);
+        assert_eq!(api.env["NODE_ENV"], "development");
+        assert_eq!(api.env["PORT"], "8080");
+
+        assert_eq!(worker.stop_timeout, Duration::from_secs(5));
+        assert_eq!(worker.max_memory, None);
+        assert_eq!(worker.env["LOG_LEVEL"], "info");
+    }
+
+    #[test]
+    fn test_environment_profile() {
+        let configs = load(SHARED, Some("production")).unwrap();
+        let (api, worker) = (&configs[0], &configs[1]);
+
+        assert_eq!(api.instances, 4);
+        assert_eq!(api.env["NODE_ENV"], "production");
+        assert_eq!(api.env["LOG_LEVEL"], "warn");
+        assert_eq!(api.env["PORT"], "8080");
+        assert_eq!(worker.env["LOG_LEVEL"], "warn");
+
+        let err = load(SHARED, Some("staging")).unwrap_err();
+        assert!(err.to_string().contains("expected one of production"));
+
+        let err = load("name = 'a'\nscript = '/a'", Some("production")).unwrap_err();
+        assert!(matches!(err, AdasaError::InvalidConfig(_)));
+
+        let typo = format!(
+            "{}\n[env.production.processes.apj]\ninstances = 1\n",
+            SHARED
+        );
+        let err = load(&typo, Some("production")).unwrap_err();
+        assert!(err.to_string().contains("apj"));
+    }
+
+    #[test]
+    fn test_unknown_template_and_cycle() {
+        let contents = r#"
+[templates.a]
+extends = "b"
+
+[templates.b]
+extends = "a"
+
+[[processes]]
+name = "x"
+script = "/x"
+extends = "a"
+
+[[processes]]
+name = "y"
+script = "/y"
+extends = "missing"
+"#;
+        let file = ConfigFile::parse(contents, Format::Toml, None).unwrap();
+        let errors: Vec<String> = file
+            .entries
+            .iter()
+            .map(|entry| file.resolve(entry).unwrap_err().to_string())
+            .collect();
+        assert!(errors[0].contains("Template cycle detected: a -> b -> a"));
+        assert!(errors[1].contains("Unknown template 'missing'"));
+    }
+
+    #[test]
+    fn test_check_file_reports_every_issue() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.toml");
+        fs::write(
+            &path,
+            r#"
+[[processes]]
+name = "api"
+script = "/bin/api"
+instances = 0
+stop_signal = "SIGFOO"
+
+[[processes]]
+name = "worker"
+args = ["run"]
+
+[[processes]]
+name = "web"
+script = "/bin/web"
+
+[processes.health_check]
+type = "tcp"
+port = 80
+interval_secs = 0
+"#,
+        )
+        .unwrap();
+
+        let issues = check_file(&path, None).unwrap_err();
+        let found: Vec<(Option<usize>, Option<&str>)> = issues
+            .iter()
+            .map(|i| (i.line, i.process.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (Some(5), Some("api")),
+                (Some(6), Some("api")),
+                (Some(8), Some("worker")),
+                (Some(16), Some("web")),
+            ]
+        );
+        assert!(issues[2].error.to_string().contains("script"));
+    }
+
+    #[test]
+    fn test_check_file_syntax_error_line() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let path = temp_dir.path().join("app.toml");
+        fs::write(&path, "name = \"api\"\nscript = \n").unwrap();
+        let issues = check_file(&path, None).unwrap_err();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(2));
+
+        let path = temp_dir.path().join("app.json");
+        fs::write(&path, "{\n  \"name\": \"api\",\n  \"script\": }\n").unwrap();
+        let issues = check_file(&path, None).unwrap_err();
+        assert_eq!(issues[0].line, Some(3));
+    }
+
+    #[test]
+    fn test_check_file_json_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.json");
+        fs::write(
+            &path,
+            r#"{
+  "processes": [
+    { "name": "api", "script": "/bin/api" },
+    {
+      "name": "web",
+      "script": "/bin/web",
+      "max_cpu": 500
+    }
+  ]
+}"#,
+        )
+        .unwrap();
+
+        let issues = check_file(&path, None).unwrap_err();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(7));
+        assert_eq!(issues[0].process.as_deref(), Some("web"));
+    }
+
+    #[test]
+    fn test_check_file_yaml() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.yml");
+        fs::write(
+            &path,
+            r#"defaults:
+  stop_timeout_secs: 20
+
+processes:
+  - name: api
+    script: /bin/api
+    env:
+      PORT: "8080"
+  - name: web
+    script: /bin/web
+    instances: 0
+"#,
+        )
+        .unwrap();
+
+        let issues = check_file(&path, None).unwrap_err();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(11));
+        assert_eq!(issues[0].process.as_deref(), Some("web"));
+
+        let contents = fs::read_to_string(&path)
+            .unwrap()
+            .replace("instances: 0", "");
+        fs::write(&path, contents).unwrap();
+        let configs = check_file(&path, None).unwrap();
+        assert_eq!(configs[0].env["PORT"], "8080");
+        assert_eq!(configs[1].stop_timeout, Duration::from_secs(20));
+
+        fs::write(&path, "name: api\nscript: [\n").unwrap();
+        let issues = check_file(&path, None).unwrap_err();
+        assert!(issues[0].error.to_string().contains("YAML"));
+    }
+
+    #[test]
+    fn test_check_file_includes() {
+        let temp_dir = TempDir::new().unwrap();
+        let services = temp_dir.path().join("services");
+        fs::create_dir(&services).unwrap();
+        let path = temp_dir.path().join("app.toml");
+        fs::write(
+            &path,
+            r#"
+include = ["services/*.toml", "services/*.yaml"]
+
+[defaults]
+env = { LOG_LEVEL = "info" }
+
+[templates.node]
+script = "/usr/bin/node"
+"#,
+        )
+        .unwrap();
+        fs::write(
+            services.join("api.toml"),
+            "[[processes]]\nname = \"api\"\nextends = \"node\"\n\n[env.production.processes.api]\ninstances = 3\n",
+        )
+        .unwrap();
+        fs::write(
+            services.join("workers.yaml"),
+            "processes:\n  - name: mailer\n    script: /bin/mailer\n",
+        )
+        .unwrap();
+
+        let configs = check_file(&path, Some("production")).unwrap();
+        let names: Vec<&str> = configs.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["api", "mailer"]);
+        assert_eq!(configs[0].script, Path::new("/usr/bin/node"));
+        assert_eq!(configs[0].instances, 3);
+        assert_eq!(configs[1].env["LOG_LEVEL"], "info");
+
+        // Two teams picking the same name
+        fs::write(
+            services.join("more.yaml"),
+            "processes:\n  - name: other\n    script: /bin/other\n  - name: api\n    script: /bin/api\n",
+        )
+        .unwrap();
+        let issues = check_file(&path, None).unwrap_err();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].path.as_deref(),
+            Some(services.join("more.yaml").as_path())
+        );
+        assert_eq!(issues[0].line, Some(4));
+        let message = issues[0].error.to_string();
+        assert!(message.contains("Duplicate process name"));
+        assert!(message.contains(&format!("{}:2", services.join("api.toml").display())));
+
+        fs::write(services.join("more.yaml"), "defaults:\n  instances: 2\n").unwrap();
+        let issues = check_file(&path, None).unwrap_err();
+        assert!(issues[0].error.to_string().contains("main config file"));
+
+        fs::remove_file(services.join("more.yaml")).unwrap();
+        fs::remove_file(services.join("workers.yaml")).unwrap();
+        let issues = check_file(&path, None).unwrap_err();
+        assert!(issues[0]
+            .error
+            .to_string()
+            .contains("does not match any file"));
+    }
+}
@@ -1,8 +1,21 @@
 use crate::error::{AdasaError, Result};
+use chrono::{DateTime, Local};
+use croner::Cron;
+use nix::unistd::{getgrouplist, Gid, Group, Uid, User};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ffi::CString;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
+
+pub mod diff;
+pub mod file;
+pub mod units;
+
+use units::ByteSize;
+
+/// Number of CPU cores that `cpu_affinity` can refer to
+pub const MAX_CPU_CORES: usize = 1024;
 
 /// Action to take when resource limits are exceeded
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -16,8 +29,664 @@ pub enum LimitAction {
     Stop,
 }
 
+/// When to restart a process after it exits
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartMode {
+    /// Restart whenever the process exits, and start it with the daemon even after a stop
+    Always,
+    /// Restart only if the process exits with a failure code or is killed by a signal
+    OnFailure,
+    /// Never restart
+    Never,
+    /// Restart whenever the process exits, but keep it stopped across daemon restarts once stopped
+    UnlessStopped,
+}
+
+impl std::fmt::Display for RestartMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RestartMode::Always => write!(f, "always"),
+            RestartMode::OnFailure => write!(f, "on-failure"),
+            RestartMode::Never => write!(f, "never"),
+            RestartMode::UnlessStopped => write!(f, "unless-stopped"),
+        }
+    }
+}
+
+/// Which processes receive the stop signal when a process is stopped
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KillMode {
+    /// Only the main process
+    Process,
+    /// The process group of the main process, including any descendants that stayed in it
+    #[default]
+    Group,
+    /// Every process in the cgroup of the process, plus its process group
+    Cgroup,
+}
+
+impl std::fmt::Display for KillMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KillMode::Process => write!(f, "process"),
+            KillMode::Group => write!(f, "group"),
+            KillMode::Cgroup => write!(f, "cgroup"),
+        }
+    }
+}
+
+/// How the delay between consecutive restarts grows
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BackoffConfig {
+    /// Always wait `restart_delay`
+    Fixed {
+        /// Random spread applied to each delay, as a fraction of it (0.0 to 1.0)
+        #[serde(default)]
+        jitter: f64,
+    },
+    /// Double the delay after each restart, up to `max_delay`
+    Exponential {
+        /// Upper bound for the delay
+        #[serde(
+            default = "default_max_backoff_delay",
+            alias = "max_delay_secs",
+            with = "units::duration"
+        )]
+        max_delay: Duration,
+        /// Random spread applied to each delay, as a fraction of it (0.0 to 1.0)
+        #[serde(default)]
+        jitter: f64,
+    },
+}
+
+impl BackoffConfig {
+    /// Random spread applied to each delay, as a fraction of it
+    pub fn jitter(&self) -> f64 {
+        match self {
+            BackoffConfig::Fixed { jitter } | BackoffConfig::Exponential { jitter, .. } => *jitter,
+        }
+    }
+
+    /// Validate the backoff configuration
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.jitter()) {
+            return Err(AdasaError::ConfigValidationError(
+                "Backoff jitter must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        BackoffConfig::Exponential {
+            max_delay: default_max_backoff_delay(),
+            jitter: 0.0,
+        }
+    }
+}
+
+fn default_max_backoff_delay() -> Duration {
+    Duration::from_secs(60)
+}
+
+/// Probe used by an active health check
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum HealthProbe {
+    /// Issue an HTTP GET and expect a successful status code
+    Http {
+        /// URL to request (only `http://` is supported)
+        url: String,
+        /// Exact status code to expect (default: any 2xx or 3xx)
+        #[serde(default)]
+        expected_status: Option<u16>,
+    },
+    /// Open a TCP connection to the given address
+    Tcp {
+        /// Host to connect to
+        #[serde(default = "default_probe_host")]
+        host: String,
+        /// Port to connect to
+        port: u16,
+    },
+    /// Run a command and expect it to exit with status 0
+    Exec {
+        /// Command to execute
+        command: PathBuf,
+        /// Command-line arguments
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+/// Active health check configuration for a process
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HealthCheckConfig {
+    /// Probe to run
+    #[serde(flatten)]
+    pub probe: HealthProbe,
+
+    /// Time between probes
+    #[serde(
+        default = "default_health_interval",
+        alias = "interval_secs",
+        with = "units::duration"
+    )]
+    pub interval: Duration,
+
+    /// Time before a single probe is considered failed
+    #[serde(
+        default = "default_health_timeout",
+        alias = "timeout_secs",
+        with = "units::duration"
+    )]
+    pub timeout: Duration,
+
+    /// Consecutive failures before the process is marked unhealthy
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+impl HealthCheckConfig {
+    /// Validate the health check configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.interval.is_zero() {
+            return Err(AdasaError::ConfigValidationError(
+                "health_check.interval must be greater than zero".to_string(),
+            ));
+        }
+
+        if self.timeout.is_zero() {
+            return Err(AdasaError::ConfigValidationError(
+                "health_check.timeout must be greater than zero".to_string(),
+            ));
+        }
+
+        if self.failure_threshold == 0 {
+            return Err(AdasaError::ConfigValidationError(
+                "health_check.failure_threshold must be at least 1".to_string(),
+            ));
+        }
+
+        match &self.probe {
+            HealthProbe::Http { url, .. } => {
+                if !url.starts_with("http://") {
+                    return Err(AdasaError::ConfigValidationError(format!(
+                        "Invalid health_check.url: {}. Only http:// URLs are supported",
+                        url
+                    )));
+                }
+            }
+            HealthProbe::Tcp { host, port } => {
+                if host.is_empty() || *port == 0 {
+                    return Err(AdasaError::ConfigValidationError(
+                        "health_check requires a host and a non-zero port for tcp probes"
+                            .to_string(),
+                    ));
+                }
+            }
+            HealthProbe::Exec { command, .. } => {
+                if command.as_os_str().is_empty() {
+                    return Err(AdasaError::MissingConfigField(
+                        "health_check.command".to_string(),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Signal used to decide that a freshly started instance is ready
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ReadinessCheck {
+    /// Issue an HTTP GET until it returns a successful status code
+    Http {
+        /// URL to request (only `http://` is supported)
+        url: String,
+        /// Exact status code to expect (default: any 2xx or 3xx)
+        #[serde(default)]
+        expected_status: Option<u16>,
+    },
+    /// Connect to a TCP port until the connection is accepted
+    Tcp {
+        /// Host to connect to
+        #[serde(default = "default_probe_host")]
+        host: String,
+        /// Port to connect to
+        port: u16,
+    },
+    /// Run a command until it exits with status 0
+    Exec {
+        /// Command to execute
+        command: PathBuf,
+        /// Command-line arguments
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// Wait for a line on stdout or stderr matching a regular expression
+    Log {
+        /// Regular expression to match against each output line
+        pattern: String,
+    },
+    /// Wait for the process to send `READY=1` to the socket named in `NOTIFY_SOCKET`
+    Notify,
+}
+
+impl ReadinessCheck {
+    /// Get the equivalent health probe for probe-based checks
+    pub fn probe(&self) -> Option<HealthProbe> {
+        match self {
+            ReadinessCheck::Http {
+                url,
+                expected_status,
+            } => Some(HealthProbe::Http {
+                url: url.clone(),
+                expected_status: *expected_status,
+            }),
+            ReadinessCheck::Tcp { host, port } => Some(HealthProbe::Tcp {
+                host: host.clone(),
+                port: *port,
+            }),
+            ReadinessCheck::Exec { command, args } => Some(HealthProbe::Exec {
+                command: command.clone(),
+                args: args.clone(),
+            }),
+            ReadinessCheck::Log { .. } | ReadinessCheck::Notify => None,
+        }
+    }
+}
+
+/// Readiness gate used when a process is replaced during a rolling restart
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReadinessConfig {
+    /// How the process reports readiness
+    #[serde(flatten)]
+    pub check: ReadinessCheck,
+
+    /// Time to wait for the process to become ready
+    #[serde(
+        default = "default_readiness_timeout",
+        alias = "timeout_secs",
+        with = "units::duration"
+    )]
+    pub timeout: Duration,
+}
+
+impl ReadinessConfig {
+    /// Validate the readiness configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.timeout.is_zero() {
+            return Err(AdasaError::ConfigValidationError(
+                "readiness.timeout must be greater than zero".to_string(),
+            ));
+        }
+
+        match &self.check {
+            ReadinessCheck::Http { url, .. } => {
+                if !url.starts_with("http://") {
+                    return Err(AdasaError::ConfigValidationError(format!(
+                        "Invalid readiness.url: {}. Only http:// URLs are supported",
+                        url
+                    )));
+                }
+            }
+            ReadinessCheck::Tcp { host, port } => {
+                if host.is_empty() || *port == 0 {
+                    return Err(AdasaError::ConfigValidationError(
+                        "readiness requires a host and a non-zero port for tcp checks".to_string(),
+                    ));
+                }
+            }
+            ReadinessCheck::Exec { command, .. } => {
+                if command.as_os_str().is_empty() {
+                    return Err(AdasaError::MissingConfigField(
+                        "readiness.command".to_string(),
+                    ));
+                }
+            }
+            ReadinessCheck::Log { pattern } => {
+                regex::Regex::new(pattern).map_err(|e| {
+                    AdasaError::ConfigValidationError(format!(
+                        "Invalid readiness.pattern '{}': {}",
+                        pattern, e
+                    ))
+                })?;
+            }
+            ReadinessCheck::Notify => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Number of instances replaced at once during a rolling restart
+///
+/// Written either as a count (`2`) or as a percentage of the instances (`"25%"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchSize {
+    /// A fixed number of instances
+    Count(usize),
+    /// A percentage of the instances, rounded up
+    Percent(u8),
+}
+
+impl BatchSize {
+    /// Resolve the batch size for a group of `total` instances (always at least 1)
+    pub fn resolve(&self, total: usize) -> usize {
+        let size = match *self {
+            BatchSize::Count(count) => count,
+            BatchSize::Percent(percent) => (total * percent as usize).div_ceil(100),
+        };
+        size.clamp(1, total.max(1))
+    }
+
+    /// Validate the batch size
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            BatchSize::Count(0) => Err(AdasaError::ConfigValidationError(
+                "rolling_batch must be at least 1".to_string(),
+            )),
+            BatchSize::Percent(percent) if percent == 0 || percent > 100 => {
+                Err(AdasaError::ConfigValidationError(format!(
+                    "rolling_batch must be between 1% and 100%, got {}%",
+                    percent
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for BatchSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BatchSize::Count(count) => write!(f, "{}", count),
+            BatchSize::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+impl std::str::FromStr for BatchSize {
+    type Err = AdasaError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let invalid = || {
+            AdasaError::InvalidConfig(format!(
+                "Invalid batch size '{}'. Use a count such as 2 or a percentage such as 25%",
+                s
+            ))
+        };
+
+        let batch = match s.strip_suffix('%') {
+            Some(percent) => BatchSize::Percent(percent.trim().parse().map_err(|_| invalid())?),
+            None => BatchSize::Count(s.parse().map_err(|_| invalid())?),
+        };
+        batch.validate().map_err(|_| invalid())?;
+
+        Ok(batch)
+    }
+}
+
+impl Serialize for BatchSize {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            BatchSize::Count(count) => serializer.serialize_u64(*count as u64),
+            BatchSize::Percent(_) => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BatchSize {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Count(usize),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Count(count) => Ok(BatchSize::Count(count)),
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// An amount in the `limits` table, where `"unlimited"` stands for no limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RlimitAmount(pub u64);
+
+impl RlimitAmount {
+    /// No limit (`RLIM_INFINITY`)
+    pub const UNLIMITED: RlimitAmount = RlimitAmount(u64::MAX);
+}
+
+impl Serialize for RlimitAmount {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        if *self == Self::UNLIMITED {
+            serializer.serialize_str("unlimited")
+        } else {
+            serializer.serialize_u64(self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RlimitAmount {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(u64),
+            Keyword(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(amount) => Ok(RlimitAmount(amount)),
+            Raw::Keyword(keyword) if keyword == "unlimited" => Ok(Self::UNLIMITED),
+            Raw::Keyword(keyword) => Err(serde::de::Error::custom(format!(
+                "invalid limit '{}': expected a number or \"unlimited\"",
+                keyword
+            ))),
+        }
+    }
+}
+
+/// A resource limit: one amount for both the soft and the hard limit, or each separately
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum RlimitValue {
+    Both(RlimitAmount),
+    Split {
+        soft: RlimitAmount,
+        hard: RlimitAmount,
+    },
+}
+
+impl RlimitValue {
+    pub fn soft(&self) -> u64 {
+        match self {
+            RlimitValue::Both(amount) | RlimitValue::Split { soft: amount, .. } => amount.0,
+        }
+    }
+
+    pub fn hard(&self) -> u64 {
+        match self {
+            RlimitValue::Both(amount) | RlimitValue::Split { hard: amount, .. } => amount.0,
+        }
+    }
+}
+
+/// Resource limits (`setrlimit`) applied to a process before it starts
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RlimitConfig {
+    /// Maximum number of open file descriptors (`RLIMIT_NOFILE`)
+    #[serde(default)]
+    pub nofile: Option<RlimitValue>,
+
+    /// Maximum number of processes of the user (`RLIMIT_NPROC`)
+    #[serde(default)]
+    pub nproc: Option<RlimitValue>,
+
+    /// Maximum size of core dumps in bytes (`RLIMIT_CORE`)
+    #[serde(default)]
+    pub core: Option<RlimitValue>,
+
+    /// Maximum stack size in bytes (`RLIMIT_STACK`)
+    #[serde(default)]
+    pub stack: Option<RlimitValue>,
+
+    /// Maximum virtual address space in bytes (`RLIMIT_AS`)
+    #[serde(default, rename = "as")]
+    pub address_space: Option<RlimitValue>,
+}
+
+impl RlimitConfig {
+    /// All limits with their names in the configuration
+    pub fn entries(&self) -> [(&'static str, Option<RlimitValue>); 5] {
+        [
+            ("nofile", self.nofile),
+            ("nproc", self.nproc),
+            ("core", self.core),
+            ("stack", self.stack),
+            ("as", self.address_space),
+        ]
+    }
+
+    /// Validate that no soft limit is above its hard limit
+    pub fn validate(&self) -> Result<()> {
+        for (name, value) in self.entries() {
+            if let Some(value) = value {
+                if value.soft() > value.hard() {
+                    return Err(AdasaError::ConfigValidationError(format!(
+                        "limits.{}: soft limit cannot exceed the hard limit",
+                        name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// I/O scheduling class, as used by `ionice`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IoniceClass {
+    /// Served first, regardless of other processes (requires root)
+    Realtime,
+    /// The default class, with a priority level
+    BestEffort,
+    /// Only served when no other process needs the disk
+    Idle,
+}
+
+/// I/O scheduling priority of a process
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IoniceConfig {
+    /// Scheduling class
+    pub class: IoniceClass,
+
+    /// Priority within the class, from 0 (highest) to 7 (lowest); ignored for `idle`
+    #[serde(default = "default_ionice_level")]
+    pub level: u8,
+}
+
+/// Limits enforced by the kernel through the cgroup v2 of a process
+///
+/// Unlike `max_memory`, which adasa checks periodically, these limits are applied
+/// to every process in the cgroup as soon as they are exceeded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CgroupConfig {
+    /// Memory above which the kernel OOM-kills processes in the cgroup (`memory.max`)
+    #[serde(default)]
+    pub memory_max: Option<ByteSize>,
+
+    /// Memory above which the kernel throttles the cgroup and reclaims memory (`memory.high`)
+    #[serde(default)]
+    pub memory_high: Option<ByteSize>,
+
+    /// Maximum number of processes and threads in the cgroup (`pids.max`)
+    #[serde(default)]
+    pub pids_max: Option<u64>,
+
+    /// Relative I/O weight, from 1 to 10000 (kernel default: 100) (`io.weight`)
+    #[serde(default)]
+    pub io_weight: Option<u16>,
+
+    /// CPUs the cgroup may run on, e.g. `"0-3,6"` (`cpuset.cpus`)
+    #[serde(default)]
+    pub cpus: Option<String>,
+}
+
+impl CgroupConfig {
+    /// Validate the limits
+    pub fn validate(&self) -> Result<()> {
+        if let Some(weight) = self.io_weight {
+            if !(1..=10_000).contains(&weight) {
+                return Err(AdasaError::ConfigValidationError(
+                    "cgroup.io_weight must be between 1 and 10000".to_string(),
+                ));
+            }
+        }
+
+        if let (Some(high), Some(max)) = (self.memory_high, self.memory_max) {
+            if high > max {
+                return Err(AdasaError::ConfigValidationError(
+                    "cgroup.memory_high cannot exceed cgroup.memory_max".to_string(),
+                ));
+            }
+        }
+
+        if self.pids_max == Some(0) {
+            return Err(AdasaError::ConfigValidationError(
+                "cgroup.pids_max must be at least 1".to_string(),
+            ));
+        }
+
+        if let Some(ref cpus) = self.cpus {
+            let valid = !cpus.is_empty()
+                && cpus.split(',').all(|range| {
+                    let mut bounds = range.splitn(2, '-').map(|b| b.trim().parse::<usize>());
+                    match (bounds.next(), bounds.next()) {
+                        (Some(Ok(cpu)), None) => cpu < MAX_CPU_CORES,
+                        (Some(Ok(first)), Some(Ok(last))) => first <= last && last < MAX_CPU_CORES,
+                        _ => false,
+                    }
+                });
+            if !valid {
+                return Err(AdasaError::ConfigValidationError(format!(
+                    "Invalid cgroup.cpus '{}': expected a list of CPUs such as \"0-3,6\"",
+                    cpus
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// Process configuration with all settings for managing a process
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ProcessConfig {
     /// Process name (unique identifier)
     pub name: String,
@@ -49,16 +718,20 @@ pub struct ProcessConfig {
     #[serde(default = "default_max_restarts")]
     pub max_restarts: usize,
 
-    /// Delay before restart (in seconds)
-    #[serde(default = "default_restart_delay")]
-    pub restart_delay_secs: u64,
+    /// Delay before restart, e.g. `"500ms"` or a number of seconds
+    #[serde(
+        default = "default_restart_delay",
+        alias = "restart_delay_secs",
+        with = "units::duration"
+    )]
+    pub restart_delay: Duration,
 
-    /// Maximum memory limit in bytes (optional)
+    /// Maximum memory, e.g. `"512M"` or a number of bytes (optional)
     #[serde(default)]
-    pub max_memory: Option<u64>,
+    pub max_memory: Option<ByteSize>,
 
-    /// Maximum CPU usage percentage (0-100, optional)
-    #[serde(default)]
+    /// Maximum CPU usage percentage, e.g. `50` or `"50%"` (1-100, optional)
+    #[serde(default, deserialize_with = "units::deserialize_percent")]
     pub max_cpu: Option<u32>,
 
     /// Action to take when resource limits are exceeded
@@ -69,12 +742,133 @@ pub struct ProcessConfig {
     #[serde(default = "default_stop_signal")]
     pub stop_signal: String,
 
-    /// Timeout before force kill (in seconds)
-    #[serde(default = "default_stop_timeout")]
-    pub stop_timeout_secs: u64,
+    /// Timeout before force kill, e.g. `"30s"` or a number of seconds
+    #[serde(
+        default = "default_stop_timeout",
+        alias = "stop_timeout_secs",
+        with = "units::duration"
+    )]
+    pub stop_timeout: Duration,
+
+    /// Active health check (optional)
+    #[serde(default)]
+    pub health_check: Option<HealthCheckConfig>,
+
+    /// Readiness gate for rolling restarts (optional)
+    #[serde(default)]
+    pub readiness: Option<ReadinessConfig>,
+
+    /// Instances replaced at once during a rolling restart, e.g. `2` or `"25%"` (default: 1)
+    #[serde(default)]
+    pub rolling_batch: Option<BatchSize>,
+
+    /// Uptime a replacement without a readiness check needs during a rolling restart (default: 3s)
+    #[serde(default = "default_rolling_settle", with = "units::duration")]
+    pub rolling_settle: Duration,
+
+    /// Names of processes that must be running and ready before this one starts
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+
+    /// Cron expression for scheduled jobs, e.g. `"*/5 * * * *"` (optional)
+    ///
+    /// Scheduled jobs are started at each matching time instead of running continuously.
+    #[serde(default)]
+    pub schedule: Option<String>,
+
+    /// Run the process once to completion instead of keeping it running
+    #[serde(default)]
+    pub oneshot: bool,
+
+    /// Skip a scheduled run if the previous run is still in progress
+    #[serde(default)]
+    pub no_overlap: bool,
+
+    /// When to restart the process after it exits (default: `unless-stopped`, or `never` if `autorestart` is false)
+    #[serde(default)]
+    pub restart: Option<RestartMode>,
+
+    /// Exit codes besides 0 that count as a successful exit
+    #[serde(default)]
+    pub success_exit_codes: Vec<i32>,
+
+    /// Exit codes after which the process is never restarted
+    #[serde(default)]
+    pub no_restart_exit_codes: Vec<i32>,
+
+    /// Runs shorter than this count toward crash-loop detection; longer runs reset the backoff
+    #[serde(
+        default = "default_min_uptime",
+        alias = "min_uptime_secs",
+        with = "units::duration"
+    )]
+    pub min_uptime: Duration,
+
+    /// How the delay between consecutive restarts grows
+    #[serde(default)]
+    pub backoff: BackoffConfig,
+
+    /// Time window in which `max_restarts` is counted
+    #[serde(
+        default = "default_restart_window",
+        alias = "restart_window_secs",
+        with = "units::duration"
+    )]
+    pub restart_window: Duration,
+
+    /// Which processes receive the stop signal: `process`, `group` or `cgroup` (default: `group`)
+    #[serde(default)]
+    pub kill_mode: KillMode,
+
+    /// User to run the process as, by name or numeric UID (default: the daemon's user)
+    #[serde(default)]
+    pub user: Option<String>,
+
+    /// Group to run the process as, by name or numeric GID (default: the primary group of `user`)
+    #[serde(default)]
+    pub group: Option<String>,
+
+    /// Supplementary groups, by name or numeric GID (default: the groups `user` is a member of)
+    #[serde(default)]
+    pub supplementary_groups: Vec<String>,
+
+    /// Resource limits applied before the process starts: `nofile`, `nproc`, `core`, `stack` and `as`
+    #[serde(default)]
+    pub limits: RlimitConfig,
+
+    /// Scheduling priority, from -20 (highest) to 19 (lowest)
+    #[serde(default)]
+    pub nice: Option<i32>,
+
+    /// I/O scheduling class and priority (optional)
+    #[serde(default)]
+    pub ionice: Option<IoniceConfig>,
+
+    /// CPU cores the process may run on (default: all)
+    #[serde(default)]
+    pub cpu_affinity: Vec<usize>,
+
+    /// Kernel-enforced cgroup v2 limits: `memory_max`, `memory_high`, `pids_max`, `io_weight` and `cpus`
+    #[serde(default)]
+    pub cgroup: Option<CgroupConfig>,
+}
+
+/// Identity a process runs as, resolved from `user`, `group` and `supplementary_groups`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub uid: u32,
+    pub gid: u32,
+    /// Supplementary groups, or `None` to keep those of the daemon
+    pub groups: Option<Vec<u32>>,
+    /// Login name and home directory, if the user has a passwd entry
+    pub account: Option<(String, PathBuf)>,
 }
 
 // Default value functions for serde
+fn default_ionice_level() -> u8 {
+    4
+}
+
 fn default_instances() -> usize {
     1
 }
@@ -87,140 +881,406 @@ fn default_max_restarts() -> usize {
     10
 }
 
-fn default_restart_delay() -> u64 {
-    1
+fn default_restart_delay() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_min_uptime() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_restart_window() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_rolling_settle() -> Duration {
+    Duration::from_secs(3)
 }
 
 fn default_stop_signal() -> String {
     "SIGTERM".to_string()
 }
 
-fn default_stop_timeout() -> u64 {
-    10
+fn default_stop_timeout() -> Duration {
+    Duration::from_secs(10)
 }
 
 fn default_limit_action() -> LimitAction {
     LimitAction::Log
 }
 
+impl Default for ProcessConfig {
+    /// A process with every setting at the default used for config files, and no name or script
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            script: PathBuf::new(),
+            args: Vec::new(),
+            cwd: None,
+            env: HashMap::new(),
+            instances: default_instances(),
+            autorestart: default_autorestart(),
+            max_restarts: default_max_restarts(),
+            restart_delay: default_restart_delay(),
+            max_memory: None,
+            max_cpu: None,
+            limit_action: default_limit_action(),
+            stop_signal: default_stop_signal(),
+            stop_timeout: default_stop_timeout(),
+            health_check: None,
+            readiness: None,
+            rolling_batch: None,
+            rolling_settle: default_rolling_settle(),
+            depends_on: Vec::new(),
+            schedule: None,
+            oneshot: false,
+            no_overlap: false,
+            restart: None,
+            success_exit_codes: Vec::new(),
+            no_restart_exit_codes: Vec::new(),
+            min_uptime: default_min_uptime(),
+            backoff: BackoffConfig::default(),
+            restart_window: default_restart_window(),
+            kill_mode: KillMode::default(),
+            user: None,
+            group: None,
+            supplementary_groups: Vec::new(),
+            limits: RlimitConfig::default(),
+            nice: None,
+            ionice: None,
+            cpu_affinity: Vec::new(),
+            cgroup: None,
+        }
+    }
+}
+
+fn default_probe_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_health_interval() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_health_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn default_failure_threshold() -> u32 {
+    3
+}
+
+fn default_readiness_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
 impl ProcessConfig {
-    /// Load process configurations from a file (supports TOML and JSON)
+    /// Create a configuration for `script` with every other setting at its default
+    pub fn new(name: impl Into<String>, script: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            script: script.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Load process configurations from a file (supports TOML, YAML and JSON)
+    ///
+    /// Files listed under `include` are loaded along with it. Environment variables are expanded and every configuration is validated.
+    /// Use [`file::check_file`] to get every problem instead of the first one.
     pub fn from_file(path: &Path) -> Result<Vec<ProcessConfig>> {
-        // Read file contents
-        let contents = std::fs::read_to_string(path)
-            .map_err(|e| AdasaError::ConfigError(format!("Failed to read config file: {}", e)))?;
-
-        // Determine format based on file extension
-        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
-
-        let configs = match extension {
-            "toml" => Self::parse_toml(&contents)?,
-            "json" => Self::parse_json(&contents)?,
-            _ => {
-                return Err(AdasaError::InvalidConfig(format!(
-                    "Unsupported file format: {}. Use .toml or .json",
-                    extension
-                )))
-            }
-        };
+        Self::from_file_with_profile(path, None)
+    }
 
-        // Expand environment variables in all configs
-        let expanded_configs: Vec<ProcessConfig> = configs
-            .into_iter()
-            .map(|mut config| {
-                config.expand_env_vars();
-                config
-            })
-            .collect();
+    /// Load process configurations from a file with an environment profile applied
+    ///
+    /// The profile is one of the `env.<profile>` tables of the file; see [`file::ConfigFile`].
+    pub fn from_file_with_profile(
+        path: &Path,
+        profile: Option<&str>,
+    ) -> Result<Vec<ProcessConfig>> {
+        file::check_file(path, profile).map_err(|mut issues| issues.remove(0).error)
+    }
+
+    /// Deserialize one process entry of a config file
+    fn from_entry(entry: serde_json::Value) -> Result<ProcessConfig> {
+        serde_json::from_value(entry).map_err(|e| AdasaError::InvalidConfig(e.to_string()))
+    }
 
-        // Validate all configs
-        for config in &expanded_configs {
-            config.validate()?;
+    /// Get the effective restart mode, falling back to `autorestart` when `restart` is not set
+    pub fn restart_mode(&self) -> RestartMode {
+        match self.restart {
+            Some(mode) => mode,
+            None if self.autorestart => RestartMode::UnlessStopped,
+            None => RestartMode::Never,
         }
+    }
 
-        Ok(expanded_configs)
+    /// Check whether the process runs to completion (scheduled or one-shot) instead of continuously
+    pub fn is_job(&self) -> bool {
+        self.schedule.is_some() || self.oneshot
     }
 
-    /// Parse TOML configuration file
-    fn parse_toml(contents: &str) -> Result<Vec<ProcessConfig>> {
-        #[derive(Deserialize)]
-        struct ConfigFile {
-            #[serde(default)]
-            processes: Vec<ProcessConfig>,
-            #[serde(flatten)]
-            single: Option<ProcessConfig>,
+    /// Check whether the process needs its own cgroup, for `max_cpu`, the `cgroup` kill mode or `cgroup` limits
+    pub fn uses_cgroup(&self) -> bool {
+        self.max_cpu.is_some() || self.kill_mode == KillMode::Cgroup || self.cgroup.is_some()
+    }
+
+    /// Get the first scheduled run strictly after `after`
+    ///
+    /// Returns `None` for processes without a schedule, or if the schedule never matches again.
+    pub fn next_run_after(&self, after: SystemTime) -> Option<SystemTime> {
+        let cron = Self::parse_schedule(self.schedule.as_ref()?).ok()?;
+        let after: DateTime<Local> = after.into();
+
+        cron.find_next_occurrence(&after, false)
+            .ok()
+            .map(SystemTime::from)
+    }
+
+    /// Resolve the user and groups the process runs as
+    ///
+    /// Returns `None` if none of `user`, `group` and `supplementary_groups` is set.
+    /// Fails if a user or group does not exist.
+    pub fn credentials(&self) -> Result<Option<Credentials>> {
+        if self.user.is_none() && self.group.is_none() && self.supplementary_groups.is_empty() {
+            return Ok(None);
         }
 
-        let config_file: ConfigFile = toml::from_str(contents)
-            .map_err(|e| AdasaError::InvalidConfig(format!("Failed to parse TOML: {}", e)))?;
+        let (uid, account) = match self.user {
+            Some(ref user) => Self::resolve_user(user)?,
+            None => (Uid::current().as_raw(), None),
+        };
 
-        // Support both single process and array of processes
-        if let Some(single) = config_file.single {
-            Ok(vec![single])
-        } else if !config_file.processes.is_empty() {
-            Ok(config_file.processes)
+        let gid = match (&self.group, &account) {
+            (Some(group), _) => Self::resolve_group(group)?,
+            (None, Some(user)) => user.gid.as_raw(),
+            (None, None) => Gid::current().as_raw(),
+        };
+
+        let groups = if !self.supplementary_groups.is_empty() {
+            let groups = self
+                .supplementary_groups
+                .iter()
+                .map(|group| Self::resolve_group(group))
+                .collect::<Result<Vec<_>>>()?;
+            Some(groups)
+        } else if let Some(ref user) = account {
+            Some(Self::member_groups(user, gid)?)
+        } else if self.user.is_some() {
+            // A UID without a passwd entry is a member of no other group
+            Some(Vec::new())
         } else {
-            Err(AdasaError::InvalidConfig(
-                "No process configuration found in file".to_string(),
-            ))
+            None
+        };
+
+        Ok(Some(Credentials {
+            uid,
+            gid,
+            groups,
+            account: account.map(|user| (user.name, user.dir)),
+        }))
+    }
+
+    /// Look up a user by name or UID
+    ///
+    /// A numeric UID is accepted even without a passwd entry.
+    fn resolve_user(user: &str) -> Result<(u32, Option<User>)> {
+        let lookup_failed = |e| {
+            AdasaError::ConfigValidationError(format!("Failed to look up user '{}': {}", user, e))
+        };
+
+        if let Ok(uid) = user.parse::<u32>() {
+            let found = User::from_uid(Uid::from_raw(uid)).map_err(lookup_failed)?;
+            return Ok((uid, found));
+        }
+
+        match User::from_name(user).map_err(lookup_failed)? {
+            Some(found) => Ok((found.uid.as_raw(), Some(found))),
+            None => Err(AdasaError::ConfigValidationError(format!(
+                "Unknown user: {}",
+                user
+            ))),
         }
     }
 
-    /// Parse JSON configuration file
-    fn parse_json(contents: &str) -> Result<Vec<ProcessConfig>> {
-        #[derive(Deserialize)]
-        #[serde(untagged)]
-        enum ConfigFile {
-            Single(ProcessConfig),
-            Multiple { processes: Vec<ProcessConfig> },
+    /// Look up a group by name or GID, returning its GID
+    fn resolve_group(group: &str) -> Result<u32> {
+        if let Ok(gid) = group.parse::<u32>() {
+            return Ok(gid);
         }
 
-        let config_file: ConfigFile = serde_json::from_str(contents)
-            .map_err(|e| AdasaError::InvalidConfig(format!("Failed to parse JSON: {}", e)))?;
+        match Group::from_name(group) {
+            Ok(Some(found)) => Ok(found.gid.as_raw()),
+            Ok(None) => Err(AdasaError::ConfigValidationError(format!(
+                "Unknown group: {}",
+                group
+            ))),
+            Err(e) => Err(AdasaError::ConfigValidationError(format!(
+                "Failed to look up group '{}': {}",
+                group, e
+            ))),
+        }
+    }
 
-        match config_file {
-            ConfigFile::Single(config) => Ok(vec![config]),
-            ConfigFile::Multiple { processes } => {
-                if processes.is_empty() {
-                    Err(AdasaError::InvalidConfig(
-                        "No process configuration found in file".to_string(),
-                    ))
-                } else {
-                    Ok(processes)
+    /// Groups `user` is a member of, including `gid`
+    fn member_groups(user: &User, gid: u32) -> Result<Vec<u32>> {
+        let name = CString::new(user.name.as_str()).map_err(|_| {
+            AdasaError::ConfigValidationError(format!("Invalid user name: {}", user.name))
+        })?;
+
+        getgrouplist(&name, Gid::from_raw(gid))
+            .map(|groups| groups.into_iter().map(Gid::as_raw).collect())
+            .map_err(|e| {
+                AdasaError::ConfigValidationError(format!(
+                    "Failed to look up groups of user '{}': {}",
+                    user.name, e
+                ))
+            })
+    }
+
+    /// Parse a cron expression (5 fields, or 6 with leading seconds)
+    fn parse_schedule(schedule: &str) -> Result<Cron> {
+        Cron::new(schedule)
+            .with_seconds_optional()
+            .parse()
+            .map_err(|e| {
+                AdasaError::ConfigValidationError(format!("Invalid schedule '{}': {}", schedule, e))
+            })
+    }
+
+    /// Validate `depends_on` across a set of configurations
+    ///
+    /// Rejects dependency cycles. Dependencies that are not part of `configs`
+    /// are allowed, since they may refer to processes that are already running.
+    pub fn validate_dependencies(configs: &[ProcessConfig]) -> Result<()> {
+        Self::dependency_order(configs).map(|_| ())
+    }
+
+    /// Sort configurations so every process comes after the processes it depends on
+    ///
+    /// The file order is kept wherever dependencies allow it.
+    pub fn sort_by_dependencies(configs: Vec<ProcessConfig>) -> Result<Vec<ProcessConfig>> {
+        let order = Self::dependency_order(&configs)?;
+        let mut slots: Vec<Option<ProcessConfig>> = configs.into_iter().map(Some).collect();
+
+        Ok(order
+            .into_iter()
+            .filter_map(|idx| slots[idx].take())
+            .collect())
+    }
+
+    /// Compute a topological order of `configs` as indices into the slice
+    fn dependency_order(configs: &[ProcessConfig]) -> Result<Vec<usize>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            Unvisited,
+            Visiting,
+            Done,
+        }
+
+        fn visit(
+            idx: usize,
+            configs: &[ProcessConfig],
+            by_name: &HashMap<&str, usize>,
+            marks: &mut [Mark],
+            path: &mut Vec<usize>,
+            order: &mut Vec<usize>,
+        ) -> Result<()> {
+            match marks[idx] {
+                Mark::Done => return Ok(()),
+                Mark::Visiting => {
+                    let start = path.iter().position(|&i| i == idx).unwrap_or(0);
+                    let cycle: Vec<&str> = path[start..]
+                        .iter()
+                        .chain(std::iter::once(&idx))
+                        .map(|&i| configs[i].name.as_str())
+                        .collect();
+                    return Err(AdasaError::ConfigValidationError(format!(
+                        "Dependency cycle detected: {}",
+                        cycle.join(" -> ")
+                    )));
                 }
+                Mark::Unvisited => {}
             }
+
+            marks[idx] = Mark::Visiting;
+            path.push(idx);
+            for dependency in &configs[idx].depends_on {
+                if let Some(&dep_idx) = by_name.get(dependency.as_str()) {
+                    visit(dep_idx, configs, by_name, marks, path, order)?;
+                }
+            }
+            path.pop();
+            marks[idx] = Mark::Done;
+            order.push(idx);
+
+            Ok(())
         }
+
+        let by_name: HashMap<&str, usize> = configs
+            .iter()
+            .enumerate()
+            .map(|(idx, config)| (config.name.as_str(), idx))
+            .collect();
+        let mut marks = vec![Mark::Unvisited; configs.len()];
+        let mut path = Vec::new();
+        let mut order = Vec::with_capacity(configs.len());
+
+        for idx in 0..configs.len() {
+            visit(idx, configs, &by_name, &mut marks, &mut path, &mut order)?;
+        }
+
+        Ok(order)
     }
 
     /// Validate the configuration
     pub fn validate(&self) -> Result<()> {
+        match self.validation_errors().into_iter().next() {
+            Some((_, error)) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Every problem with the configuration, with the field it was found in
+    ///
+    /// Unlike [`validate`](Self::validate), this does not stop at the first problem.
+    pub fn validation_errors(&self) -> Vec<(&'static str, AdasaError)> {
+        let mut errors = Vec::new();
+
         // Validate name
         if self.name.is_empty() {
-            return Err(AdasaError::MissingConfigField("name".to_string()));
+            errors.push(("name", AdasaError::MissingConfigField("name".to_string())));
         }
 
         // Validate script path
         if self.script.as_os_str().is_empty() {
-            return Err(AdasaError::MissingConfigField("script".to_string()));
+            errors.push((
+                "script",
+                AdasaError::MissingConfigField("script".to_string()),
+            ));
         }
 
         // Validate instances
         if self.instances == 0 {
-            return Err(AdasaError::ConfigValidationError(
-                "instances must be at least 1".to_string(),
+            errors.push((
+                "instances",
+                AdasaError::ConfigValidationError("instances must be at least 1".to_string()),
             ));
         }
 
         if self.instances > 100 {
-            return Err(AdasaError::ConfigValidationError(
-                "instances cannot exceed 100".to_string(),
+            errors.push((
+                "instances",
+                AdasaError::ConfigValidationError("instances cannot exceed 100".to_string()),
             ));
         }
 
         // Validate max_restarts
         if self.max_restarts == 0 {
-            return Err(AdasaError::ConfigValidationError(
-                "max_restarts must be at least 1".to_string(),
+            errors.push((
+                "max_restarts",
+                AdasaError::ConfigValidationError("max_restarts must be at least 1".to_string()),
             ));
         }
 
@@ -229,39 +1289,187 @@ impl ProcessConfig {
             "SIGTERM", "SIGINT", "SIGQUIT", "SIGKILL", "SIGHUP", "SIGUSR1", "SIGUSR2",
         ];
         if !valid_signals.contains(&self.stop_signal.as_str()) {
-            return Err(AdasaError::ConfigValidationError(format!(
-                "Invalid stop_signal: {}. Must be one of: {}",
-                self.stop_signal,
-                valid_signals.join(", ")
-            )));
+            errors.push((
+                "stop_signal",
+                AdasaError::ConfigValidationError(format!(
+                    "Invalid stop_signal: {}. Must be one of: {}",
+                    self.stop_signal,
+                    valid_signals.join(", ")
+                )),
+            ));
         }
 
         // Validate working directory exists if specified
         if let Some(ref cwd) = self.cwd {
             if !cwd.exists() {
-                return Err(AdasaError::ConfigValidationError(format!(
-                    "Working directory does not exist: {}",
-                    cwd.display()
-                )));
-            }
-            if !cwd.is_dir() {
-                return Err(AdasaError::ConfigValidationError(format!(
-                    "Working directory is not a directory: {}",
-                    cwd.display()
-                )));
+                errors.push((
+                    "cwd",
+                    AdasaError::ConfigValidationError(format!(
+                        "Working directory does not exist: {}",
+                        cwd.display()
+                    )),
+                ));
+            } else if !cwd.is_dir() {
+                errors.push((
+                    "cwd",
+                    AdasaError::ConfigValidationError(format!(
+                        "Working directory is not a directory: {}",
+                        cwd.display()
+                    )),
+                ));
             }
         }
 
         // Validate CPU limit if specified
         if let Some(cpu) = self.max_cpu {
             if cpu == 0 || cpu > 100 {
-                return Err(AdasaError::ConfigValidationError(
-                    "max_cpu must be between 1 and 100".to_string(),
+                errors.push((
+                    "max_cpu",
+                    AdasaError::ConfigValidationError(
+                        "max_cpu must be between 1 and 100".to_string(),
+                    ),
                 ));
             }
         }
 
-        Ok(())
+        // Validate dependencies
+        for dependency in &self.depends_on {
+            if dependency.is_empty() {
+                errors.push((
+                    "depends_on",
+                    AdasaError::ConfigValidationError(
+                        "depends_on entries cannot be empty".to_string(),
+                    ),
+                ));
+            } else if *dependency == self.name {
+                errors.push((
+                    "depends_on",
+                    AdasaError::ConfigValidationError(format!(
+                        "Dependency cycle detected: {} -> {}",
+                        self.name, dependency
+                    )),
+                ));
+            }
+        }
+
+        // Validate schedule
+        if let Some(ref schedule) = self.schedule {
+            if let Err(e) = Self::parse_schedule(schedule) {
+                errors.push(("schedule", e));
+            }
+
+            if self.oneshot {
+                errors.push((
+                    "oneshot",
+                    AdasaError::ConfigValidationError(
+                        "schedule and oneshot cannot be combined".to_string(),
+                    ),
+                ));
+            }
+        }
+
+        if self.restart_window.is_zero() {
+            errors.push((
+                "restart_window",
+                AdasaError::ConfigValidationError(
+                    "restart_window must be greater than zero".to_string(),
+                ),
+            ));
+        }
+
+        if let Err(e) = self.backoff.validate() {
+            errors.push(("backoff", e));
+        }
+
+        // Validate user and groups
+        if let Err(e) = self.credentials() {
+            let field = if self.user.is_some() { "user" } else { "group" };
+            errors.push((field, e));
+        }
+
+        // Validate limits and scheduling
+        if let Err(e) = self.limits.validate() {
+            errors.push(("limits", e));
+        }
+
+        if let Some(nice) = self.nice {
+            if !(-20..=19).contains(&nice) {
+                errors.push((
+                    "nice",
+                    AdasaError::ConfigValidationError(
+                        "nice must be between -20 and 19".to_string(),
+                    ),
+                ));
+            }
+        }
+
+        if let Some(ref ionice) = self.ionice {
+            if ionice.level > 7 {
+                errors.push((
+                    "ionice",
+                    AdasaError::ConfigValidationError(
+                        "ionice level must be between 0 and 7".to_string(),
+                    ),
+                ));
+            }
+        }
+
+        if let Some(core) = self
+            .cpu_affinity
+            .iter()
+            .find(|core| **core >= MAX_CPU_CORES)
+        {
+            errors.push((
+                "cpu_affinity",
+                AdasaError::ConfigValidationError(format!(
+                    "Invalid cpu_affinity core {}: must be below {}",
+                    core, MAX_CPU_CORES
+                )),
+            ));
+        }
+
+        if let Some(ref cgroup) = self.cgroup {
+            if let Err(e) = cgroup.validate() {
+                errors.push(("cgroup", e));
+            }
+        }
+
+        // Validate exit codes
+        for (field, codes) in [
+            ("success_exit_codes", &self.success_exit_codes),
+            ("no_restart_exit_codes", &self.no_restart_exit_codes),
+        ] {
+            if let Some(code) = codes.iter().find(|code| !(0..=255).contains(*code)) {
+                errors.push((
+                    field,
+                    AdasaError::ConfigValidationError(format!(
+                        "Invalid exit code {}: must be between 0 and 255",
+                        code
+                    )),
+                ));
+            }
+        }
+
+        // Validate health check if specified
+        if let Some(ref health_check) = self.health_check {
+            if let Err(e) = health_check.validate() {
+                errors.push(("health_check", e));
+            }
+        }
+
+        if let Some(ref readiness) = self.readiness {
+            if let Err(e) = readiness.validate() {
+                errors.push(("readiness", e));
+            }
+        }
+
+        if let Some(ref rolling_batch) = self.rolling_batch {
+            if let Err(e) = rolling_batch.validate() {
+                errors.push(("rolling_batch", e));
+            }
+        }
+
+        errors
     }
 
     /// Expand environment variables in configuration fields
@@ -308,49 +1516,47 @@ impl ProcessConfig {
         let expanded = Self::expand_env_in_string(&path_str);
         PathBuf::from(expanded)
     }
-
-    /// Get restart delay as Duration
-    pub fn restart_delay(&self) -> Duration {
-        Duration::from_secs(self.restart_delay_secs)
-    }
-
-    /// Get stop timeout as Duration
-    pub fn stop_timeout(&self) -> Duration {
-        Duration::from_secs(self.stop_timeout_secs)
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
+    use file::{ConfigFile, Format};
     use std::fs;
     use tempfile::TempDir;
 
+    fn parse(contents: &str, format: Format) -> Result<Vec<ProcessConfig>> {
+        let file = ConfigFile::parse(contents, format, None).map_err(|issue| issue.error)?;
+        file.entries
+            .iter()
+            .map(|entry| file.resolve(entry).and_then(ProcessConfig::from_entry))
+            .collect()
+    }
+
+    fn parse_toml(contents: &str) -> Result<Vec<ProcessConfig>> {
+        parse(contents, Format::Toml)
+    }
+
+    fn parse_json(contents: &str) -> Result<Vec<ProcessConfig>> {
+        parse(contents, Format::Json)
+    }
+
     #[test]
     fn test_process_config_defaults() {
-        let config = ProcessConfig {
-            name: "test".to_string(),
-            script: PathBuf::from("/bin/echo"),
-            args: vec![],
-            cwd: None,
-            env: HashMap::new(),
-            instances: default_instances(),
-            autorestart: default_autorestart(),
-            max_restarts: default_max_restarts(),
-            restart_delay_secs: default_restart_delay(),
-            max_memory: None,
-            max_cpu: None,
-            limit_action: default_limit_action(),
-            stop_signal: default_stop_signal(),
-            stop_timeout_secs: default_stop_timeout(),
-        };
+        let config = ProcessConfig::new("test", "/bin/echo");
+
+        // The defaults match what a config file gets when it omits every optional field
+        let parsed: ProcessConfig =
+            serde_json::from_str(r#"{"name": "test", "script": "/bin/echo"}"#).unwrap();
+        assert_eq!(config, parsed);
 
         assert_eq!(config.instances, 1);
-        assert_eq!(config.autorestart, true);
+        assert!(config.autorestart);
         assert_eq!(config.max_restarts, 10);
-        assert_eq!(config.restart_delay_secs, 1);
+        assert_eq!(config.restart_delay, Duration::from_secs(1));
         assert_eq!(config.stop_signal, "SIGTERM");
-        assert_eq!(config.stop_timeout_secs, 10);
+        assert_eq!(config.stop_timeout, Duration::from_secs(10));
     }
 
     #[test]
@@ -358,18 +1564,7 @@ mod tests {
         let config = ProcessConfig {
             name: "test".to_string(),
             script: PathBuf::from("/bin/echo"),
-            args: vec![],
-            cwd: None,
-            env: HashMap::new(),
-            instances: 1,
-            autorestart: true,
-            max_restarts: 10,
-            restart_delay_secs: 1,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: default_limit_action(),
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 10,
+            ..Default::default()
         };
 
         assert!(config.validate().is_ok());
@@ -380,18 +1575,7 @@ mod tests {
         let config = ProcessConfig {
             name: "".to_string(),
             script: PathBuf::from("/bin/echo"),
-            args: vec![],
-            cwd: None,
-            env: HashMap::new(),
-            instances: 1,
-            autorestart: true,
-            max_restarts: 10,
-            restart_delay_secs: 1,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: default_limit_action(),
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 10,
+            ..Default::default()
         };
 
         assert!(matches!(
@@ -405,18 +1589,8 @@ mod tests {
         let config = ProcessConfig {
             name: "test".to_string(),
             script: PathBuf::from("/bin/echo"),
-            args: vec![],
-            cwd: None,
-            env: HashMap::new(),
             instances: 0,
-            autorestart: true,
-            max_restarts: 10,
-            restart_delay_secs: 1,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: default_limit_action(),
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 10,
+            ..Default::default()
         };
 
         assert!(matches!(
@@ -430,18 +1604,8 @@ mod tests {
         let config = ProcessConfig {
             name: "test".to_string(),
             script: PathBuf::from("/bin/echo"),
-            args: vec![],
-            cwd: None,
-            env: HashMap::new(),
-            instances: 1,
-            autorestart: true,
-            max_restarts: 10,
-            restart_delay_secs: 1,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: default_limit_action(),
             stop_signal: "INVALID".to_string(),
-            stop_timeout_secs: 10,
+            ..Default::default()
         };
 
         assert!(matches!(
@@ -465,15 +1629,7 @@ mod tests {
                 map.insert("KEY".to_string(), "$TEST_VAR".to_string());
                 map
             },
-            instances: 1,
-            autorestart: true,
-            max_restarts: 10,
-            restart_delay_secs: 1,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: default_limit_action(),
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 10,
+            ..Default::default()
         };
 
         config.expand_env_vars();
@@ -494,7 +1650,7 @@ mod tests {
             autorestart = true
         "#;
 
-        let configs = ProcessConfig::parse_toml(toml_content).unwrap();
+        let configs = parse_toml(toml_content).unwrap();
         assert_eq!(configs.len(), 1);
         assert_eq!(configs[0].name, "my-app");
         assert_eq!(configs[0].instances, 2);
@@ -514,7 +1670,7 @@ mod tests {
             args = ["worker.py"]
         "#;
 
-        let configs = ProcessConfig::parse_toml(toml_content).unwrap();
+        let configs = parse_toml(toml_content).unwrap();
         assert_eq!(configs.len(), 2);
         assert_eq!(configs[0].name, "app1");
         assert_eq!(configs[1].name, "app2");
@@ -531,7 +1687,7 @@ mod tests {
             }
         "#;
 
-        let configs = ProcessConfig::parse_json(json_content).unwrap();
+        let configs = parse_json(json_content).unwrap();
         assert_eq!(configs.len(), 1);
         assert_eq!(configs[0].name, "my-app");
         assert_eq!(configs[0].instances, 2);
@@ -556,12 +1712,615 @@ mod tests {
             }
         "#;
 
-        let configs = ProcessConfig::parse_json(json_content).unwrap();
+        let configs = parse_json(json_content).unwrap();
         assert_eq!(configs.len(), 2);
         assert_eq!(configs[0].name, "app1");
         assert_eq!(configs[1].name, "app2");
     }
 
+    #[test]
+    fn test_parse_toml_health_check() {
+        let toml_content = r#"
+            name = "api"
+            script = "/usr/bin/node"
+
+            [health_check]
+            type = "http"
+            url = "http://127.0.0.1:3000/health"
+            interval_secs = 15
+            failure_threshold = 5
+        "#;
+
+        let configs = parse_toml(toml_content).unwrap();
+        let health_check = configs[0].health_check.as_ref().unwrap();
+        assert_eq!(
+            health_check.probe,
+            HealthProbe::Http {
+                url: "http://127.0.0.1:3000/health".to_string(),
+                expected_status: None,
+            }
+        );
+        assert_eq!(health_check.interval, Duration::from_secs(15));
+        assert_eq!(health_check.timeout, Duration::from_secs(5));
+        assert_eq!(health_check.failure_threshold, 5);
+    }
+
+    #[test]
+    fn test_parse_json_health_check() {
+        let json_content = r#"
+            {
+                "name": "db-proxy",
+                "script": "/usr/bin/proxy",
+                "health_check": { "type": "tcp", "port": 5432 }
+            }
+        "#;
+
+        let configs = parse_json(json_content).unwrap();
+        let health_check = configs[0].health_check.as_ref().unwrap();
+        assert_eq!(
+            health_check.probe,
+            HealthProbe::Tcp {
+                host: "127.0.0.1".to_string(),
+                port: 5432,
+            }
+        );
+        assert_eq!(health_check.interval, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_validate_health_check() {
+        let mut health_check = HealthCheckConfig {
+            probe: HealthProbe::Exec {
+                command: PathBuf::from("/bin/true"),
+                args: vec![],
+            },
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(5),
+            failure_threshold: 3,
+        };
+        assert!(health_check.validate().is_ok());
+
+        health_check.failure_threshold = 0;
+        assert!(health_check.validate().is_err());
+
+        health_check.failure_threshold = 3;
+        health_check.probe = HealthProbe::Http {
+            url: "https://example.com/health".to_string(),
+            expected_status: None,
+        };
+        assert!(matches!(
+            health_check.validate(),
+            Err(AdasaError::ConfigValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_toml_readiness() {
+        let toml_content = r#"
+            name = "web"
+            script = "/usr/bin/node"
+            instances = 4
+            rolling_batch = "25%"
+            rolling_settle = "500ms"
+
+            [readiness]
+            type = "log"
+            pattern = "listening on port \\d+"
+            timeout_secs = 20
+        "#;
+
+        let configs = parse_toml(toml_content).unwrap();
+        let readiness = configs[0].readiness.as_ref().unwrap();
+        assert_eq!(
+            readiness.check,
+            ReadinessCheck::Log {
+                pattern: r"listening on port \d+".to_string(),
+            }
+        );
+        assert_eq!(readiness.timeout, Duration::from_secs(20));
+        assert_eq!(configs[0].rolling_batch, Some(BatchSize::Percent(25)));
+        assert_eq!(configs[0].rolling_settle, Duration::from_millis(500));
+        assert!(configs[0].validate().is_ok());
+    }
+
+    #[test]
+    fn test_parse_json_readiness() {
+        let json_content = r#"
+            {
+                "name": "worker",
+                "script": "/usr/bin/worker",
+                "rolling_batch": 2,
+                "readiness": { "type": "notify" }
+            }
+        "#;
+
+        let configs = parse_json(json_content).unwrap();
+        let readiness = configs[0].readiness.as_ref().unwrap();
+        assert_eq!(readiness.check, ReadinessCheck::Notify);
+        assert_eq!(readiness.timeout, Duration::from_secs(30));
+        assert_eq!(configs[0].rolling_batch, Some(BatchSize::Count(2)));
+    }
+
+    #[test]
+    fn test_validate_readiness() {
+        let mut readiness = ReadinessConfig {
+            check: ReadinessCheck::Log {
+                pattern: "ready(".to_string(),
+            },
+            timeout: Duration::from_secs(30),
+        };
+        assert!(matches!(
+            readiness.validate(),
+            Err(AdasaError::ConfigValidationError(_))
+        ));
+
+        readiness.check = ReadinessCheck::Tcp {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+        };
+        assert!(readiness.validate().is_ok());
+
+        readiness.timeout = Duration::from_secs(0);
+        assert!(readiness.validate().is_err());
+    }
+
+    #[test]
+    fn test_batch_size() {
+        assert_eq!("3".parse::<BatchSize>().unwrap(), BatchSize::Count(3));
+        assert_eq!("25%".parse::<BatchSize>().unwrap(), BatchSize::Percent(25));
+        assert!("0".parse::<BatchSize>().is_err());
+        assert!("150%".parse::<BatchSize>().is_err());
+        assert!("half".parse::<BatchSize>().is_err());
+
+        assert_eq!(BatchSize::Percent(25).resolve(4), 1);
+        assert_eq!(BatchSize::Percent(25).resolve(6), 2);
+        assert_eq!(BatchSize::Percent(1).resolve(3), 1);
+        assert_eq!(BatchSize::Count(10).resolve(4), 4);
+
+        assert_eq!(
+            serde_json::to_string(&BatchSize::Percent(50)).unwrap(),
+            "\"50%\""
+        );
+        assert_eq!(serde_json::to_string(&BatchSize::Count(2)).unwrap(), "2");
+    }
+
+    #[test]
+    fn test_parse_toml_depends_on() {
+        let toml_content = r#"
+            [[processes]]
+            name = "api"
+            script = "/usr/bin/api"
+            depends_on = ["db-proxy"]
+
+            [[processes]]
+            name = "db-proxy"
+            script = "/usr/bin/proxy"
+        "#;
+
+        let configs = parse_toml(toml_content).unwrap();
+        assert_eq!(configs[0].depends_on, vec!["db-proxy".to_string()]);
+        assert!(configs[1].depends_on.is_empty());
+
+        let ordered = ProcessConfig::sort_by_dependencies(configs).unwrap();
+        let names: Vec<&str> = ordered.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["db-proxy", "api"]);
+    }
+
+    #[test]
+    fn test_sort_by_dependencies_keeps_file_order() {
+        let toml_content = r#"
+            [[processes]]
+            name = "worker"
+            script = "/usr/bin/worker"
+            depends_on = ["queue", "cache"]
+
+            [[processes]]
+            name = "web"
+            script = "/usr/bin/web"
+
+            [[processes]]
+            name = "cache"
+            script = "/usr/bin/cache"
+
+            [[processes]]
+            name = "queue"
+            script = "/usr/bin/queue"
+            depends_on = ["external-service"]
+        "#;
+
+        let configs = parse_toml(toml_content).unwrap();
+        let ordered = ProcessConfig::sort_by_dependencies(configs).unwrap();
+        let names: Vec<&str> = ordered.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["queue", "cache", "worker", "web"]);
+    }
+
+    #[test]
+    fn test_parse_toml_schedule_and_oneshot() {
+        let toml_content = r#"
+            [[processes]]
+            name = "backup"
+            script = "/usr/bin/backup"
+            schedule = "30 2 * * *"
+            no_overlap = true
+
+            [[processes]]
+            name = "migrate"
+            script = "/usr/bin/migrate"
+            oneshot = true
+        "#;
+
+        let configs = parse_toml(toml_content).unwrap();
+        assert_eq!(configs[0].schedule.as_deref(), Some("30 2 * * *"));
+        assert!(configs[0].no_overlap);
+        assert!(configs[0].is_job());
+        assert!(configs[1].oneshot);
+        assert!(configs[1].is_job());
+
+        let after = Local.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let next: DateTime<Local> = configs[0].next_run_after(after.into()).unwrap().into();
+        assert_eq!(next, Local.with_ymd_and_hms(2024, 1, 2, 2, 30, 0).unwrap());
+        assert!(configs[1].next_run_after(after.into()).is_none());
+    }
+
+    #[test]
+    fn test_validate_schedule() {
+        let toml_content = r#"
+            [[processes]]
+            name = "job"
+            script = "/bin/true"
+            schedule = "*/10 * * * * *"
+        "#;
+
+        let mut config = parse_toml(toml_content).unwrap().remove(0);
+        assert!(config.validate().is_ok());
+
+        config.schedule = Some("every minute".to_string());
+        assert!(matches!(
+            config.validate(),
+            Err(AdasaError::ConfigValidationError(_))
+        ));
+
+        config.schedule = Some("0 * * * *".to_string());
+        config.oneshot = true;
+        assert!(matches!(
+            config.validate(),
+            Err(AdasaError::ConfigValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_toml_restart_mode() {
+        let toml_content = r#"
+            [[processes]]
+            name = "worker"
+            script = "/bin/true"
+            restart = "on-failure"
+            success_exit_codes = [3]
+            no_restart_exit_codes = [78]
+
+            [[processes]]
+            name = "legacy"
+            script = "/bin/true"
+            autorestart = false
+        "#;
+
+        let configs = parse_toml(toml_content).unwrap();
+        assert_eq!(configs[0].restart_mode(), RestartMode::OnFailure);
+        assert_eq!(configs[0].success_exit_codes, vec![3]);
+        assert_eq!(configs[0].no_restart_exit_codes, vec![78]);
+        assert_eq!(configs[1].restart_mode(), RestartMode::Never);
+        assert_eq!(
+            ProcessConfig::new("web", "/bin/true").restart_mode(),
+            RestartMode::UnlessStopped
+        );
+
+        let mut config = configs[0].clone();
+        assert!(config.validate().is_ok());
+        config.no_restart_exit_codes.push(256);
+        assert!(matches!(
+            config.validate(),
+            Err(AdasaError::ConfigValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_toml_backoff() {
+        let toml_content = r#"
+            [[processes]]
+            name = "worker"
+            script = "/bin/true"
+            min_uptime_secs = 30
+            restart_window_secs = 600
+            backoff = { type = "exponential", max_delay_secs = 120, jitter = 0.2 }
+
+            [[processes]]
+            name = "defaults"
+            script = "/bin/true"
+        "#;
+
+        let configs = parse_toml(toml_content).unwrap();
+        assert_eq!(configs[0].min_uptime, Duration::from_secs(30));
+        assert_eq!(configs[0].restart_window, Duration::from_secs(600));
+        assert_eq!(
+            configs[0].backoff,
+            BackoffConfig::Exponential {
+                max_delay: Duration::from_secs(120),
+                jitter: 0.2
+            }
+        );
+        assert_eq!(configs[1].min_uptime, Duration::from_secs(1));
+        assert_eq!(configs[1].restart_window, Duration::from_secs(60));
+        assert_eq!(configs[1].backoff, BackoffConfig::default());
+
+        let mut config = configs[0].clone();
+        config.backoff = BackoffConfig::Fixed { jitter: 1.5 };
+        assert!(matches!(
+            config.validate(),
+            Err(AdasaError::ConfigValidationError(_))
+        ));
+
+        config.backoff = BackoffConfig::Fixed { jitter: 0.0 };
+        config.restart_window = Duration::from_secs(0);
+        assert!(matches!(
+            config.validate(),
+            Err(AdasaError::ConfigValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_human_units() {
+        let toml_content = r#"
+            [[processes]]
+            name = "api"
+            script = "/bin/api"
+            max_memory = "512M"
+            max_cpu = "50%"
+            restart_delay = "500ms"
+            stop_timeout = "2m"
+            backoff = { type = "exponential", max_delay = "1m30s" }
+            health_check = { type = "tcp", port = 80, interval = "2.5s", timeout = "750ms" }
+            cgroup = { memory_high = "1.5GiB", memory_max = "2G" }
+
+            [[processes]]
+            name = "legacy"
+            script = "/bin/legacy"
+            max_memory = 1048576
+            restart_delay_secs = 2
+            stop_timeout = 1.5
+        "#;
+
+        let configs = parse_toml(toml_content).unwrap();
+        let (api, legacy) = (&configs[0], &configs[1]);
+        assert_eq!(api.max_memory, Some(ByteSize(512 * 1024 * 1024)));
+        assert_eq!(api.max_cpu, Some(50));
+        assert_eq!(api.restart_delay, Duration::from_millis(500));
+        assert_eq!(api.stop_timeout, Duration::from_secs(120));
+        assert_eq!(
+            api.backoff,
+            BackoffConfig::Exponential {
+                max_delay: Duration::from_secs(90),
+                jitter: 0.0
+            }
+        );
+        let health_check = api.health_check.as_ref().unwrap();
+        assert_eq!(health_check.interval, Duration::from_millis(2500));
+        assert_eq!(health_check.timeout, Duration::from_millis(750));
+        assert_eq!(
+            api.cgroup.as_ref().unwrap().memory_high,
+            Some(ByteSize(3 * 1024 * 1024 * 512))
+        );
+
+        assert_eq!(legacy.max_memory, Some(ByteSize(1024 * 1024)));
+        assert_eq!(legacy.restart_delay, Duration::from_secs(2));
+        assert_eq!(legacy.stop_timeout, Duration::from_millis(1500));
+
+        let json = serde_json::to_value(api).unwrap();
+        assert_eq!(json["max_memory"], "512MiB");
+        assert_eq!(json["restart_delay"], "500ms");
+        assert_eq!(json["stop_timeout"], "2m");
+        assert_eq!(serde_json::from_value::<ProcessConfig>(json).unwrap(), *api);
+
+        let err = parse_toml("name = 'a'\nscript = '/a'\nstop_timeout = '10 minutes'").unwrap_err();
+        assert!(err.to_string().contains("Invalid duration '10 minutes'"));
+        let err = parse_toml("name = 'a'\nscript = '/a'\nmax_memory = '12X'").unwrap_err();
+        assert!(err.to_string().contains("Invalid size '12X'"));
+    }
+
+    #[test]
+    fn test_parse_toml_limits() {
+        let toml_content = r#"
+            [[processes]]
+            name = "server"
+            script = "/bin/true"
+            nice = 10
+            ionice = { class = "best-effort", level = 2 }
+            cpu_affinity = [0, 2]
+
+            [processes.limits]
+            nofile = 65536
+            core = "unlimited"
+            stack = { soft = 8388608, hard = 16777216 }
+        "#;
+
+        let configs = parse_toml(toml_content).unwrap();
+        let config = &configs[0];
+        assert_eq!(
+            config.limits.nofile,
+            Some(RlimitValue::Both(RlimitAmount(65536)))
+        );
+        assert_eq!(config.limits.core.unwrap().hard(), u64::MAX);
+        assert_eq!(config.limits.stack.unwrap().soft(), 8388608);
+        assert_eq!(config.limits.stack.unwrap().hard(), 16777216);
+        assert_eq!(config.limits.address_space, None);
+        assert_eq!(config.nice, Some(10));
+        assert_eq!(
+            config.ionice,
+            Some(IoniceConfig {
+                class: IoniceClass::BestEffort,
+                level: 2
+            })
+        );
+        assert_eq!(config.cpu_affinity, vec![0, 2]);
+        assert!(config.validate().is_ok());
+
+        // "unlimited" survives a round trip through the persisted JSON
+        let json = serde_json::to_string(config).unwrap();
+        let parsed: ProcessConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.limits, config.limits);
+
+        let invalid = r#"
+            name = "bad"
+            script = "/bin/true"
+            limits = { nofile = "lots" }
+        "#;
+        assert!(parse_toml(invalid).is_err());
+
+        let mut config = config.clone();
+        config.limits.stack = Some(RlimitValue::Split {
+            soft: RlimitAmount(2),
+            hard: RlimitAmount(1),
+        });
+        assert!(config.validate().is_err());
+
+        config.limits.stack = None;
+        config.nice = Some(20);
+        assert!(config.validate().is_err());
+
+        config.nice = None;
+        config.cpu_affinity = vec![MAX_CPU_CORES];
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_cgroup_config() {
+        let toml_content = r#"
+            name = "worker"
+            script = "/bin/true"
+
+            [cgroup]
+            memory_max = 536870912
+            memory_high = 402653184
+            pids_max = 256
+            io_weight = 50
+            cpus = "0-3,6"
+        "#;
+
+        let configs = parse_toml(toml_content).unwrap();
+        let config = &configs[0];
+        let cgroup = config.cgroup.clone().unwrap();
+        assert_eq!(cgroup.memory_max, Some(ByteSize(536870912)));
+        assert_eq!(cgroup.pids_max, Some(256));
+        assert_eq!(cgroup.cpus.as_deref(), Some("0-3,6"));
+        assert!(config.uses_cgroup());
+        assert!(config.validate().is_ok());
+
+        let invalid = [
+            CgroupConfig {
+                io_weight: Some(0),
+                ..cgroup.clone()
+            },
+            CgroupConfig {
+                memory_high: Some(ByteSize(cgroup.memory_max.unwrap().0 + 1)),
+                ..cgroup.clone()
+            },
+            CgroupConfig {
+                pids_max: Some(0),
+                ..cgroup.clone()
+            },
+            CgroupConfig {
+                cpus: Some("3-1".to_string()),
+                ..cgroup.clone()
+            },
+            CgroupConfig {
+                cpus: Some("all".to_string()),
+                ..cgroup
+            },
+        ];
+        for cgroup in invalid {
+            assert!(cgroup.validate().is_err(), "{:?}", cgroup);
+        }
+    }
+
+    #[test]
+    fn test_credentials() {
+        let toml_content = r#"
+            [[processes]]
+            name = "as-root"
+            script = "/bin/true"
+            user = "root"
+
+            [[processes]]
+            name = "numeric"
+            script = "/bin/true"
+            user = "4242"
+            group = "4242"
+
+            [[processes]]
+            name = "unknown"
+            script = "/bin/true"
+            user = "no-such-user-adasa"
+        "#;
+
+        let configs = parse_toml(toml_content).unwrap();
+
+        let root = configs[0].credentials().unwrap().unwrap();
+        assert_eq!((root.uid, root.gid), (0, 0));
+        assert!(root.groups.unwrap().contains(&0));
+        assert_eq!(root.account.unwrap().0, "root");
+
+        // A UID without a passwd entry is accepted and gets no supplementary groups
+        let numeric = configs[1].credentials().unwrap().unwrap();
+        assert_eq!((numeric.uid, numeric.gid), (4242, 4242));
+        assert_eq!(numeric.groups, Some(Vec::new()));
+        assert!(numeric.account.is_none());
+
+        assert!(matches!(
+            configs[2].validate(),
+            Err(AdasaError::ConfigValidationError(msg)) if msg.contains("no-such-user-adasa")
+        ));
+
+        let mut config = configs[0].clone();
+        config.user = None;
+        assert_eq!(config.credentials().unwrap(), None);
+        config.supplementary_groups = vec!["no-such-group-adasa".to_string()];
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_dependency_cycle() {
+        let toml_content = r#"
+            [[processes]]
+            name = "a"
+            script = "/bin/true"
+            depends_on = ["b"]
+
+            [[processes]]
+            name = "b"
+            script = "/bin/true"
+            depends_on = ["c"]
+
+            [[processes]]
+            name = "c"
+            script = "/bin/true"
+            depends_on = ["a"]
+        "#;
+
+        let configs = parse_toml(toml_content).unwrap();
+        match ProcessConfig::validate_dependencies(&configs) {
+            Err(AdasaError::ConfigValidationError(msg)) => {
+                assert!(msg.contains("a -> b -> c -> a"), "{}", msg);
+            }
+            other => panic!("expected a cycle error, got {:?}", other),
+        }
+
+        let mut config = configs[0].clone();
+        config.depends_on = vec!["a".to_string()];
+        assert!(matches!(
+            config.validate(),
+            Err(AdasaError::ConfigValidationError(_))
+        ));
+    }
+
     #[test]
     fn test_from_file_toml() {
         let temp_dir = TempDir::new().unwrap();
@@ -603,9 +2362,9 @@ mod tests {
     #[test]
     fn test_from_file_unsupported_format() {
         let temp_dir = TempDir::new().unwrap();
-        let config_path = temp_dir.path().join("config.yaml");
+        let config_path = temp_dir.path().join("config.ini");
 
-        fs::write(&config_path, "name: test").unwrap();
+        fs::write(&config_path, "name = test").unwrap();
 
         let result = ProcessConfig::from_file(&config_path);
         assert!(matches!(result, Err(AdasaError::InvalidConfig(_))));
@@ -0,0 +1,399 @@
+// Human-friendly amounts in config files: byte sizes such as "512M" and durations such as "1m30s"
+
+use crate::error::{AdasaError, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::time::Duration;
+
+const KIB: u64 = 1 << 10;
+const MIB: u64 = 1 << 20;
+const GIB: u64 = 1 << 30;
+const TIB: u64 = 1 << 40;
+
+/// Binary units, largest first, as written when a size is serialized
+const BINARY_UNITS: &[(&str, u64)] = &[("TiB", TIB), ("GiB", GIB), ("MiB", MIB), ("KiB", KIB)];
+
+/// Decimal units, largest first
+const DECIMAL_UNITS: &[(&str, u64)] = &[
+    ("TB", 1_000_000_000_000),
+    ("GB", 1_000_000_000),
+    ("MB", 1_000_000),
+    ("KB", 1_000),
+];
+
+/// Multiplier of a byte size unit
+///
+/// Single letters are binary units, as in `"512M"`: `K`, `M`, `G` and `T` are
+/// the same as `KiB`, `MiB`, `GiB` and `TiB`. `KB`, `MB`, `GB` and `TB` are
+/// decimal. Units are not case-sensitive.
+fn byte_unit(unit: &str) -> Option<u64> {
+    let unit = unit.to_ascii_lowercase();
+    let multiplier = match unit.as_str() {
+        "" | "b" => 1,
+        "k" | "kib" => KIB,
+        "m" | "mib" => MIB,
+        "g" | "gib" => GIB,
+        "t" | "tib" => TIB,
+        _ => {
+            DECIMAL_UNITS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&unit))?
+                .1
+        }
+    };
+    Some(multiplier)
+}
+
+/// Split `"1.5GiB"` into its number and its unit
+fn split_number(s: &str) -> (&str, &str) {
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    (&s[..end], s[end..].trim_start())
+}
+
+/// `amount` of `unit` as a number with at most two decimals, if it can be written exactly
+fn in_unit(amount: u64, unit: u64) -> Option<String> {
+    let hundredths = amount as u128 * 100;
+    if amount < unit || !hundredths.is_multiple_of(unit as u128) {
+        return None;
+    }
+    let hundredths = hundredths / unit as u128;
+    let text = format!("{}.{:02}", hundredths / 100, hundredths % 100);
+    Some(text.trim_end_matches('0').trim_end_matches('.').to_string())
+}
+
+/// An amount of memory in bytes, written in config files as a number of bytes or
+/// with a unit, e.g. `"512M"` or `"1.5GiB"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub fn bytes(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        ByteSize(bytes)
+    }
+}
+
+impl std::fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let readable = BINARY_UNITS
+            .iter()
+            .chain(DECIMAL_UNITS)
+            .find_map(|(name, unit)| Some(format!("{}{}", in_unit(self.0, *unit)?, name)));
+
+        match readable {
+            Some(readable) => f.write_str(&readable),
+            None => write!(f, "{}B", self.0),
+        }
+    }
+}
+
+impl std::str::FromStr for ByteSize {
+    type Err = AdasaError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let invalid = || {
+            AdasaError::InvalidConfig(format!(
+                "Invalid size '{}'. Use a number of bytes or a size such as 512M or 1.5GiB",
+                s
+            ))
+        };
+
+        let (number, unit) = split_number(s);
+        let multiplier = byte_unit(unit).ok_or_else(invalid)?;
+
+        // Whole numbers are multiplied exactly, so large byte counts keep every digit
+        let bytes = match number.parse::<u64>() {
+            Ok(whole) => whole.checked_mul(multiplier).ok_or_else(invalid)?,
+            Err(_) => {
+                let number: f64 = number.parse().map_err(|_| invalid())?;
+                let bytes = (number * multiplier as f64).round();
+                if !bytes.is_finite() || bytes > u64::MAX as f64 {
+                    return Err(invalid());
+                }
+                bytes as u64
+            }
+        };
+
+        Ok(ByteSize(bytes))
+    }
+}
+
+impl Serialize for ByteSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Bytes(u64),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Bytes(bytes) => Ok(ByteSize(bytes)),
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Multiplier of a duration unit, in milliseconds
+fn duration_unit(unit: &str) -> Option<f64> {
+    let millis = match unit {
+        "ms" => 1.0,
+        "s" => 1_000.0,
+        "m" => 60_000.0,
+        "h" => 3_600_000.0,
+        "d" => 86_400_000.0,
+        _ => return None,
+    };
+    Some(millis)
+}
+
+/// Parse a duration such as `"500ms"`, `"30s"`, `"2m"` or `"1h30m"`
+///
+/// A number without a unit is a number of seconds. Durations are kept to the millisecond.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let invalid = || {
+        AdasaError::InvalidConfig(format!(
+            "Invalid duration '{}'. Use a number of seconds or a duration such as 500ms, 30s, 2m or 1h30m",
+            s
+        ))
+    };
+
+    if s.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(secs) = s.parse::<f64>() {
+        return from_millis(secs * 1_000.0).ok_or_else(invalid);
+    }
+
+    let mut millis = 0.0;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let (number, tail) = split_number(rest);
+        let unit_len = tail
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(tail.len());
+        let number: f64 = number.parse().map_err(|_| invalid())?;
+        let unit = duration_unit(&tail[..unit_len]).ok_or_else(invalid)?;
+        millis += number * unit;
+        rest = tail[unit_len..].trim_start();
+    }
+
+    from_millis(millis).ok_or_else(invalid)
+}
+
+fn from_millis(millis: f64) -> Option<Duration> {
+    let millis = millis.round();
+    (millis.is_finite() && millis >= 0.0 && millis <= u64::MAX as f64)
+        .then(|| Duration::from_millis(millis as u64))
+}
+
+/// Format a duration the way [`parse_duration`] reads it, e.g. `"500ms"`, `"30s"` or `"1m30.5s"`
+///
+/// Anything below a millisecond is dropped.
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1_000 {
+        return if millis == 0 {
+            "0s".to_string()
+        } else {
+            format!("{}ms", millis)
+        };
+    }
+
+    let hours = millis / 3_600_000;
+    let minutes = millis / 60_000 % 60;
+    let secs = millis / 1_000 % 60;
+    let fraction = millis % 1_000;
+
+    let mut text = String::new();
+    if hours > 0 {
+        text.push_str(&format!("{}h", hours));
+    }
+    if minutes > 0 {
+        text.push_str(&format!("{}m", minutes));
+    }
+    if fraction > 0 {
+        let fraction = format!("{:03}", fraction);
+        text.push_str(&format!("{}.{}s", secs, fraction.trim_end_matches('0')));
+    } else if secs > 0 {
+        text.push_str(&format!("{}s", secs));
+    }
+    text
+}
+
+/// Serde support for durations in config files, for use with `#[serde(with = "...")]`
+///
+/// Reads a number of seconds or a duration such as `"30s"`, and writes the readable form.
+pub mod duration {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_duration(*duration))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Duration, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Secs(u64),
+            Fraction(f64),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Secs(secs) => Ok(Duration::from_secs(secs)),
+            Raw::Fraction(secs) => from_millis(secs * 1_000.0).ok_or_else(|| {
+                serde::de::Error::custom(format!("invalid duration {}: must not be negative", secs))
+            }),
+            Raw::Text(text) => parse_duration(&text).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Read a CPU percentage written as a number or as a percentage such as `"50%"`,
+/// for use with `#[serde(deserialize_with = "...")]`
+pub fn deserialize_percent<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<u32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Percent(u32),
+        Text(String),
+    }
+
+    match Option::<Raw>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Raw::Percent(percent)) => Ok(Some(percent)),
+        Some(Raw::Text(text)) => text
+            .trim()
+            .trim_end_matches('%')
+            .trim_end()
+            .parse()
+            .map(Some)
+            .map_err(|_| {
+                serde::de::Error::custom(format!(
+                    "invalid percentage '{}': expected a number such as 50 or \"50%\"",
+                    text
+                ))
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_byte_size() {
+        let parse = |s: &str| s.parse::<ByteSize>().unwrap().bytes();
+
+        assert_eq!(parse("1024"), 1024);
+        assert_eq!(parse("512M"), 512 * MIB);
+        assert_eq!(parse("512mb"), 512_000_000);
+        assert_eq!(parse("1.5GiB"), 3 * GIB / 2);
+        assert_eq!(parse("2 K"), 2048);
+        assert_eq!(parse("100B"), 100);
+
+        for invalid in ["", "M", "12X", "1.2.3G", "-1M", "99999999999T"] {
+            assert!(invalid.parse::<ByteSize>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_format_byte_size() {
+        for (bytes, text) in [
+            (512 * MIB, "512MiB"),
+            (3 * GIB / 2, "1.5GiB"),
+            (1536, "1.5KiB"),
+            (1_000_000, "1MB"),
+            (100, "100B"),
+            (0, "0B"),
+        ] {
+            assert_eq!(ByteSize(bytes).to_string(), text);
+            assert_eq!(text.parse::<ByteSize>().unwrap(), ByteSize(bytes));
+        }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let millis = |s: &str| parse_duration(s).unwrap().as_millis();
+
+        assert_eq!(millis("30"), 30_000);
+        assert_eq!(millis("0.25"), 250);
+        assert_eq!(millis("500ms"), 500);
+        assert_eq!(millis("30s"), 30_000);
+        assert_eq!(millis("2m"), 120_000);
+        assert_eq!(millis("1.5h"), 5_400_000);
+        assert_eq!(millis("1m 30s"), 90_000);
+        assert_eq!(millis("1d"), 86_400_000);
+
+        for invalid in ["", "s", "10x", "-5s", "5 seconds", "1..5s"] {
+            assert!(parse_duration(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_format_duration() {
+        for (millis, text) in [
+            (0, "0s"),
+            (500, "500ms"),
+            (30_000, "30s"),
+            (120_000, "2m"),
+            (90_500, "1m30.5s"),
+            (3_600_000, "1h"),
+            (5_430_000, "1h30m30s"),
+        ] {
+            let duration = Duration::from_millis(millis);
+            assert_eq!(format_duration(duration), text);
+            assert_eq!(parse_duration(text).unwrap(), duration);
+        }
+    }
+
+    #[test]
+    fn test_serde_forms() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Settings {
+            #[serde(with = "duration")]
+            timeout: Duration,
+            memory: ByteSize,
+            #[serde(default, deserialize_with = "deserialize_percent")]
+            cpu: Option<u32>,
+        }
+
+        let settings: Settings =
+            toml::from_str("timeout = 1.5\nmemory = 1048576\ncpu = '50%'").unwrap();
+        assert_eq!(settings.timeout, Duration::from_millis(1_500));
+        assert_eq!(settings.memory, ByteSize(MIB));
+        assert_eq!(settings.cpu, Some(50));
+
+        let json = serde_json::to_value(&settings).unwrap();
+        assert_eq!(json["timeout"], "1.5s");
+        assert_eq!(json["memory"], "1MiB");
+
+        let settings: Settings =
+            serde_json::from_str(r#"{"timeout": "2m", "memory": "1G"}"#).unwrap();
+        assert_eq!(settings.timeout, Duration::from_secs(120));
+        assert_eq!(settings.cpu, None);
+        assert!(serde_json::from_str::<Settings>(r#"{"timeout": -1, "memory": 1}"#).is_err());
+    }
+}
@@ -73,8 +73,6 @@ pub fn daemonize() -> Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     #[test]
     #[cfg(unix)]
     fn test_daemonize_compiles() {
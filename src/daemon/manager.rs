@@ -62,7 +62,7 @@ impl DaemonManager {
     /// Stop the daemon by sending SIGTERM
     #[cfg(unix)]
     pub fn stop_daemon(&self, timeout_secs: u64) -> Result<()> {
-        let pid = self.get_pid().ok_or_else(|| AdasaError::DaemonNotRunning)?;
+        let pid = self.get_pid().ok_or(AdasaError::DaemonNotRunning)?;
 
         println!("Stopping daemon (PID: {})...", pid);
 
@@ -26,6 +26,12 @@ pub enum AdasaError {
     #[error("Failed to restart process {0}: {1}")]
     RestartError(String, String),
 
+    #[error("Health check failed: {0}")]
+    HealthCheckFailed(String),
+
+    #[error("Process did not become ready: {0}")]
+    NotReady(String),
+
     // IPC-related errors
     #[error("IPC error: {0}")]
     IpcError(String),
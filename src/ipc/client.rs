@@ -9,6 +9,7 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
 use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
 
 /// Default socket path for daemon communication
 const DEFAULT_SOCKET_PATH: &str = "/tmp/adasa.sock";
@@ -143,6 +144,58 @@ impl IpcClient {
 
 
 
+    /// Send a streaming command and pass every response to `on_response`
+    ///
+    /// Uses a dedicated connection that stays open until the daemon ends the
+    /// stream. Dropping the returned future closes the connection, which tells
+    /// the daemon to stop streaming.
+    pub async fn stream_command<F>(&self, command: Command, mut on_response: F) -> Result<()>
+    where
+        F: FnMut(Response),
+    {
+        let request_id = self.request_id.fetch_add(1, Ordering::SeqCst);
+        let request = Request::new(request_id, command);
+
+        let stream = self.connect()?;
+        stream
+            .set_nonblocking(true)
+            .map_err(|e| AdasaError::IpcError(format!("Failed to set non-blocking: {}", e)))?;
+        let stream = tokio::net::UnixStream::from_std(stream)
+            .map_err(|e| AdasaError::IpcError(format!("Failed to convert stream: {}", e)))?;
+        let (reader, mut writer) = stream.into_split();
+
+        let mut request_bytes = serde_json::to_vec(&request).map_err(|e| {
+            AdasaError::SerializationError(format!("Failed to serialize request: {}", e))
+        })?;
+        request_bytes.push(b'\n');
+        writer
+            .write_all(&request_bytes)
+            .await
+            .map_err(|e| AdasaError::IpcError(format!("Failed to write request: {}", e)))?;
+
+        let mut lines = tokio::io::BufReader::new(reader).lines();
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|e| AdasaError::IpcError(format!("Failed to read response: {}", e)))?
+        {
+            let response: Response = serde_json::from_str(&line).map_err(|e| {
+                AdasaError::DeserializationError(format!("Failed to deserialize response: {}", e))
+            })?;
+
+            if response.id != request_id {
+                return Err(AdasaError::ProtocolError(format!(
+                    "Response ID mismatch: expected {}, got {}",
+                    request_id, response.id
+                )));
+            }
+
+            on_response(response);
+        }
+
+        Ok(())
+    }
+
     /// Establish a connection to the daemon's Unix socket
     fn connect(&self) -> Result<UnixStream> {
         // Check if socket file exists
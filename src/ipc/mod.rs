@@ -6,7 +6,8 @@ pub mod server;
 
 pub use client::IpcClient;
 pub use protocol::{
-    Command, DaemonCommand, DeleteOptions, LogOptions, ProcessId, ProcessInfo, ProcessState,
-    ProcessStats, Request, Response, ResponseData, RestartOptions, StartOptions, StopOptions,
+    Command, DaemonCommand, DeleteOptions, Event, LimitResource, LogOptions, ProcessId,
+    ProcessInfo, ProcessState, ProcessStats, Request, Response, ResponseData, RestartOptions,
+    StartOptions, StopOptions,
 };
-pub use server::IpcServer;
+pub use server::{IpcServer, Reply};
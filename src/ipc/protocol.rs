@@ -1,5 +1,7 @@
 // IPC Protocol definitions for client-daemon communication
 
+use crate::config::diff::{ReloadPlan, RunningProcess};
+use crate::config::{BatchSize, LimitAction};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -34,6 +36,10 @@ pub enum ProcessState {
     Stopped,
     Errored,
     Restarting,
+    Unhealthy,
+    Scheduled,
+    Completed,
+    CrashLoop,
 }
 
 impl std::fmt::Display for ProcessState {
@@ -45,6 +51,10 @@ impl std::fmt::Display for ProcessState {
             ProcessState::Stopped => write!(f, "stopped"),
             ProcessState::Errored => write!(f, "errored"),
             ProcessState::Restarting => write!(f, "restarting"),
+            ProcessState::Unhealthy => write!(f, "unhealthy"),
+            ProcessState::Scheduled => write!(f, "scheduled"),
+            ProcessState::Completed => write!(f, "completed"),
+            ProcessState::CrashLoop => write!(f, "crash-loop"),
         }
     }
 }
@@ -57,7 +67,31 @@ pub struct ProcessStats {
     pub restarts: usize,
     pub cpu_usage: f32,
     pub memory_usage: u64,
+    /// CPU usage of the process and everything it started, in percent of one core
+    #[serde(default)]
+    pub tree_cpu_usage: f32,
+    /// Memory of the process and everything it started, in bytes
+    #[serde(default)]
+    pub tree_memory_usage: u64,
     pub last_restart: Option<SystemTime>,
+    /// Exit code the process last exited with
+    #[serde(default)]
+    pub last_exit_code: Option<i32>,
+    /// Signal that killed the process on its most recent exit
+    #[serde(default)]
+    pub last_exit_signal: Option<i32>,
+    /// When the most recent run of a job started
+    #[serde(default)]
+    pub last_run: Option<SystemTime>,
+    /// How long the most recent finished run of a job took
+    #[serde(default)]
+    pub last_run_duration: Option<Duration>,
+    /// When a scheduled job runs next
+    #[serde(default)]
+    pub next_run: Option<SystemTime>,
+    /// Processes of the cgroup that the kernel OOM killer has killed
+    #[serde(default)]
+    pub oom_kills: u64,
 }
 
 impl Default for ProcessStats {
@@ -68,7 +102,15 @@ impl Default for ProcessStats {
             restarts: 0,
             cpu_usage: 0.0,
             memory_usage: 0,
+            tree_cpu_usage: 0.0,
+            tree_memory_usage: 0,
             last_restart: None,
+            last_exit_code: None,
+            last_exit_signal: None,
+            last_run: None,
+            last_run_duration: None,
+            next_run: None,
+            oom_kills: 0,
         }
     }
 }
@@ -98,6 +140,9 @@ pub struct RestartOptions {
     pub target: String,
     /// If true, restart instances sequentially with health checks (for multi-instance processes)
     pub rolling: bool,
+    /// Instances to restart at once during a rolling restart (default: the process setting)
+    #[serde(default)]
+    pub batch: Option<BatchSize>,
 }
 
 /// Options for viewing logs
@@ -115,6 +160,42 @@ pub struct DeleteOptions {
     pub target: String,
 }
 
+/// Options for changing the number of instances of a process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaleOptions {
+    /// Base name of the process, e.g. `web` for `web-0` and `web-1`
+    pub target: String,
+    /// Number of instances to run
+    pub instances: usize,
+}
+
+/// Options for starting the processes of a config file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigFileOptions {
+    pub config_path: PathBuf,
+    /// Environment profile to apply, one of the `env.<profile>` tables of the file
+    pub profile: Option<String>,
+}
+
+/// Options for reloading a config file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReloadOptions {
+    pub config_path: PathBuf,
+    /// Environment profile to apply, one of the `env.<profile>` tables of the file
+    pub profile: Option<String>,
+    /// Only plan the changes, without applying them
+    pub dry_run: bool,
+}
+
+/// Options for fetching the live configuration of the processes in a config file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetConfigOptions {
+    /// Names of the processes in the file
+    pub names: Vec<String>,
+    /// Canonical path of the file, used to find processes loaded from it
+    pub config_path: PathBuf,
+}
+
 /// Daemon management commands
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DaemonCommand {
@@ -127,14 +208,43 @@ pub enum DaemonCommand {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Command {
     Start(StartOptions),
-    StartFromConfig { config_path: PathBuf },
+    StartFromConfig(ConfigFileOptions),
     Stop(StopOptions),
     Restart(RestartOptions),
     List,
     Logs(LogOptions),
     Delete(DeleteOptions),
     Daemon(DaemonCommand),
-    ReloadConfig { config_path: PathBuf },
+    ReloadConfig(ReloadOptions),
+    Subscribe,
+    Save { name: Option<String> },
+    Resurrect { name: Option<String> },
+    Stats { target: String },
+    Scale(ScaleOptions),
+    GetConfig(GetConfigOptions),
+}
+
+impl Command {
+    /// Short name of the command, used to label metrics
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Start(_) => "start",
+            Command::StartFromConfig(_) => "start_from_config",
+            Command::Stop(_) => "stop",
+            Command::Restart(_) => "restart",
+            Command::List => "list",
+            Command::Logs(_) => "logs",
+            Command::Delete(_) => "delete",
+            Command::Daemon(_) => "daemon",
+            Command::ReloadConfig(_) => "reload_config",
+            Command::Subscribe => "subscribe",
+            Command::Save { .. } => "save",
+            Command::Resurrect { .. } => "resurrect",
+            Command::Stats { .. } => "stats",
+            Command::Scale(_) => "scale",
+            Command::GetConfig(_) => "get_config",
+        }
+    }
 }
 
 /// Process information returned in responses
@@ -146,6 +256,90 @@ pub struct ProcessInfo {
     pub stats: ProcessStats,
 }
 
+/// Resource usage of a process at one point in time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StatsSample {
+    pub at: SystemTime,
+    /// CPU usage of the process and its descendants, in percent of one core
+    pub cpu_usage: f32,
+    /// Memory of the process and its descendants, in bytes
+    pub memory_usage: u64,
+    /// Threads of the main process
+    pub threads: u32,
+    /// Open file descriptors of the main process
+    pub fds: u32,
+}
+
+/// Recorded resource usage of a process, oldest sample first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessHistory {
+    pub id: ProcessId,
+    pub name: String,
+    pub samples: Vec<StatsSample>,
+}
+
+/// Resource whose limit a process exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitResource {
+    /// Memory usage in bytes
+    Memory,
+    /// CPU usage in percent
+    Cpu,
+}
+
+/// State change pushed to clients subscribed with [`Command::Subscribe`]
+///
+/// The connection stays open after a `Subscribe` request, and each event is
+/// sent as a separate response line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A process was started
+    ProcessStarted {
+        id: ProcessId,
+        name: String,
+        pid: u32,
+    },
+    /// A process exited; neither code nor signal is set if the exit status is unknown
+    ProcessExited {
+        id: ProcessId,
+        name: String,
+        exit_code: Option<i32>,
+        signal: Option<i32>,
+    },
+    /// A process was restarted with a new child
+    ProcessRestarted {
+        id: ProcessId,
+        name: String,
+        pid: u32,
+        restarts: usize,
+    },
+    /// A process exceeded its memory or CPU limit
+    LimitViolation {
+        id: ProcessId,
+        name: String,
+        resource: LimitResource,
+        usage: f64,
+        limit: f64,
+        action: LimitAction,
+    },
+    /// A process crashed too often within its restart window and is no longer restarted
+    CrashLoop {
+        id: ProcessId,
+        name: String,
+        restarts: usize,
+    },
+    /// A configuration file was reloaded
+    ConfigReloaded {
+        config_path: PathBuf,
+        started: usize,
+        updated: usize,
+    },
+    /// The daemon is shutting down; no further events follow
+    DaemonShuttingDown,
+}
+
 /// Response data variants
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ResponseData {
@@ -165,6 +359,21 @@ pub enum ResponseData {
     DaemonStatus { running: bool, uptime: Duration },
     /// Generic success message
     Success(String),
+    /// Subscription accepted; events follow on the same connection
+    Subscribed,
+    /// Event pushed to a subscribed client
+    Event(Event),
+    /// Recorded resource usage of the requested processes
+    Stats(Vec<ProcessHistory>),
+    /// Changes made by a config reload, or only planned for a dry run
+    Reload {
+        plan: ReloadPlan,
+        dry_run: bool,
+        /// Changes that failed to apply
+        errors: Vec<String>,
+    },
+    /// Live configuration of the requested processes
+    Config(Vec<RunningProcess>),
 }
 
 /// Request message from client to daemon
@@ -1,18 +1,40 @@
 // IPC Server - Listens for client connections and handles requests
 
 use crate::error::{AdasaError, Result};
-use crate::ipc::{Command, Request, Response};
+use crate::ipc::{Command, Request, Response, ResponseData};
 use serde_json;
 use std::io::{BufRead, BufReader, Write};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader as AsyncBufReader};
+use tokio::net::unix::OwnedWriteHalf;
 use tokio::net::UnixStream as TokioUnixStream;
+use tokio::sync::mpsc;
 
 /// Default socket path for daemon communication
 const DEFAULT_SOCKET_PATH: &str = "/tmp/adasa.sock";
 
+/// Reply of a command handler
+pub enum Reply {
+    /// A single response, after which the connection is closed
+    Single(Response),
+    /// A first response followed by updates, sent as they arrive
+    ///
+    /// Every update is sent as a separate response with the request ID. The
+    /// stream ends when the sender is dropped or the client closes the connection.
+    Stream {
+        first: Response,
+        updates: mpsc::Receiver<ResponseData>,
+    },
+}
+
+impl From<Response> for Reply {
+    fn from(response: Response) -> Self {
+        Reply::Single(response)
+    }
+}
+
 /// IPC server for handling client connections
 pub struct IpcServer {
     socket_path: PathBuf,
@@ -119,10 +141,14 @@ impl IpcServer {
     }
 
     /// Run the server accept loop with an async handler (optimized)
-    pub async fn run<F, Fut>(&self, handler: F) -> Result<()>
+    ///
+    /// The handler returns either a [`Response`] or a [`Reply`], so that commands
+    /// such as `logs --follow` can keep streaming responses to the client.
+    pub async fn run<F, Fut, R>(&self, handler: F) -> Result<()>
     where
         F: Fn(Command) -> Fut + Send + Sync + 'static,
-        Fut: std::future::Future<Output = Result<Response>> + Send,
+        Fut: std::future::Future<Output = Result<R>> + Send,
+        R: Into<Reply>,
     {
         let handler = Arc::new(handler);
         
@@ -163,13 +189,14 @@ impl IpcServer {
     }
 
     /// Handle a single connection asynchronously (optimized for performance)
-    async fn handle_connection_async<F, Fut>(
+    async fn handle_connection_async<F, Fut, R>(
         stream: TokioUnixStream,
         handler: Arc<F>,
     ) -> Result<()>
     where
         F: Fn(Command) -> Fut + Send + Sync,
-        Fut: std::future::Future<Output = Result<Response>> + Send,
+        Fut: std::future::Future<Output = Result<R>> + Send,
+        R: Into<Reply>,
     {
         // Use async buffered reader for efficient I/O
         let (reader, mut writer) = stream.into_split();
@@ -190,19 +217,59 @@ impl IpcServer {
         })?;
 
         // Handle the command
-        let response = match handler(request.command).await {
-            Ok(resp) => Response {
-                id: request.id,
-                result: resp.result,
-            },
-            Err(e) => Response::error(request.id, e.to_string()),
+        let reply = match handler(request.command).await {
+            Ok(reply) => reply.into(),
+            Err(e) => Reply::Single(Response::error(request.id, e.to_string())),
+        };
+
+        match reply {
+            Reply::Single(response) => {
+                Self::write_response(&mut writer, request.id, response).await?;
+            }
+            Reply::Stream { first, mut updates } => {
+                Self::write_response(&mut writer, request.id, first).await?;
+
+                loop {
+                    request_line.clear();
+                    tokio::select! {
+                        update = updates.recv() => match update {
+                            Some(data) => {
+                                let response = Response::success(request.id, data);
+                                Self::write_response(&mut writer, request.id, response).await?;
+                            }
+                            None => break,
+                        },
+                        // Nothing is expected from the client anymore, so a read
+                        // only returns once it has closed the connection
+                        read = reader.read_line(&mut request_line) => {
+                            if matches!(read, Ok(0) | Err(_)) {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write a response with the ID of the request it answers
+    async fn write_response(
+        writer: &mut OwnedWriteHalf,
+        request_id: u64,
+        response: Response,
+    ) -> Result<()> {
+        let response = Response {
+            id: request_id,
+            result: response.result,
         };
 
         // Serialize response (use to_vec for better performance)
         let mut response_bytes = serde_json::to_vec(&response).map_err(|e| {
             AdasaError::SerializationError(format!("Failed to serialize response: {}", e))
         })?;
-        
+
         // Add newline
         response_bytes.push(b'\n');
 
@@ -6,6 +6,7 @@ pub mod daemon;
 pub mod error;
 pub mod ipc;
 pub mod logs;
+pub mod metrics;
 pub mod perf;
 pub mod process;
 pub mod state;
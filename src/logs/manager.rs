@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Child;
+use tokio::sync::mpsc::UnboundedSender;
 
 /// LogManager handles log capture and routing for all managed processes
 pub struct LogManager {
@@ -124,6 +125,33 @@ impl LogManager {
             )));
         }
 
+        Self::capture_output(&self.log_dir, process_id, process_name, child, None, None)
+    }
+
+    /// Capture stdout and stderr from a child process into log files in `log_dir`
+    ///
+    /// Unlike [`LogManager::capture_logs`], this does not require a registered logger,
+    /// so it can be used to re-attach capture to a process that has been respawned.
+    ///
+    /// # Arguments
+    /// * `log_dir` - Directory where log files are stored
+    /// * `process_id` - Process ID
+    /// * `process_name` - Process name (used for log file naming)
+    /// * `child` - Mutable reference to the child process
+    /// * `owner` - UID and GID to give the log files, if the process runs as another user
+    /// * `line_tap` - Optional channel that receives a copy of every captured line
+    ///
+    /// # Returns
+    /// * `Ok(())` - Successfully started log capture
+    /// * `Err(AdasaError)` - Failed to capture logs
+    pub fn capture_output(
+        log_dir: &Path,
+        process_id: u64,
+        process_name: &str,
+        child: &mut Child,
+        owner: Option<(u32, u32)>,
+        line_tap: Option<UnboundedSender<String>>,
+    ) -> Result<()> {
         // Take stdout pipe from child
         let stdout = child.stdout.take().ok_or_else(|| {
             AdasaError::LogError(format!(
@@ -142,13 +170,15 @@ impl LogManager {
 
         // Spawn task to read stdout
         let stdout_reader = BufReader::new(stdout);
-        let log_dir = self.log_dir.clone();
+        let log_dir = log_dir.to_path_buf();
         let process_name = process_name.to_string();
         tokio::spawn(Self::read_stdout_task(
             process_id,
             process_name.clone(),
             stdout_reader,
             log_dir.clone(),
+            owner,
+            line_tap.clone(),
         ));
 
         // Spawn task to read stderr
@@ -158,6 +188,8 @@ impl LogManager {
             process_name,
             stderr_reader,
             log_dir,
+            owner,
+            line_tap,
         ));
 
         Ok(())
@@ -169,12 +201,19 @@ impl LogManager {
         process_name: String,
         mut reader: BufReader<tokio::process::ChildStdout>,
         log_dir: PathBuf,
+        owner: Option<(u32, u32)>,
+        mut line_tap: Option<UnboundedSender<String>>,
     ) {
         // Create a dedicated LogWriter for this task
         let mut writer = match LogWriter::new(&log_dir, &process_name, process_id).await {
             Ok(w) => w,
             Err(_) => return,
         };
+        if let Some((uid, gid)) = owner {
+            if let Err(e) = writer.set_owner(uid, gid) {
+                tracing::warn!("Failed to change owner of logs of {}: {}", process_name, e);
+            }
+        }
 
         let mut line = String::new();
 
@@ -187,6 +226,11 @@ impl LogManager {
                 Ok(_) => {
                     // Write line to log file
                     let _ = writer.write_stdout(line.as_bytes()).await;
+                    if let Some(ref tap) = line_tap {
+                        if tap.send(line.clone()).is_err() {
+                            line_tap = None;
+                        }
+                    }
                     line.clear();
                 }
                 Err(_) => {
@@ -206,12 +250,19 @@ impl LogManager {
         process_name: String,
         mut reader: BufReader<tokio::process::ChildStderr>,
         log_dir: PathBuf,
+        owner: Option<(u32, u32)>,
+        mut line_tap: Option<UnboundedSender<String>>,
     ) {
         // Create a dedicated LogWriter for this task
         let mut writer = match LogWriter::new(&log_dir, &process_name, process_id).await {
             Ok(w) => w,
             Err(_) => return,
         };
+        if let Some((uid, gid)) = owner {
+            if let Err(e) = writer.set_owner(uid, gid) {
+                tracing::warn!("Failed to change owner of logs of {}: {}", process_name, e);
+            }
+        }
 
         let mut line = String::new();
 
@@ -224,6 +275,11 @@ impl LogManager {
                 Ok(_) => {
                     // Write line to log file
                     let _ = writer.write_stderr(line.as_bytes()).await;
+                    if let Some(ref tap) = line_tap {
+                        if tap.send(line.clone()).is_err() {
+                            line_tap = None;
+                        }
+                    }
                     line.clear();
                 }
                 Err(_) => {
@@ -1,4 +1,5 @@
 use crate::error::{AdasaError, Result};
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader};
@@ -216,12 +217,15 @@ pub struct LogStream {
     /// Receiver for log entries
     receiver: mpsc::Receiver<LogEntry>,
     /// Handle to the background task
-    _task_handle: tokio::task::JoinHandle<()>,
+    task_handle: tokio::task::JoinHandle<()>,
 }
 
 impl LogStream {
     /// Create a new LogStream for tailing log files
     ///
+    /// Only output written after the stream is created is returned. The stream
+    /// keeps following the log files across rotations.
+    ///
     /// # Arguments
     /// * `log_dir` - Directory containing log files
     /// * `process_name` - Name of the process
@@ -243,22 +247,23 @@ impl LogStream {
     ) -> Result<Self> {
         let (tx, rx) = mpsc::channel(100);
 
+        // Open the files before returning, so nothing written afterwards is missed
+        let mut stdout_reader = if include_stdout {
+            let stdout_path = log_dir.join(format!("{}-{}-out.log", process_name, process_id));
+            Some(LogTailer::new(stdout_path, LogSource::Stdout).await)
+        } else {
+            None
+        };
+
+        let mut stderr_reader = if include_stderr {
+            let stderr_path = log_dir.join(format!("{}-{}-err.log", process_name, process_id));
+            Some(LogTailer::new(stderr_path, LogSource::Stderr).await)
+        } else {
+            None
+        };
+
         // Spawn background task to tail log files
         let task_handle = tokio::spawn(async move {
-            let mut stdout_reader = if include_stdout {
-                let stdout_path = log_dir.join(format!("{}-{}-out.log", process_name, process_id));
-                Some(LogTailer::new(stdout_path, LogSource::Stdout).await)
-            } else {
-                None
-            };
-
-            let mut stderr_reader = if include_stderr {
-                let stderr_path = log_dir.join(format!("{}-{}-err.log", process_name, process_id));
-                Some(LogTailer::new(stderr_path, LogSource::Stderr).await)
-            } else {
-                None
-            };
-
             loop {
                 let mut has_data = false;
 
@@ -311,7 +316,7 @@ impl LogStream {
 
         Ok(Self {
             receiver: rx,
-            _task_handle: task_handle,
+            task_handle,
         })
     }
 
@@ -325,23 +330,71 @@ impl LogStream {
     }
 }
 
+impl Drop for LogStream {
+    fn drop(&mut self) {
+        // The tailing task only notices a dropped receiver when it has data to send
+        self.task_handle.abort();
+    }
+}
+
 /// Internal helper for tailing a single log file
+///
+/// The tailer follows the path rather than the open file: when `LogWriter`
+/// rotates the log (or it is truncated), the tailer finishes the old file and
+/// continues from the start of the new one.
 struct LogTailer {
     path: PathBuf,
     source: LogSource,
     reader: Option<BufReader<File>>,
     position: u64,
+    inode: u64,
 }
 
 impl LogTailer {
-    /// Create a new LogTailer
+    /// Create a new LogTailer that starts at the current end of the file
     async fn new(path: PathBuf, source: LogSource) -> Self {
-        Self {
+        let position = match tokio::fs::metadata(&path).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+
+        let mut tailer = Self {
             path,
             source,
             reader: None,
-            position: 0,
+            position,
+            inode: 0,
+        };
+        // Open right away so that a rotation before the first read is noticed
+        let _ = tailer.open().await;
+        tailer
+    }
+
+    /// Open the file at the current position
+    async fn open(&mut self) -> Result<()> {
+        let file = File::open(&self.path)
+            .await
+            .map_err(|e| AdasaError::LogFileError(format!("Failed to open log file: {}", e)))?;
+
+        let metadata = file
+            .metadata()
+            .await
+            .map_err(|e| AdasaError::LogFileError(format!("Failed to stat log file: {}", e)))?;
+        if metadata.len() < self.position {
+            self.position = 0;
         }
+
+        let mut reader = BufReader::new(file);
+
+        // Seek to the last known position
+        reader
+            .seek(std::io::SeekFrom::Start(self.position))
+            .await
+            .map_err(|e| AdasaError::LogError(format!("Failed to seek in log file: {}", e)))?;
+
+        self.inode = metadata.ino();
+        self.reader = Some(reader);
+        Ok(())
     }
 
     /// Read the next log entry
@@ -352,50 +405,73 @@ impl LogTailer {
                 // File doesn't exist yet, wait for it
                 return Ok(None);
             }
+            self.open().await?;
+        }
 
-            let file = File::open(&self.path)
-                .await
-                .map_err(|e| AdasaError::LogFileError(format!("Failed to open log file: {}", e)))?;
+        if let Some(entry) = self.read_line().await? {
+            return Ok(Some(entry));
+        }
 
-            let mut reader = BufReader::new(file);
+        // At the end of the file: switch over if the log was rotated or truncated
+        if self.rotated().await {
+            // Pick up anything written to the old file right before it was rotated
+            if let Some(entry) = self.read_line().await? {
+                return Ok(Some(entry));
+            }
+            self.reader = None;
+            self.position = 0;
+        }
 
-            // Seek to the last known position
-            reader
-                .seek(std::io::SeekFrom::Start(self.position))
-                .await
-                .map_err(|e| AdasaError::LogError(format!("Failed to seek in log file: {}", e)))?;
+        Ok(None)
+    }
 
-            self.reader = Some(reader);
-        }
+    /// Read a line from the open file
+    async fn read_line(&mut self) -> Result<Option<LogEntry>> {
+        let Some(ref mut reader) = self.reader else {
+            return Ok(None);
+        };
 
-        // Try to read a line
-        if let Some(ref mut reader) = self.reader {
-            let mut line = String::new();
-            match reader.read_line(&mut line).await {
-                Ok(0) => {
-                    // EOF - no new data
-                    Ok(None)
-                }
-                Ok(n) => {
-                    // Update position
-                    self.position += n as u64;
+        let mut line = String::new();
+        match reader.read_line(&mut line).await {
+            Ok(0) => {
+                // EOF - no new data
+                Ok(None)
+            }
+            Ok(n) if !line.ends_with('\n') => {
+                // Partial line: rewind and wait for the writer to finish it
+                reader
+                    .seek(std::io::SeekFrom::Current(-(n as i64)))
+                    .await
+                    .map_err(|e| {
+                        AdasaError::LogError(format!("Failed to seek in log file: {}", e))
+                    })?;
+                Ok(None)
+            }
+            Ok(n) => {
+                // Update position
+                self.position += n as u64;
 
-                    // Parse and return entry
-                    let entry = LogEntry::parse(line.trim_end(), self.source);
-                    Ok(Some(entry))
-                }
-                Err(e) => {
-                    // Error reading - file may have been rotated
-                    // Reset reader to try reopening
-                    self.reader = None;
-                    Err(AdasaError::LogError(format!(
-                        "Failed to read log line: {}",
-                        e
-                    )))
-                }
+                // Parse and return entry
+                let entry = LogEntry::parse(line.trim_end(), self.source);
+                Ok(Some(entry))
+            }
+            Err(e) => {
+                // Error reading - reset reader to try reopening
+                self.reader = None;
+                Err(AdasaError::LogError(format!(
+                    "Failed to read log line: {}",
+                    e
+                )))
             }
-        } else {
-            Ok(None)
+        }
+    }
+
+    /// Check whether the path now refers to a different or truncated file
+    async fn rotated(&self) -> bool {
+        match tokio::fs::metadata(&self.path).await {
+            Ok(metadata) => metadata.ino() != self.inode || metadata.len() < self.position,
+            // Between the rename and the creation of the new file
+            Err(_) => false,
         }
     }
 }
@@ -600,4 +676,36 @@ mod tests {
 
         assert!(entry.is_some());
     }
+
+    #[tokio::test]
+    async fn test_log_stream_after_truncation() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_dir = temp_dir.path().to_path_buf();
+
+        let log_file = log_dir.join("test-1-out.log");
+        let mut file = File::create(&log_file).await.unwrap();
+        file.write_all(b"[2024-01-01 10:00:00.000] A long line that will be truncated\n")
+            .await
+            .unwrap();
+        file.flush().await.unwrap();
+        drop(file);
+
+        let mut stream = LogStream::new(log_dir.clone(), "test".to_string(), 1, true, false, None)
+            .await
+            .unwrap();
+
+        // Truncate in place, like logrotate's copytruncate
+        let mut file = File::create(&log_file).await.unwrap();
+        file.write_all(b"[2024-01-01 10:00:01.000] After\n")
+            .await
+            .unwrap();
+        file.flush().await.unwrap();
+        drop(file);
+
+        let entry = tokio::time::timeout(Duration::from_secs(2), stream.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.message, "After");
+    }
 }
@@ -25,6 +25,8 @@ pub struct LogWriter {
     stdout_size: u64,
     /// Current size of stderr file
     stderr_size: u64,
+    /// UID and GID the log files belong to, if not the daemon's
+    owner: Option<(u32, u32)>,
 }
 
 impl LogWriter {
@@ -93,6 +95,7 @@ impl LogWriter {
             max_size,
             stdout_size,
             stderr_size,
+            owner: None,
         })
     }
 
@@ -217,6 +220,30 @@ impl LogWriter {
         Ok(())
     }
 
+    /// Give the log files, including those created by later rotations, to another user
+    ///
+    /// Used when the process runs as a different user than the daemon, so that the
+    /// user can read its own logs.
+    pub fn set_owner(&mut self, uid: u32, gid: u32) -> Result<()> {
+        self.owner = Some((uid, gid));
+        Self::chown(&self.stdout_path, self.owner)?;
+        Self::chown(&self.stderr_path, self.owner)
+    }
+
+    fn chown(file_path: &Path, owner: Option<(u32, u32)>) -> Result<()> {
+        let Some((uid, gid)) = owner else {
+            return Ok(());
+        };
+
+        std::os::unix::fs::chown(file_path, Some(uid), Some(gid)).map_err(|e| {
+            AdasaError::LogFileError(format!(
+                "Failed to change owner of {}: {}",
+                file_path.display(),
+                e
+            ))
+        })
+    }
+
     /// Reopen a log file after rotation
     async fn reopen_file(&self, file_path: &Path) -> Result<TokioFile> {
         let file = OpenOptions::new()
@@ -224,6 +251,7 @@ impl LogWriter {
             .append(true)
             .open(file_path)
             .map_err(|e| AdasaError::LogFileError(format!("Failed to reopen log file: {}", e)))?;
+        Self::chown(file_path, self.owner)?;
 
         Ok(TokioFile::from_std(file))
     }
@@ -407,4 +435,32 @@ mod tests {
 
         assert!(writer.stdout_size() > initial_size);
     }
+
+    #[tokio::test]
+    async fn test_set_owner_survives_rotation() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let log_dir = temp_dir.path();
+
+        // Without root, files can only be given to their current owner
+        let (uid, gid) = if nix::unistd::Uid::effective().is_root() {
+            (65534, 65534)
+        } else {
+            let metadata = std::fs::metadata(log_dir).unwrap();
+            (metadata.uid(), metadata.gid())
+        };
+
+        let mut writer = LogWriter::with_max_size(log_dir, "test-process", 1, 10)
+            .await
+            .unwrap();
+        writer.set_owner(uid, gid).unwrap();
+        writer.write_stdout(b"first line to rotate").await.unwrap();
+        writer.write_stdout(b"second line").await.unwrap();
+
+        let metadata = std::fs::metadata(writer.stdout_path()).unwrap();
+        assert_eq!((metadata.uid(), metadata.gid()), (uid, gid));
+        let metadata = std::fs::metadata(writer.stderr_path()).unwrap();
+        assert_eq!((metadata.uid(), metadata.gid()), (uid, gid));
+    }
 }
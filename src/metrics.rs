@@ -0,0 +1,513 @@
+// Prometheus metrics for processes and the daemon itself
+
+use crate::error::{AdasaError, Result};
+use crate::process::{split_instance, ManagedProcess};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Environment variable that enables the metrics listener when `--metrics` is not given
+pub const METRICS_ADDR_ENV: &str = "ADASA_METRICS_ADDR";
+
+/// Content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Upper bounds (in seconds) of the latency histogram buckets
+const DURATION_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// How long a scraper may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest request head the listener accepts
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// Cumulative latency histogram in the Prometheus layout
+#[derive(Debug, Clone)]
+struct Histogram {
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            counts: vec![0; DURATION_BUCKETS.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (bound, count) in DURATION_BUCKETS.iter().zip(self.counts.iter_mut()) {
+            if secs <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+
+    fn write(&self, out: &mut String, name: &str, labels: &[(&str, &str)]) {
+        for (bound, count) in DURATION_BUCKETS.iter().zip(&self.counts) {
+            let le = bound.to_string();
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &le));
+            sample(out, &format!("{}_bucket", name), &bucket_labels, *count);
+        }
+        let mut inf_labels = labels.to_vec();
+        inf_labels.push(("le", "+Inf"));
+        sample(out, &format!("{}_bucket", name), &inf_labels, self.count);
+        sample(out, &format!("{}_sum", name), labels, self.sum);
+        sample(out, &format!("{}_count", name), labels, self.count);
+    }
+}
+
+/// Metrics collected by the daemon about its own work
+///
+/// Per-process metrics are read from the process manager at scrape time,
+/// so only the timings that are not kept anywhere else live here.
+#[derive(Debug)]
+pub struct DaemonMetrics {
+    ipc_requests: Mutex<BTreeMap<&'static str, Histogram>>,
+    supervisor_loop: Mutex<Histogram>,
+}
+
+impl DaemonMetrics {
+    pub fn new() -> Self {
+        Self {
+            ipc_requests: Mutex::new(BTreeMap::new()),
+            supervisor_loop: Mutex::new(Histogram::new()),
+        }
+    }
+
+    /// Record how long the daemon took to handle an IPC request
+    ///
+    /// # Arguments
+    /// * `command` - Name of the command, see [`crate::ipc::protocol::Command::name`]
+    /// * `duration` - Time spent handling the request
+    pub fn observe_ipc_request(&self, command: &'static str, duration: Duration) {
+        let mut requests = self.ipc_requests.lock().unwrap();
+        requests
+            .entry(command)
+            .or_insert_with(Histogram::new)
+            .observe(duration);
+    }
+
+    /// Record how long one iteration of the supervisor loop took
+    pub fn observe_supervisor_loop(&self, duration: Duration) {
+        self.supervisor_loop.lock().unwrap().observe(duration);
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    ///
+    /// # Arguments
+    /// * `processes` - Processes managed by the daemon
+    /// * `uptime` - How long the daemon has been running
+    pub fn render(&self, processes: &[&ManagedProcess], uptime: Duration) -> String {
+        let mut processes = processes.to_vec();
+        processes.sort_by_key(|p| p.id.as_u64());
+
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "adasa_daemon_uptime_seconds",
+            "gauge",
+            "Seconds since the daemon started",
+        );
+        sample(
+            &mut out,
+            "adasa_daemon_uptime_seconds",
+            &[],
+            uptime.as_secs_f64(),
+        );
+
+        header(
+            &mut out,
+            "adasa_managed_processes",
+            "gauge",
+            "Number of processes managed by the daemon",
+        );
+        sample(&mut out, "adasa_managed_processes", &[], processes.len());
+
+        header(
+            &mut out,
+            "adasa_ipc_request_duration_seconds",
+            "histogram",
+            "Time spent handling IPC requests",
+        );
+        for (command, histogram) in self.ipc_requests.lock().unwrap().iter() {
+            histogram.write(
+                &mut out,
+                "adasa_ipc_request_duration_seconds",
+                &[("command", command)],
+            );
+        }
+
+        header(
+            &mut out,
+            "adasa_supervisor_loop_duration_seconds",
+            "histogram",
+            "Time spent in one iteration of the supervisor loop",
+        );
+        self.supervisor_loop.lock().unwrap().write(
+            &mut out,
+            "adasa_supervisor_loop_duration_seconds",
+            &[],
+        );
+
+        write_process_metrics(&mut out, &processes);
+
+        out
+    }
+}
+
+impl Default for DaemonMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Per-process metric: name, type, help text and how to read the value
+type ProcessMetric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&ManagedProcess) -> f64,
+);
+
+const PROCESS_METRICS: &[ProcessMetric] = &[
+    (
+        "adasa_process_up",
+        "gauge",
+        "Whether the process is running (1) or not (0)",
+        |p| f64::from(u8::from(p.state.is_active())),
+    ),
+    (
+        "adasa_process_cpu_usage_percent",
+        "gauge",
+        "CPU usage of the process in percent",
+        |p| f64::from(p.stats.cpu_usage),
+    ),
+    (
+        "adasa_process_memory_bytes",
+        "gauge",
+        "Resident memory of the process in bytes",
+        |p| p.stats.memory_usage as f64,
+    ),
+    (
+        "adasa_process_tree_cpu_usage_percent",
+        "gauge",
+        "CPU usage of the process and everything it started in percent",
+        |p| f64::from(p.stats.tree_cpu_usage),
+    ),
+    (
+        "adasa_process_tree_memory_bytes",
+        "gauge",
+        "Memory of the process and everything it started in bytes",
+        |p| p.stats.tree_memory_usage as f64,
+    ),
+    (
+        "adasa_process_uptime_seconds",
+        "gauge",
+        "Seconds since the process was last started (0 when not running)",
+        |p| {
+            if p.state.is_active() {
+                p.stats.uptime().as_secs_f64()
+            } else {
+                0.0
+            }
+        },
+    ),
+    (
+        "adasa_process_restarts_total",
+        "counter",
+        "Number of times the process was restarted",
+        |p| p.stats.restarts as f64,
+    ),
+    (
+        "adasa_process_memory_violations_total",
+        "counter",
+        "Number of times the process exceeded its memory limit",
+        |p| p.stats.memory_violations as f64,
+    ),
+    (
+        "adasa_process_cpu_violations_total",
+        "counter",
+        "Number of times the process exceeded its CPU limit",
+        |p| p.stats.cpu_violations as f64,
+    ),
+    (
+        "adasa_process_oom_kills_total",
+        "counter",
+        "Number of processes in the cgroup of the process killed by the OOM killer",
+        |p| p.stats.oom_kills as f64,
+    ),
+];
+
+fn write_process_metrics(out: &mut String, processes: &[&ManagedProcess]) {
+    let labels: Vec<_> = processes
+        .iter()
+        .map(|p| {
+            let (name, instance) = split_instance(&p.name);
+            (name.to_string(), p.id.to_string(), instance.to_string())
+        })
+        .collect();
+    let label_set = |i: usize| -> [(&str, &str); 3] {
+        let (name, id, instance) = &labels[i];
+        [("name", name), ("id", id), ("instance", instance)]
+    };
+
+    for (name, kind, help, value) in PROCESS_METRICS {
+        header(out, name, kind, help);
+        for (i, p) in processes.iter().enumerate() {
+            sample(out, name, &label_set(i), value(p));
+        }
+    }
+
+    header(
+        out,
+        "adasa_process_state",
+        "gauge",
+        "Current state of the process, as a state label with value 1",
+    );
+    for (i, p) in processes.iter().enumerate() {
+        let state = p.state.to_string();
+        let mut state_labels = label_set(i).to_vec();
+        state_labels.push(("state", &state));
+        sample(out, "adasa_process_state", &state_labels, 1);
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        out.push('{');
+        for (i, (key, value)) in labels.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "{}=\"{}\"", key, escape_label(value));
+        }
+        out.push('}');
+    }
+    let _ = writeln!(out, " {}", value);
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Bind the metrics listener
+///
+/// Binding happens before the daemon starts serving so that a bad address
+/// is reported at startup instead of in a background task.
+pub async fn bind(addr: SocketAddr) -> Result<TcpListener> {
+    TcpListener::bind(addr).await.map_err(|e| {
+        AdasaError::SystemError(format!(
+            "failed to bind metrics listener on {}: {}",
+            addr, e
+        ))
+    })
+}
+
+/// Serve `GET /metrics` over HTTP/1.1 until the task is aborted
+///
+/// # Arguments
+/// * `listener` - Listener returned by [`bind`]
+/// * `render` - Produces the metrics page for each scrape
+pub async fn serve<F, Fut>(listener: TcpListener, render: F)
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = String> + Send,
+{
+    let render = Arc::new(render);
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                tracing::warn!("Failed to accept metrics connection: {}", e);
+                continue;
+            }
+        };
+
+        let render = Arc::clone(&render);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, render.as_ref()).await {
+                tracing::debug!("Metrics connection failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle_connection<F, Fut>(stream: TcpStream, render: &F) -> std::io::Result<()>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = String>,
+{
+    let mut reader = BufReader::new(stream);
+
+    let request_line =
+        match tokio::time::timeout(REQUEST_TIMEOUT, read_request_head(&mut reader)).await {
+            Ok(result) => result?,
+            Err(_) => return Ok(()),
+        };
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let path = path.split('?').next().unwrap_or_default();
+
+    let (status, body) = match (method, path) {
+        ("GET", "/metrics") => ("200 OK", render().await),
+        (_, "/metrics") => ("405 Method Not Allowed", "method not allowed\n".to_string()),
+        _ => ("404 Not Found", "not found\n".to_string()),
+    };
+
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        CONTENT_TYPE,
+        body.len()
+    );
+    if status.starts_with("405") {
+        response.push_str("Allow: GET\r\n");
+    }
+    response.push_str("\r\n");
+    response.push_str(&body);
+
+    let stream = reader.get_mut();
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Read the request line and skip the headers
+async fn read_request_head(reader: &mut BufReader<TcpStream>) -> std::io::Result<String> {
+    let mut request_line = String::new();
+    let mut line = String::new();
+    let mut total = 0;
+
+    loop {
+        line.clear();
+        let n = reader.read_line(&mut line).await?;
+        total += n;
+        if total > MAX_REQUEST_HEAD {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "request head too large",
+            ));
+        }
+        if n == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        if request_line.is_empty() {
+            request_line = line.trim_end().to_string();
+        }
+    }
+
+    Ok(request_line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProcessConfig;
+    use crate::process::ProcessManager;
+    use tokio::io::AsyncReadExt;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let mut histogram = Histogram::new();
+        histogram.observe(Duration::from_micros(200));
+        histogram.observe(Duration::from_millis(20));
+
+        let mut out = String::new();
+        histogram.write(&mut out, "op_seconds", &[("command", "list")]);
+
+        assert!(out.contains("op_seconds_bucket{command=\"list\",le=\"0.0005\"} 1\n"));
+        assert!(out.contains("op_seconds_bucket{command=\"list\",le=\"0.025\"} 2\n"));
+        assert!(out.contains("op_seconds_bucket{command=\"list\",le=\"+Inf\"} 2\n"));
+        assert!(out.contains("op_seconds_count{command=\"list\"} 2\n"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[tokio::test]
+    async fn test_scrape_metrics_endpoint() {
+        let mut manager = ProcessManager::new();
+        let config: ProcessConfig = toml::from_str(
+            r#"
+            name = "sleeper-1"
+            script = "/bin/sleep"
+            args = ["30"]
+            "#,
+        )
+        .unwrap();
+        let id = manager.spawn(config).await.unwrap();
+
+        let metrics = DaemonMetrics::new();
+        metrics.observe_ipc_request("list", Duration::from_millis(2));
+        metrics.observe_supervisor_loop(Duration::from_millis(1));
+        let page = metrics.render(&manager.list(), Duration::from_secs(5));
+
+        let listener = bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve(listener, move || {
+            let page = page.clone();
+            async move { page }
+        }));
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+        assert!(response.contains("# TYPE adasa_process_up gauge\n"));
+        assert!(response.contains(&format!(
+            "adasa_process_up{{name=\"sleeper\",id=\"{}\",instance=\"1\"}} 1\n",
+            id
+        )));
+        assert!(response.contains(&format!(
+            "adasa_process_restarts_total{{name=\"sleeper\",id=\"{}\",instance=\"1\"}} 0\n",
+            id
+        )));
+        assert!(response.contains("adasa_managed_processes 1\n"));
+        assert!(response.contains("adasa_ipc_request_duration_seconds_count{command=\"list\"} 1\n"));
+        assert!(response.contains("adasa_supervisor_loop_duration_seconds_count 1\n"));
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        server.abort();
+        manager.stop(id, false).await.unwrap();
+    }
+}
@@ -207,7 +207,7 @@ mod tests {
 
     #[test]
     fn test_buffer_pool_max_size() {
-        let pool = BufferPool::new(|| Vec::<u8>::new(), 2);
+        let pool = BufferPool::new(Vec::<u8>::new, 2);
         
         // Acquire and release 3 items
         {
@@ -0,0 +1,449 @@
+use crate::config::{HealthCheckConfig, HealthProbe};
+use crate::error::{AdasaError, Result};
+use crate::ipc::protocol::ProcessId;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::process::Command;
+use tokio::task::JoinSet;
+
+/// Tracks the outcome of health probes for a single process
+#[derive(Debug, Clone, Default)]
+pub struct HealthTracker {
+    /// Number of failed probes since the last success
+    consecutive_failures: u32,
+    /// Time the last probe was started
+    last_check: Option<Instant>,
+    /// Error message from the most recent failed probe
+    last_error: Option<String>,
+}
+
+impl HealthTracker {
+    /// Create a new health tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether a probe is due
+    ///
+    /// The first probe runs once the process has been up for one interval,
+    /// giving it time to start listening.
+    pub fn is_due(&self, config: &HealthCheckConfig, uptime: Duration) -> bool {
+        match self.last_check {
+            Some(last) => last.elapsed() >= config.interval,
+            None => uptime >= config.interval,
+        }
+    }
+
+    /// Record that a probe has been started
+    pub fn mark_checked(&mut self) {
+        self.last_check = Some(Instant::now());
+    }
+
+    /// Record a successful probe
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.last_error = None;
+    }
+
+    /// Record a failed probe
+    ///
+    /// # Returns
+    /// * `true` - The failure threshold has been reached
+    /// * `false` - The process is still considered healthy
+    pub fn record_failure(&mut self, error: String, failure_threshold: u32) -> bool {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.last_error = Some(error);
+        self.consecutive_failures >= failure_threshold
+    }
+
+    /// Get the number of consecutive failed probes
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// Get the error from the most recent failed probe, if any
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Reset probe history (call when the process is respawned)
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Run a single health probe, failing if it does not finish within the configured timeout
+pub async fn run_probe(config: &HealthCheckConfig) -> Result<()> {
+    let timeout = config.timeout;
+
+    match tokio::time::timeout(timeout, probe(&config.probe)).await {
+        Ok(result) => result,
+        Err(_) => Err(AdasaError::HealthCheckFailed(format!(
+            "probe timed out after {:?}",
+            timeout
+        ))),
+    }
+}
+
+/// Run several health probes concurrently
+///
+/// # Arguments
+/// * `checks` - Process IDs paired with the health check to run for them
+///
+/// # Returns
+/// The probe result for each process
+pub async fn run_probes(
+    checks: Vec<(ProcessId, HealthCheckConfig)>,
+) -> Vec<(ProcessId, Result<()>)> {
+    let mut tasks = JoinSet::new();
+
+    for (id, config) in checks {
+        tasks.spawn(async move { (id, run_probe(&config).await) });
+    }
+
+    let mut results = Vec::with_capacity(tasks.len());
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(result) => results.push(result),
+            Err(e) => tracing::error!("Health probe task failed: {}", e),
+        }
+    }
+
+    results
+}
+
+/// Run a single probe without a timeout
+pub(crate) async fn probe(probe: &HealthProbe) -> Result<()> {
+    match probe {
+        HealthProbe::Http {
+            url,
+            expected_status,
+        } => http_probe(url, *expected_status).await,
+        HealthProbe::Tcp { host, port } => tcp_probe(host, *port).await,
+        HealthProbe::Exec { command, args } => exec_probe(command, args).await,
+    }
+}
+
+/// Send an HTTP/1.1 GET request and check the response status code
+async fn http_probe(url: &str, expected_status: Option<u16>) -> Result<()> {
+    let (address, host, path) = parse_http_url(url)?;
+
+    let mut stream = TcpStream::connect(&address).await.map_err(|e| {
+        AdasaError::HealthCheckFailed(format!("failed to connect to {}: {}", address, e))
+    })?;
+
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: adasa-health-check\r\nConnection: close\r\n\r\n",
+        path, host
+    );
+    stream.write_all(request.as_bytes()).await.map_err(|e| {
+        AdasaError::HealthCheckFailed(format!("failed to send request to {}: {}", url, e))
+    })?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line).await.map_err(|e| {
+        AdasaError::HealthCheckFailed(format!("failed to read response from {}: {}", url, e))
+    })?;
+
+    // Status line format: HTTP/1.1 200 OK
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| {
+            AdasaError::HealthCheckFailed(format!(
+                "invalid HTTP response from {}: {:?}",
+                url,
+                status_line.trim_end()
+            ))
+        })?;
+
+    let healthy = match expected_status {
+        Some(expected) => status == expected,
+        None => (200..400).contains(&status),
+    };
+
+    if healthy {
+        Ok(())
+    } else {
+        Err(AdasaError::HealthCheckFailed(format!(
+            "{} returned status {}",
+            url, status
+        )))
+    }
+}
+
+/// Split an `http://` URL into the socket address, Host header and request path
+fn parse_http_url(url: &str) -> Result<(String, String, String)> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| AdasaError::HealthCheckFailed(format!("unsupported URL scheme: {}", url)))?;
+
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => (&rest[..idx], &rest[idx..]),
+        None => (rest, "/"),
+    };
+
+    if authority.is_empty() {
+        return Err(AdasaError::HealthCheckFailed(format!(
+            "missing host in URL: {}",
+            url
+        )));
+    }
+
+    // A port is present if the authority ends with ":<digits>" outside of IPv6 brackets
+    let has_port = authority
+        .rsplit_once(':')
+        .map(|(host, port)| {
+            !port.is_empty()
+                && port.bytes().all(|b| b.is_ascii_digit())
+                && (!authority.starts_with('[') || host.ends_with(']'))
+        })
+        .unwrap_or(false);
+
+    let address = if has_port {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+
+    Ok((address, authority.to_string(), path.to_string()))
+}
+
+/// Open a TCP connection to check that the process is accepting connections
+async fn tcp_probe(host: &str, port: u16) -> Result<()> {
+    TcpStream::connect((host, port))
+        .await
+        .map(|_| ())
+        .map_err(|e| {
+            AdasaError::HealthCheckFailed(format!("failed to connect to {}:{}: {}", host, port, e))
+        })
+}
+
+/// Run a command and check that it exits successfully
+async fn exec_probe(command: &Path, args: &[String]) -> Result<()> {
+    let status = Command::new(command)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .status()
+        .await
+        .map_err(|e| {
+            AdasaError::HealthCheckFailed(format!("failed to run {}: {}", command.display(), e))
+        })?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AdasaError::HealthCheckFailed(format!(
+            "{} exited with {}",
+            command.display(),
+            status
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    fn health_check(probe: HealthProbe) -> HealthCheckConfig {
+        HealthCheckConfig {
+            probe,
+            interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(1),
+            failure_threshold: 2,
+        }
+    }
+
+    async fn serve_http_once(status_line: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            let response = format!("{}\r\nContent-Length: 0\r\n\r\n", status_line);
+            let _ = socket.write_all(response.as_bytes()).await;
+        });
+
+        port
+    }
+
+    #[test]
+    fn test_tracker_threshold() {
+        let mut tracker = HealthTracker::new();
+
+        assert!(!tracker.record_failure("first".to_string(), 2));
+        assert_eq!(tracker.consecutive_failures(), 1);
+        assert!(tracker.record_failure("second".to_string(), 2));
+        assert_eq!(tracker.last_error(), Some("second"));
+
+        tracker.record_success();
+        assert_eq!(tracker.consecutive_failures(), 0);
+        assert!(tracker.last_error().is_none());
+    }
+
+    #[test]
+    fn test_tracker_is_due() {
+        let config = health_check(HealthProbe::Tcp {
+            host: "127.0.0.1".to_string(),
+            port: 1,
+        });
+        let mut tracker = HealthTracker::new();
+
+        // First probe waits for one interval of uptime
+        assert!(!tracker.is_due(&config, Duration::from_millis(500)));
+        assert!(tracker.is_due(&config, Duration::from_secs(1)));
+
+        tracker.mark_checked();
+        assert!(!tracker.is_due(&config, Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_parse_http_url() {
+        assert_eq!(
+            parse_http_url("http://localhost:8080/health").unwrap(),
+            (
+                "localhost:8080".to_string(),
+                "localhost:8080".to_string(),
+                "/health".to_string()
+            )
+        );
+        assert_eq!(
+            parse_http_url("http://example.com").unwrap(),
+            (
+                "example.com:80".to_string(),
+                "example.com".to_string(),
+                "/".to_string()
+            )
+        );
+        assert_eq!(
+            parse_http_url("http://[::1]:9000/").unwrap().0,
+            "[::1]:9000"
+        );
+        assert_eq!(parse_http_url("http://[::1]/").unwrap().0, "[::1]:80");
+        assert!(parse_http_url("https://example.com").is_err());
+        assert!(parse_http_url("http:///health").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_http_probe_success() {
+        let port = serve_http_once("HTTP/1.1 200 OK").await;
+        let config = health_check(HealthProbe::Http {
+            url: format!("http://127.0.0.1:{}/health", port),
+            expected_status: None,
+        });
+
+        assert!(run_probe(&config).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_http_probe_error_status() {
+        let port = serve_http_once("HTTP/1.1 503 Service Unavailable").await;
+        let config = health_check(HealthProbe::Http {
+            url: format!("http://127.0.0.1:{}/health", port),
+            expected_status: None,
+        });
+
+        assert!(matches!(
+            run_probe(&config).await,
+            Err(AdasaError::HealthCheckFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_http_probe_expected_status() {
+        let port = serve_http_once("HTTP/1.1 204 No Content").await;
+        let config = health_check(HealthProbe::Http {
+            url: format!("http://127.0.0.1:{}/", port),
+            expected_status: Some(200),
+        });
+
+        assert!(run_probe(&config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tcp_probe() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let config = health_check(HealthProbe::Tcp {
+            host: "127.0.0.1".to_string(),
+            port,
+        });
+
+        assert!(run_probe(&config).await.is_ok());
+
+        // Nothing is listening once the listener is dropped
+        drop(listener);
+        assert!(run_probe(&config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_exec_probe() {
+        let ok = health_check(HealthProbe::Exec {
+            command: PathBuf::from("/bin/true"),
+            args: vec![],
+        });
+        assert!(run_probe(&ok).await.is_ok());
+
+        let failing = health_check(HealthProbe::Exec {
+            command: PathBuf::from("/bin/sh"),
+            args: vec!["-c".to_string(), "exit 3".to_string()],
+        });
+        assert!(run_probe(&failing).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_probe_timeout() {
+        let config = health_check(HealthProbe::Exec {
+            command: PathBuf::from("/bin/sleep"),
+            args: vec!["5".to_string()],
+        });
+
+        let started = Instant::now();
+        let result = run_probe(&config).await;
+
+        assert!(
+            matches!(result, Err(AdasaError::HealthCheckFailed(msg)) if msg.contains("timed out"))
+        );
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn test_run_probes() {
+        let checks = vec![
+            (
+                ProcessId::new(1),
+                health_check(HealthProbe::Exec {
+                    command: PathBuf::from("/bin/true"),
+                    args: vec![],
+                }),
+            ),
+            (
+                ProcessId::new(2),
+                health_check(HealthProbe::Exec {
+                    command: PathBuf::from("/bin/false"),
+                    args: vec![],
+                }),
+            ),
+        ];
+
+        let mut results = run_probes(checks).await;
+        results.sort_by_key(|(id, _)| id.as_u64());
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].1.is_ok());
+        assert!(results[1].1.is_err());
+    }
+}
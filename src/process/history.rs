@@ -0,0 +1,96 @@
+// Bounded history of the resource usage of a process, kept in memory by the daemon
+
+use crate::ipc::protocol::StatsSample;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// How long samples are kept unless the daemon is configured otherwise
+pub const DEFAULT_STATS_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Environment variable overriding the retention, in seconds
+pub const STATS_RETENTION_ENV: &str = "ADASA_STATS_RETENTION";
+
+/// Upper bound on the samples kept per process, whatever the retention
+pub const MAX_SAMPLES: usize = 43_200;
+
+/// Resource usage samples of a process, oldest first
+///
+/// Samples older than the retention are dropped as new ones arrive, and the
+/// history never grows beyond [`MAX_SAMPLES`]. The history is kept across
+/// restarts, so the usage leading up to a crash can still be inspected.
+#[derive(Debug, Clone, Default)]
+pub struct StatsHistory {
+    samples: VecDeque<StatsSample>,
+}
+
+impl StatsHistory {
+    /// Add a sample and drop the samples that fell out of the retention window
+    pub fn push(&mut self, sample: StatsSample, retention: Duration) {
+        let oldest = sample.at.checked_sub(retention);
+        while let Some(first) = self.samples.front() {
+            let expired = oldest.is_some_and(|oldest| first.at < oldest);
+            if !expired && self.samples.len() < MAX_SAMPLES {
+                break;
+            }
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(sample);
+    }
+
+    /// Recorded samples, oldest first
+    pub fn samples(&self) -> impl Iterator<Item = &StatsSample> {
+        self.samples.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn sample(at: SystemTime, memory_usage: u64) -> StatsSample {
+        StatsSample {
+            at,
+            cpu_usage: 0.0,
+            memory_usage,
+            threads: 1,
+            fds: 3,
+        }
+    }
+
+    #[test]
+    fn test_push_drops_expired_samples() {
+        let start = SystemTime::now();
+        let retention = Duration::from_secs(10);
+        let mut history = StatsHistory::default();
+
+        for secs in 0..=20 {
+            history.push(sample(start + Duration::from_secs(secs), secs), retention);
+        }
+
+        let memory: Vec<u64> = history.samples().map(|s| s.memory_usage).collect();
+        assert_eq!(memory, (10..=20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_push_is_bounded() {
+        let start = SystemTime::now();
+        let mut history = StatsHistory::default();
+
+        for i in 0..MAX_SAMPLES as u64 + 5 {
+            history.push(sample(start + Duration::from_millis(i), i), Duration::MAX);
+        }
+
+        assert_eq!(history.len(), MAX_SAMPLES);
+        assert_eq!(history.samples().next().unwrap().memory_usage, 5);
+    }
+}
@@ -0,0 +1,47 @@
+// Names of the instances of a process, such as `web-0`, `web-1`, ...
+
+/// Split an instance name such as `web-2` into its base name and instance number
+///
+/// Names without a numeric suffix are instance 0 of themselves.
+pub fn split_instance(name: &str) -> (&str, &str) {
+    match name.rsplit_once('-') {
+        Some((base, index))
+            if !base.is_empty()
+                && !index.is_empty()
+                && index.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            (base, index)
+        }
+        _ => (name, "0"),
+    }
+}
+
+/// Check whether `name` is `base` itself or one of its numbered instances (`base-0`, `base-1`, ...)
+pub fn is_instance_of(name: &str, base: &str) -> bool {
+    name == base || split_instance(name).0 == base
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_instance() {
+        assert_eq!(split_instance("web-2"), ("web", "2"));
+        assert_eq!(split_instance("api-server-10"), ("api-server", "10"));
+        assert_eq!(split_instance("api-server"), ("api-server", "0"));
+        assert_eq!(split_instance("web"), ("web", "0"));
+        assert_eq!(split_instance("-1"), ("-1", "0"));
+    }
+
+    #[test]
+    fn test_is_instance_of() {
+        assert!(is_instance_of("web", "web"));
+        assert!(is_instance_of("web-0", "web"));
+        assert!(is_instance_of("web-12", "web"));
+        assert!(!is_instance_of("web-api", "web"));
+        assert!(!is_instance_of("web-api-1", "web"));
+        assert!(!is_instance_of("web-", "web"));
+        assert!(!is_instance_of("webapp", "web"));
+    }
+}
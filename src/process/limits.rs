@@ -1,3 +1,5 @@
+use crate::config::units::ByteSize;
+use crate::config::{IoniceClass, IoniceConfig, ProcessConfig, RlimitConfig};
 use crate::error::{AdasaError, Result};
 use tracing::{info, warn};
 
@@ -7,10 +9,18 @@ use nix::sys::resource::{setrlimit, Resource};
 /// Resource limits configuration for a process
 #[derive(Debug, Clone)]
 pub struct ResourceLimits {
-    /// Maximum memory in bytes
+    /// Maximum memory in bytes, enforced by monitoring
     pub max_memory: Option<u64>,
     /// Maximum CPU percentage (0-100)
     pub max_cpu: Option<u32>,
+    /// Resource limits set with `setrlimit` before exec
+    pub rlimits: RlimitConfig,
+    /// Scheduling priority
+    pub nice: Option<i32>,
+    /// I/O scheduling class and priority
+    pub ionice: Option<IoniceConfig>,
+    /// CPU cores the process may run on
+    pub cpu_affinity: Vec<usize>,
 }
 
 impl ResourceLimits {
@@ -19,30 +29,99 @@ impl ResourceLimits {
         Self {
             max_memory,
             max_cpu,
+            rlimits: RlimitConfig::default(),
+            nice: None,
+            ionice: None,
+            cpu_affinity: Vec::new(),
         }
     }
 
-    /// Apply memory limits to the current process (must be called before exec)
-    #[cfg(unix)]
-    pub fn apply_memory_limit(&self) -> Result<()> {
-        if let Some(max_memory) = self.max_memory {
-            info!("Setting memory limit to {} bytes", max_memory);
+    /// Collect all limits of a process configuration
+    pub fn from_config(config: &ProcessConfig) -> Self {
+        Self {
+            rlimits: config.limits.clone(),
+            nice: config.nice,
+            ionice: config.ionice,
+            cpu_affinity: config.cpu_affinity.clone(),
+            ..Self::new(config.max_memory.map(ByteSize::bytes), config.max_cpu)
+        }
+    }
 
-            // Set both soft and hard limits for virtual memory (RLIMIT_AS)
-            setrlimit(Resource::RLIMIT_AS, max_memory, max_memory).map_err(|e| {
-                AdasaError::ResourceLimitError(format!("Failed to set memory limit: {}", e))
-            })?;
+    /// Check whether anything has to be applied to the process before exec
+    pub fn has_spawn_limits(&self) -> bool {
+        self.rlimits != RlimitConfig::default()
+            || self.nice.is_some()
+            || self.ionice.is_some()
+            || !self.cpu_affinity.is_empty()
+    }
+
+    /// Apply the rlimits, nice level, I/O priority and CPU affinity to the current process
+    ///
+    /// Called in the child between fork and exec, so it only makes system calls and
+    /// neither allocates nor logs. It runs before privileges are dropped, so a daemon
+    /// running as root can raise limits and priorities for an unprivileged user.
+    pub fn apply_before_exec(&self) -> nix::Result<()> {
+        use nix::errno::Errno;
+        use nix::libc;
+
+        let resources = [
+            Resource::RLIMIT_NOFILE,
+            Resource::RLIMIT_NPROC,
+            Resource::RLIMIT_CORE,
+            Resource::RLIMIT_STACK,
+            Resource::RLIMIT_AS,
+        ];
+        for (resource, (_, value)) in resources.into_iter().zip(self.rlimits.entries()) {
+            if let Some(value) = value {
+                setrlimit(resource, value.soft(), value.hard())?;
+            }
+        }
 
-            info!("Memory limit applied successfully");
+        if let Some(nice) = self.nice {
+            // SAFETY: setpriority only reads its integer arguments
+            if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } == -1 {
+                return Err(Errno::last());
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            use nix::sched::{sched_setaffinity, CpuSet};
+            use nix::unistd::Pid;
+
+            if let Some(ionice) = self.ionice {
+                const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+                const IOPRIO_CLASS_SHIFT: u32 = 13;
+
+                let (class, level) = match ionice.class {
+                    IoniceClass::Realtime => (1, ionice.level),
+                    IoniceClass::BestEffort => (2, ionice.level),
+                    IoniceClass::Idle => (3, 0),
+                };
+                let ioprio = (class << IOPRIO_CLASS_SHIFT) | u32::from(level);
+                // SAFETY: ioprio_set only reads its integer arguments
+                let result =
+                    unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) };
+                if result == -1 {
+                    return Err(Errno::last());
+                }
+            }
+
+            if !self.cpu_affinity.is_empty() {
+                let mut cpus = CpuSet::new();
+                for &core in &self.cpu_affinity {
+                    cpus.set(core)?;
+                }
+                sched_setaffinity(Pid::from_raw(0), &cpus)?;
+            }
         }
-        Ok(())
-    }
 
-    #[cfg(not(unix))]
-    pub fn apply_memory_limit(&self) -> Result<()> {
-        if self.max_memory.is_some() {
-            warn!("Memory limits are not supported on this platform");
+        // I/O priorities and CPU affinity are only supported on Linux
+        #[cfg(not(target_os = "linux"))]
+        if self.ionice.is_some() || !self.cpu_affinity.is_empty() {
+            return Err(Errno::ENOSYS);
         }
+
         Ok(())
     }
 
@@ -66,19 +145,36 @@ pub mod cgroup {
 
     const CGROUP_BASE: &str = "/sys/fs/cgroup";
 
-    /// CGroup manager for CPU throttling
+    /// Directory under which each process gets its own cgroup, unless configured otherwise
+    pub const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup/adasa";
+
+    /// Environment variable the daemon reads the cgroup root from
+    pub const CGROUP_ROOT_ENV: &str = "ADASA_CGROUP_ROOT";
+
+    /// Controllers enabled for the cgroups of the processes
+    const CONTROLLERS: [&str; 5] = ["cpu", "memory", "pids", "io", "cpuset"];
+
+    /// CGroup manager for CPU throttling and kernel-enforced limits
     #[derive(Debug)]
     pub struct CGroupManager {
+        root: PathBuf,
         cgroup_path: PathBuf,
         process_name: String,
     }
 
     impl CGroupManager {
-        /// Create a new cgroup manager for a process
+        /// Create a new cgroup manager for a process under [`DEFAULT_CGROUP_ROOT`]
         pub fn new(process_name: String) -> Self {
-            let cgroup_path = PathBuf::from(CGROUP_BASE).join("adasa").join(&process_name);
+            Self::with_root(DEFAULT_CGROUP_ROOT, process_name)
+        }
+
+        /// Create a new cgroup manager for a process under `root`
+        pub fn with_root<P: AsRef<Path>>(root: P, process_name: String) -> Self {
+            let root = root.as_ref().to_path_buf();
+            let cgroup_path = root.join(&process_name);
 
             Self {
+                root,
                 cgroup_path,
                 process_name,
             }
@@ -89,32 +185,63 @@ pub mod cgroup {
             Path::new(CGROUP_BASE).join("cgroup.controllers").exists()
         }
 
+        /// Path of the cgroup of the process
+        pub fn path(&self) -> &Path {
+            &self.cgroup_path
+        }
+
+        /// Check whether the cgroup has been created
+        pub fn exists(&self) -> bool {
+            self.cgroup_path.exists()
+        }
+
+        /// Path of the file that moves a process into the cgroup when its PID is written to it
+        pub fn procs_file(&self) -> PathBuf {
+            self.cgroup_path.join("cgroup.procs")
+        }
+
+        /// Check whether the root is on a cgroup v2 hierarchy
+        ///
+        /// The root may not exist yet, so its closest existing ancestor is checked.
+        fn is_root_on_cgroups_v2(&self) -> bool {
+            self.root
+                .ancestors()
+                .find(|dir| dir.exists())
+                .is_some_and(|dir| dir.join("cgroup.controllers").exists())
+        }
+
         /// Setup cgroup for the process
+        ///
+        /// Creates the root and the cgroup of the process, and enables every controller
+        /// the root can delegate. Controllers that cannot be enabled are skipped; writing
+        /// a limit that needs one fails later in [`CGroupManager::apply_config`].
         pub fn setup(&self) -> Result<()> {
-            if !Self::is_cgroups_v2_available() {
-                return Err(AdasaError::ResourceLimitError(
-                    "cgroups v2 not available on this system".to_string(),
-                ));
+            if !self.is_root_on_cgroups_v2() {
+                return Err(AdasaError::ResourceLimitError(format!(
+                    "cgroups v2 not available at {}",
+                    self.root.display()
+                )));
             }
 
-            // Create the adasa parent cgroup if it doesn't exist
-            let adasa_cgroup = PathBuf::from(CGROUP_BASE).join("adasa");
-            if !adasa_cgroup.exists() {
-                fs::create_dir(&adasa_cgroup).map_err(|e| {
-                    AdasaError::ResourceLimitError(format!(
-                        "Failed to create adasa cgroup: {}. You may need root privileges.",
-                        e
-                    ))
-                })?;
+            fs::create_dir_all(&self.root).map_err(|e| {
+                AdasaError::ResourceLimitError(format!(
+                    "Failed to create cgroup root {}: {}. You may need root privileges.",
+                    self.root.display(),
+                    e
+                ))
+            })?;
 
-                // Enable cpu controller in parent
-                let subtree_control = adasa_cgroup.join("cgroup.subtree_control");
-                fs::write(&subtree_control, "+cpu").map_err(|e| {
-                    AdasaError::ResourceLimitError(format!(
-                        "Failed to enable cpu controller: {}",
+            // Enable the controllers one by one, so one the kernel lacks does not block the rest
+            let subtree_control = self.root.join("cgroup.subtree_control");
+            for controller in CONTROLLERS {
+                if let Err(e) = fs::write(&subtree_control, format!("+{}", controller)) {
+                    tracing::debug!(
+                        "Failed to enable {} controller in {}: {}",
+                        controller,
+                        self.root.display(),
                         e
-                    ))
-                })?;
+                    );
+                }
             }
 
             // Create process-specific cgroup
@@ -133,11 +260,7 @@ pub mod cgroup {
                 self.setup()?;
             }
 
-            // Add process to cgroup
-            let procs_file = self.cgroup_path.join("cgroup.procs");
-            fs::write(&procs_file, pid.to_string()).map_err(|e| {
-                AdasaError::ResourceLimitError(format!("Failed to add process to cgroup: {}", e))
-            })?;
+            self.add_process(pid)?;
 
             // Set CPU quota
             // cpu.max format: "$MAX $PERIOD"
@@ -160,6 +283,119 @@ pub mod cgroup {
             Ok(())
         }
 
+        /// Write the limits of a process to its cgroup
+        ///
+        /// Limits that are not configured are reset to the kernel default, so a changed
+        /// configuration takes effect on the next start. Writing a configured limit fails
+        /// if its controller is not available.
+        pub fn apply_config(&self, config: &ProcessConfig) -> Result<()> {
+            let limits = config.cgroup.clone().unwrap_or_default();
+
+            let settings = [
+                (
+                    "cpu.max",
+                    config
+                        .max_cpu
+                        .map(|cpu| format!("{} 100000", cpu as u64 * 1000)),
+                    "max 100000".to_string(),
+                ),
+                (
+                    "memory.max",
+                    limits.memory_max.map(|v| v.bytes().to_string()),
+                    "max".to_string(),
+                ),
+                (
+                    "memory.high",
+                    limits.memory_high.map(|v| v.bytes().to_string()),
+                    "max".to_string(),
+                ),
+                (
+                    "pids.max",
+                    limits.pids_max.map(|v| v.to_string()),
+                    "max".to_string(),
+                ),
+                (
+                    "io.weight",
+                    limits.io_weight.map(|w| format!("default {}", w)),
+                    "default 100".to_string(),
+                ),
+                ("cpuset.cpus", limits.cpus.clone(), String::new()),
+            ];
+
+            for (file, value, default) in settings {
+                let path = self.cgroup_path.join(file);
+                match value {
+                    Some(value) => fs::write(&path, &value).map_err(|e| {
+                        AdasaError::ResourceLimitError(format!(
+                            "Failed to set {} to '{}' for {}: {}",
+                            file, value, self.process_name, e
+                        ))
+                    })?,
+                    None if path.exists() => {
+                        if let Err(e) = fs::write(&path, default) {
+                            tracing::debug!("Failed to reset {}: {}", path.display(), e);
+                        }
+                    }
+                    None => {}
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Number of processes in the cgroup that the kernel OOM killer has killed
+        ///
+        /// Read from `memory.events`; 0 if the memory controller is not enabled.
+        pub fn oom_kills(&self) -> u64 {
+            fs::read_to_string(self.cgroup_path.join("memory.events"))
+                .ok()
+                .and_then(|events| {
+                    events.lines().find_map(|line| {
+                        line.strip_prefix("oom_kill ")
+                            .and_then(|count| count.trim().parse().ok())
+                    })
+                })
+                .unwrap_or(0)
+        }
+
+        /// Memory used by the processes in the cgroup, including page cache (`memory.current`)
+        pub fn memory_current(&self) -> Option<u64> {
+            fs::read_to_string(self.cgroup_path.join("memory.current"))
+                .ok()?
+                .trim()
+                .parse()
+                .ok()
+        }
+
+        /// CPU time used by the processes in the cgroup so far, in microseconds (`cpu.stat`)
+        pub fn cpu_usage_usec(&self) -> Option<u64> {
+            fs::read_to_string(self.cgroup_path.join("cpu.stat"))
+                .ok()?
+                .lines()
+                .find_map(|line| line.strip_prefix("usage_usec "))?
+                .trim()
+                .parse()
+                .ok()
+        }
+
+        /// Move a process into the cgroup, creating the cgroup if needed
+        pub fn add_process(&self, pid: u32) -> Result<()> {
+            if !self.cgroup_path.exists() {
+                self.setup()?;
+            }
+
+            fs::write(self.procs_file(), pid.to_string()).map_err(|e| {
+                AdasaError::ResourceLimitError(format!("Failed to add process to cgroup: {}", e))
+            })
+        }
+
+        /// PIDs of the processes currently in the cgroup
+        pub fn procs(&self) -> Vec<u32> {
+            fs::read_to_string(self.procs_file())
+                .map(|contents| contents.lines().filter_map(|l| l.parse().ok()).collect())
+                .unwrap_or_default()
+        }
+
         /// Remove CPU limit from a process
         pub fn remove_cpu_limit(&self, pid: u32) -> Result<()> {
             if !self.cgroup_path.exists() {
@@ -196,26 +432,68 @@ pub mod cgroup {
 #[cfg(not(target_os = "linux"))]
 pub mod cgroup {
     use super::*;
+    use std::path::{Path, PathBuf};
+
+    pub const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup/adasa";
+
+    pub const CGROUP_ROOT_ENV: &str = "ADASA_CGROUP_ROOT";
 
     #[derive(Debug)]
     pub struct CGroupManager {
+        cgroup_path: PathBuf,
         process_name: String,
     }
 
     impl CGroupManager {
         pub fn new(process_name: String) -> Self {
-            Self { process_name }
+            Self::with_root(DEFAULT_CGROUP_ROOT, process_name)
+        }
+
+        pub fn with_root<P: AsRef<Path>>(root: P, process_name: String) -> Self {
+            Self {
+                cgroup_path: root.as_ref().join(&process_name),
+                process_name,
+            }
         }
 
         pub fn is_cgroups_v2_available() -> bool {
             false
         }
 
+        pub fn path(&self) -> &Path {
+            &self.cgroup_path
+        }
+
+        pub fn procs_file(&self) -> PathBuf {
+            self.cgroup_path.join("cgroup.procs")
+        }
+
+        pub fn exists(&self) -> bool {
+            false
+        }
+
         pub fn setup(&self) -> Result<()> {
-            warn!("CPU throttling via cgroups is only supported on Linux");
+            Err(AdasaError::ResourceLimitError(
+                "cgroups are only supported on Linux".to_string(),
+            ))
+        }
+
+        pub fn apply_config(&self, _config: &ProcessConfig) -> Result<()> {
             Ok(())
         }
 
+        pub fn oom_kills(&self) -> u64 {
+            0
+        }
+
+        pub fn memory_current(&self) -> Option<u64> {
+            None
+        }
+
+        pub fn cpu_usage_usec(&self) -> Option<u64> {
+            None
+        }
+
         pub fn apply_cpu_limit(&self, _pid: u32, _cpu_percent: u32) -> Result<()> {
             warn!(
                 "CPU throttling is not supported on this platform for process {}",
@@ -224,6 +502,18 @@ pub mod cgroup {
             Ok(())
         }
 
+        pub fn add_process(&self, _pid: u32) -> Result<()> {
+            warn!(
+                "cgroups are not supported on this platform for process {}",
+                self.process_name
+            );
+            Ok(())
+        }
+
+        pub fn procs(&self) -> Vec<u32> {
+            Vec::new()
+        }
+
         pub fn remove_cpu_limit(&self, _pid: u32) -> Result<()> {
             Ok(())
         }
@@ -263,8 +553,74 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_cgroup_manager_creation() {
-        let _manager = cgroup::CGroupManager::new("test-process".to_string());
+        let manager = cgroup::CGroupManager::new("test-process".to_string());
         // Just verify it can be created
-        assert!(true);
+        drop(manager);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cgroup_apply_config() {
+        // A directory with a `cgroup.controllers` file stands in for the cgroup v2 hierarchy
+        let hierarchy = tempfile::tempdir().unwrap();
+        std::fs::write(
+            hierarchy.path().join("cgroup.controllers"),
+            "cpu memory pids",
+        )
+        .unwrap();
+
+        let mut config: ProcessConfig = toml::from_str(
+            r#"
+            name = "web"
+            script = "/bin/true"
+            max_cpu = 50
+
+            [cgroup]
+            memory_max = "256M"
+            pids_max = 64
+            io_weight = 200
+            cpus = "0-1"
+            "#,
+        )
+        .unwrap();
+
+        let manager =
+            cgroup::CGroupManager::with_root(hierarchy.path().join("adasa"), "web".to_string());
+        manager.setup().unwrap();
+        manager.apply_config(&config).unwrap();
+
+        let read = |file: &str| std::fs::read_to_string(manager.path().join(file)).unwrap();
+        assert_eq!(read("cpu.max"), "50000 100000");
+        assert_eq!(read("memory.max"), "268435456");
+        assert_eq!(read("pids.max"), "64");
+        assert_eq!(read("io.weight"), "default 200");
+        assert_eq!(read("cpuset.cpus"), "0-1");
+        assert!(!manager.path().join("memory.high").exists());
+
+        // Limits removed from the configuration go back to the kernel defaults
+        config.max_cpu = None;
+        config.cgroup = None;
+        manager.apply_config(&config).unwrap();
+        assert_eq!(read("cpu.max"), "max 100000");
+        assert_eq!(read("memory.max"), "max");
+        assert_eq!(read("io.weight"), "default 100");
+
+        assert_eq!(manager.oom_kills(), 0);
+        std::fs::write(
+            manager.path().join("memory.events"),
+            "low 0\nhigh 4\nmax 2\noom 1\noom_kill 1\n",
+        )
+        .unwrap();
+        assert_eq!(manager.oom_kills(), 1);
+
+        assert_eq!(manager.memory_current(), None);
+        std::fs::write(manager.path().join("memory.current"), "1048576\n").unwrap();
+        std::fs::write(
+            manager.path().join("cpu.stat"),
+            "usage_usec 250000\nuser_usec 200000\nsystem_usec 50000\n",
+        )
+        .unwrap();
+        assert_eq!(manager.memory_current(), Some(1048576));
+        assert_eq!(manager.cpu_usage_usec(), Some(250000));
     }
 }
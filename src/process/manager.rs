@@ -1,19 +1,59 @@
-use crate::config::{LimitAction, ProcessConfig};
+use crate::config::units::{format_duration, ByteSize};
+use crate::config::{
+    BatchSize, HealthCheckConfig, KillMode, LimitAction, ProcessConfig, RestartMode,
+};
 use crate::error::{AdasaError, Result};
-use crate::ipc::protocol::ProcessId;
+use crate::ipc::protocol::{Event, LimitResource, ProcessHistory, ProcessId, StatsSample};
+use crate::logs::LogManager;
 use crate::perf::PerfTimer;
+use crate::process::health;
+use crate::process::history::DEFAULT_STATS_RETENTION;
+use crate::process::instance::{is_instance_of, split_instance};
+use crate::process::limits::cgroup::{CGroupManager, DEFAULT_CGROUP_ROOT};
 use crate::process::monitor::ProcessMonitor;
-use crate::process::spawner::spawn_process;
+use crate::process::procfs;
+use crate::process::readiness::{self, ReadinessWatch};
+use crate::process::restart::{ExitKind, RestartPolicy};
+use crate::process::spawner::{spawn_process_in, SpawnedProcess};
+use crate::process::tree::ProcessTree;
 use crate::process::types::{ManagedProcess, ProcessState};
-use nix::sys::signal::{self, Signal};
-use nix::unistd::Pid;
-use std::collections::HashMap;
-use std::time::Duration;
+use nix::sys::signal::Signal;
+use std::borrow::BorrowMut;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{broadcast, RwLock, RwLockMappedWriteGuard, RwLockWriteGuard};
+use tokio::task::JoinSet;
+
+/// How often to check whether a replacement exited while waiting for readiness
+const READINESS_EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Events buffered per subscriber before the slowest one starts missing events
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// How often to check whether the processes being stopped have exited, when they cannot be waited on
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long to wait for processes to disappear after SIGKILL
+const KILL_TIMEOUT: Duration = Duration::from_secs(1);
 
 pub struct ProcessManager {
     processes: HashMap<ProcessId, ManagedProcess>,
     next_id: u64,
     monitor: ProcessMonitor,
+    /// Directory to capture output of respawned processes into
+    log_dir: Option<PathBuf>,
+    /// State changes, sent to every subscriber
+    events: broadcast::Sender<Event>,
+    /// Directory under which each process that needs a cgroup gets its own
+    cgroup_root: PathBuf,
+    /// How long resource usage samples are kept
+    stats_retention: Duration,
+    /// Unhealthy processes signalled to stop for a restart, by when they get SIGKILL
+    unhealthy_stops: HashMap<ProcessId, tokio::time::Instant>,
+    /// Restarts waiting out their backoff delay, by when they are due
+    pending_restarts: HashMap<ProcessId, tokio::time::Instant>,
 }
 
 impl ProcessManager {
@@ -22,10 +62,198 @@ impl ProcessManager {
             processes: HashMap::new(),
             next_id: 1,
             monitor: ProcessMonitor::new(),
+            log_dir: None,
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            cgroup_root: PathBuf::from(DEFAULT_CGROUP_ROOT),
+            stats_retention: DEFAULT_STATS_RETENTION,
+            unhealthy_stops: HashMap::new(),
+            pending_restarts: HashMap::new(),
+        }
+    }
+
+    /// Create a process manager that captures the output of respawned processes into `log_dir`
+    pub fn with_log_dir<P: AsRef<Path>>(log_dir: P) -> Self {
+        Self {
+            log_dir: Some(log_dir.as_ref().to_path_buf()),
+            ..Self::new()
         }
     }
 
+    /// Create the cgroups of processes started from now on under `root` instead of [`DEFAULT_CGROUP_ROOT`]
+    pub fn set_cgroup_root<P: AsRef<Path>>(&mut self, root: P) {
+        self.cgroup_root = root.as_ref().to_path_buf();
+    }
+
+    /// Keep resource usage samples for `retention` instead of [`DEFAULT_STATS_RETENTION`]
+    pub fn set_stats_retention(&mut self, retention: Duration) {
+        self.stats_retention = retention;
+    }
+
     pub async fn spawn(&mut self, config: ProcessConfig) -> Result<ProcessId> {
+        self.spawn_watched(config, None, None).await
+    }
+
+    /// Register a process from persisted daemon state under its original ID
+    ///
+    /// Processes that were running are spawned again, and scheduled jobs wait for
+    /// their next run. Stopped processes are spawned again only if their restart mode
+    /// is `always`. Other stopped processes and those that errored, completed or
+    /// crash-looped are registered without a child and keep that state until they
+    /// are restarted.
+    /// A process that fails to spawn is kept as errored, so its ID stays taken.
+    ///
+    /// # Arguments
+    /// * `id` - ID the process had before the daemon restarted
+    /// * `config` - Persisted configuration of the process
+    /// * `state` - State the process was in when the daemon state was saved
+    pub async fn restore(
+        &mut self,
+        id: ProcessId,
+        config: ProcessConfig,
+        state: ProcessState,
+    ) -> Result<()> {
+        if self.processes.contains_key(&id) {
+            return Err(AdasaError::ProcessAlreadyExists(id.to_string()));
+        }
+
+        // Only `always` brings back a process the user stopped
+        let resume = match state {
+            ProcessState::Starting
+            | ProcessState::Running
+            | ProcessState::Unhealthy
+            | ProcessState::Scheduled => true,
+            ProcessState::Stopping | ProcessState::Stopped => {
+                config.restart_mode() == RestartMode::Always
+            }
+            _ => false,
+        };
+
+        if resume {
+            return match self.spawn_watched(config.clone(), None, Some(id)).await {
+                Ok(_) => Ok(()),
+                Err(e @ AdasaError::ProcessAlreadyExists(_)) => Err(e),
+                Err(e) => {
+                    self.register_inactive(id, config, ProcessState::Errored);
+                    Err(e)
+                }
+            };
+        }
+
+        let state = match state {
+            // The daemon went down while the process was being stopped
+            ProcessState::Stopping => ProcessState::Stopped,
+            state => state,
+        };
+
+        if self.processes.values().any(|p| p.name == config.name) {
+            return Err(AdasaError::ProcessAlreadyExists(config.name));
+        }
+        self.register_inactive(id, config, state);
+
+        Ok(())
+    }
+
+    /// Take over a process that an earlier daemon left running
+    ///
+    /// The process is only adopted if `pid` still belongs to the process that started
+    /// at `start_time`, so a reused PID is never mistaken for it. An adopted process is
+    /// not a child of this daemon: its exit is noticed by polling, its exit status is
+    /// unknown, and its output is no longer captured.
+    ///
+    /// # Arguments
+    /// * `id` - ID the process had before the daemon restarted
+    /// * `config` - Persisted configuration of the process
+    /// * `pid` - PID the process was running under
+    /// * `start_time` - Start time of the process from `/proc/<pid>/stat`
+    pub fn adopt(
+        &mut self,
+        id: ProcessId,
+        config: ProcessConfig,
+        pid: u32,
+        start_time: u64,
+    ) -> Result<()> {
+        if self.processes.contains_key(&id) {
+            return Err(AdasaError::ProcessAlreadyExists(id.to_string()));
+        }
+        if self.processes.values().any(|p| p.name == config.name) {
+            return Err(AdasaError::ProcessAlreadyExists(config.name));
+        }
+        if !procfs::is_same_process(pid, start_time) {
+            return Err(AdasaError::ProcessNotFound(format!(
+                "{} (PID {} is no longer running)",
+                config.name, pid
+            )));
+        }
+
+        let id = self.assign_id(Some(id));
+        let mut process =
+            ManagedProcess::inactive(id, config.name.clone(), config, ProcessState::Running);
+        process.stats.pid = pid;
+        if let Some(started_at) = procfs::started_at(start_time) {
+            process.stats.started_at = started_at;
+        }
+        process.adopted_start_time = Some(start_time);
+        process.cgroup_manager = self.prepare_cgroup(&process.name, &process.config);
+
+        tracing::info!("Adopted running process {} (PID: {})", process.name, pid);
+        self.processes.insert(id, process);
+
+        Ok(())
+    }
+
+    fn register_inactive(&mut self, id: ProcessId, config: ProcessConfig, state: ProcessState) {
+        let id = self.assign_id(Some(id));
+        let process = ManagedProcess::inactive(id, config.name.clone(), config, state);
+        self.processes.insert(id, process);
+    }
+
+    /// Allocate a fresh ID, e.g. for a restored process whose old ID is taken
+    pub fn allocate_id(&mut self) -> ProcessId {
+        self.assign_id(None)
+    }
+
+    /// Use `id` if one is given, otherwise allocate the next free ID
+    fn assign_id(&mut self, id: Option<ProcessId>) -> ProcessId {
+        let id = id.unwrap_or(ProcessId::new(self.next_id));
+        self.next_id = self.next_id.max(id.as_u64() + 1);
+        id
+    }
+
+    /// Subscribe to state changes of the managed processes
+    ///
+    /// A subscriber that falls more than a few hundred events behind misses the
+    /// oldest ones and receives [`broadcast::error::RecvError::Lagged`].
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// Send an event to all subscribers
+    pub fn emit(&self, event: Event) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(event);
+    }
+
+    /// Spawn a process and prepare to wait for it to become ready
+    ///
+    /// Pass the returned watch to [`ProcessManager::wait_until_ready`]. Processes
+    /// without a readiness check only have to stay up for `fallback_delay`.
+    pub async fn spawn_with_readiness(
+        &mut self,
+        config: ProcessConfig,
+        fallback_delay: Duration,
+    ) -> Result<(ProcessId, ReadinessWatch)> {
+        let mut watch = ReadinessWatch::prepare(config.readiness.as_ref(), fallback_delay)?;
+        let id = self.spawn_watched(config, Some(&mut watch), None).await?;
+        Ok((id, watch))
+    }
+
+    /// Spawn a process under `id`, or under a newly allocated ID if `id` is `None`
+    async fn spawn_watched(
+        &mut self,
+        config: ProcessConfig,
+        watch: Option<&mut ReadinessWatch>,
+        id: Option<ProcessId>,
+    ) -> Result<ProcessId> {
         let _timer = PerfTimer::with_threshold("spawn_process", 200);
         
         if self.processes.values().any(|p| p.name == config.name) {
@@ -34,42 +262,93 @@ impl ProcessManager {
 
         config.validate()?;
 
-        let spawned = spawn_process(&config).await?;
-        let name = spawned.name.clone();
-        let id = ProcessId::new(self.next_id);
-        self.next_id += 1;
+        // Scheduled jobs are registered now and started by `run_due_jobs`
+        if config.schedule.is_some() {
+            let id = self.assign_id(id);
 
-        let mut managed = ManagedProcess::new(id, name, config.clone(), spawned);
+            let mut managed = ManagedProcess::scheduled(id, config.name.clone(), config);
+            managed.cgroup_manager = self.prepare_cgroup(&managed.name, &managed.config);
+            tracing::info!(
+                "Scheduled job {} registered, next run at {:?}",
+                managed.name,
+                managed.next_run
+            );
+            self.processes.insert(id, managed);
 
-        if let Some(cpu_limit) = config.max_cpu {
-            if let Some(ref cgroup_manager) = managed.cgroup_manager {
-                if let Err(e) = cgroup_manager.apply_cpu_limit(managed.stats.pid, cpu_limit) {
-                    tracing::warn!(
-                        "Failed to apply CPU limit to process {}: {}",
-                        managed.name,
-                        e
-                    );
+            return Ok(id);
+        }
+
+        let cgroup = self.prepare_cgroup(&config.name, &config);
+        let mut line_tap = None;
+        let mut spawned = match watch {
+            Some(watch) => {
+                line_tap = watch.take_line_tap();
+                let mut spawn_config = config.clone();
+                if let Some((key, value)) = watch.spawn_env() {
+                    spawn_config.env.insert(key, value);
                 }
+                spawn_process_in(&spawn_config, cgroup.as_ref()).await?
             }
-        }
+            None => spawn_process_in(&config, cgroup.as_ref()).await?,
+        };
+        let name = spawned.name.clone();
+        let id = self.assign_id(id);
+
+        self.attach_output(id, &name, &mut spawned, line_tap);
 
+        let mut managed = ManagedProcess::new(id, name, config.clone(), spawned);
+        managed.cgroup_manager = cgroup;
         managed.mark_running();
+        self.emit(Event::ProcessStarted {
+            id,
+            name: managed.name.clone(),
+            pid: managed.stats.pid,
+        });
         self.processes.insert(id, managed);
 
         Ok(id)
     }
 
     pub async fn stop(&mut self, id: ProcessId, force: bool) -> Result<()> {
+        self.cancel_restart(id);
+
         let process = self
             .processes
             .get_mut(&id)
             .ok_or_else(|| AdasaError::ProcessNotFound(id.to_string()))?;
 
         process.mark_stopping();
+        process.next_run = None;
+
+        for mut run in process.overlapping_runs.drain(..) {
+            let _ = run.kill().await;
+        }
+
+        // A job between runs, or a process whose exit was already collected, has no child to signal
+        let Some(child) = process.child.as_mut() else {
+            let adopted = process.adopted_start_time.take();
+            if let Some(start_time) = adopted {
+                Self::stop_adopted(process, start_time, force).await?;
+            }
+            process.mark_stopped();
+            tracing::info!("Process {} stopped successfully", process.name);
+
+            if adopted.is_some() {
+                let event = Self::exited_event(process);
+                self.emit(event);
+            }
+            return Ok(());
+        };
 
         let pid = process.stats.pid;
-        let nix_pid = Pid::from_raw(pid as i32);
         let process_name = process.name.clone();
+        let tree = ProcessTree::capture(
+            pid,
+            process.config.kill_mode,
+            process.cgroup_manager.as_ref(),
+        );
+        let timeout = process.config.stop_timeout;
+        let deadline = tokio::time::Instant::now() + timeout;
 
         if force {
             tracing::info!(
@@ -77,7 +356,7 @@ impl ProcessManager {
                 process_name,
                 pid
             );
-            signal::kill(nix_pid, Signal::SIGKILL).map_err(|e| {
+            tree.signal(Signal::SIGKILL).map_err(|e| {
                 AdasaError::StopError(
                     process_name.clone(),
                     format!("Failed to send SIGKILL: {}", e),
@@ -87,27 +366,27 @@ impl ProcessManager {
             let stop_signal = Self::parse_signal(&process.config.stop_signal)?;
 
             tracing::info!(
-                "Gracefully stopping process {} (PID: {}) with {}",
+                "Gracefully stopping process {} (PID: {}, kill mode: {}) with {}",
                 process_name,
                 pid,
+                tree.mode(),
                 process.config.stop_signal
             );
 
-            signal::kill(nix_pid, stop_signal).map_err(|e| {
+            tree.signal(stop_signal).map_err(|e| {
                 AdasaError::StopError(
                     process_name.clone(),
                     format!("Failed to send {}: {}", process.config.stop_signal, e),
                 )
             })?;
 
-            let timeout = process.config.stop_timeout();
             tracing::debug!(
                 "Waiting {:?} for process {} to exit gracefully",
                 timeout,
                 process_name
             );
 
-            let wait_result = tokio::time::timeout(timeout, process.child.wait()).await;
+            let wait_result = tokio::time::timeout_at(deadline, child.wait()).await;
 
             match wait_result {
                 Ok(Ok(status)) => {
@@ -129,7 +408,7 @@ impl ProcessManager {
                         process_name,
                         timeout
                     );
-                    signal::kill(nix_pid, Signal::SIGKILL).map_err(|e| {
+                    tree.signal(Signal::SIGKILL).map_err(|e| {
                         AdasaError::StopError(
                             process_name.clone(),
                             format!("Failed to send SIGKILL after timeout: {}", e),
@@ -139,14 +418,131 @@ impl ProcessManager {
             }
         }
 
-        let _ = process.child.wait().await;
+        let status = child.wait().await.ok();
+        let grace = if force {
+            Duration::ZERO
+        } else {
+            deadline.saturating_duration_since(tokio::time::Instant::now())
+        };
+        Self::stop_descendants(&tree, &process_name, grace).await;
+
+        process.record_exit(status);
         process.mark_stopped();
+        let event = Self::exited_event(process);
+        self.emit(event);
 
         tracing::info!("Process {} stopped successfully", process_name);
 
         Ok(())
     }
 
+    /// Stop an adopted process, which cannot be waited on, by polling until it is gone
+    async fn stop_adopted(
+        process: &mut ManagedProcess,
+        start_time: u64,
+        force: bool,
+    ) -> Result<()> {
+        let pid = process.stats.pid;
+        let gone = || !procfs::is_same_process(pid, start_time);
+        let tree = ProcessTree::capture(
+            pid,
+            process.config.kill_mode,
+            process.cgroup_manager.as_ref(),
+        );
+        let (stop_signal, timeout) = if force {
+            (Signal::SIGKILL, KILL_TIMEOUT)
+        } else {
+            (
+                Self::parse_signal(&process.config.stop_signal)?,
+                process.config.stop_timeout,
+            )
+        };
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        if !gone() {
+            tracing::info!(
+                "Stopping adopted process {} (PID: {}) with {}",
+                process.name,
+                pid,
+                stop_signal
+            );
+            tree.signal(stop_signal).map_err(|e| {
+                AdasaError::StopError(
+                    process.name.clone(),
+                    format!("Failed to send {}: {}", stop_signal, e),
+                )
+            })?;
+
+            if !Self::poll_until(timeout, gone).await {
+                tracing::warn!(
+                    "Adopted process {} did not exit within {:?}, sending SIGKILL",
+                    process.name,
+                    timeout
+                );
+                let _ = tree.signal(Signal::SIGKILL);
+                Self::poll_until(KILL_TIMEOUT, gone).await;
+            }
+        }
+
+        let grace = deadline.saturating_duration_since(tokio::time::Instant::now());
+        Self::stop_descendants(&tree, &process.name, grace).await;
+
+        process.record_exit(None);
+        Ok(())
+    }
+
+    /// Make sure nothing of a process tree outlives its main process
+    ///
+    /// Descendants get until `grace` runs out to exit on their own, then the rest of
+    /// the tree is killed. With the `process` kill mode descendants are left running
+    /// and only reported.
+    async fn stop_descendants(tree: &ProcessTree<'_>, name: &str, grace: Duration) {
+        if tree.mode() == KillMode::Process {
+            let survivors = tree.survivors();
+            if !survivors.is_empty() {
+                tracing::warn!(
+                    "Descendants of process {} are still running (kill mode: process): {:?}",
+                    name,
+                    survivors
+                );
+            }
+            return;
+        }
+
+        if Self::poll_until(grace, || tree.survivors().is_empty()).await {
+            return;
+        }
+
+        let survivors = tree.survivors();
+        tracing::warn!(
+            "Killing {} remaining descendant(s) of process {}: {:?}",
+            survivors.len(),
+            name,
+            survivors
+        );
+        let _ = tree.signal(Signal::SIGKILL);
+
+        if !Self::poll_until(KILL_TIMEOUT, || tree.survivors().is_empty()).await {
+            tracing::error!(
+                "Descendants of process {} survived SIGKILL: {:?}",
+                name,
+                tree.survivors()
+            );
+        }
+    }
+
+    /// Poll `done` until it returns true or `timeout` expires
+    async fn poll_until(timeout: Duration, done: impl Fn() -> bool) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while !done() {
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(STOP_POLL_INTERVAL).await;
+        }
+        true
+    }
+
     fn parse_signal(signal_name: &str) -> Result<Signal> {
         match signal_name {
             "SIGTERM" => Ok(Signal::SIGTERM),
@@ -181,6 +577,7 @@ impl ProcessManager {
             .remove(&id)
             .ok_or_else(|| AdasaError::ProcessNotFound(id.to_string()))?;
 
+        self.cancel_restart(id);
         self.monitor.clear_cache(process.stats.pid);
 
         Ok(())
@@ -193,23 +590,162 @@ impl ProcessManager {
     pub fn find_all_by_name(&self, name: &str) -> Vec<&ManagedProcess> {
         self.processes
             .values()
-            .filter(|p| p.name == name || p.name.starts_with(&format!("{}-", name)))
+            .filter(|p| is_instance_of(&p.name, name))
             .collect()
     }
 
+    /// Instances of a process by base name, paired with their instance number and in that order
+    ///
+    /// A process running a single instance is named after its base name and is instance 0.
+    pub fn instances_of(&self, base_name: &str) -> Vec<(usize, &ManagedProcess)> {
+        let mut instances: Vec<(usize, &ManagedProcess)> = self
+            .processes
+            .values()
+            .filter(|p| is_instance_of(&p.name, base_name))
+            .map(|p| {
+                let index = if p.name == base_name {
+                    0
+                } else {
+                    split_instance(&p.name).1.parse().unwrap_or(0)
+                };
+                (index, p)
+            })
+            .collect();
+        instances.sort_by_key(|(instance_num, p)| (*instance_num, p.id.as_u64()));
+        instances
+    }
+
+    /// Replace the configuration of a process without restarting it
+    ///
+    /// The restart policy, resource limits and health check apply right away, and
+    /// cgroup limits are written to the cgroup of the process. A process that needs a
+    /// cgroup for the first time gets one, and its running tree is moved into it.
+    /// Settings that are only read when the process is spawned take effect at its next start.
+    pub fn update_config(&mut self, id: ProcessId, mut config: ProcessConfig) -> Result<()> {
+        let process = self
+            .processes
+            .get_mut(&id)
+            .ok_or_else(|| AdasaError::ProcessNotFound(id.to_string()))?;
+
+        // Each managed process is a single instance under its own name
+        config.name = process.name.clone();
+        config.instances = 1;
+
+        process.restart_policy = RestartPolicy::from_process_config(&config);
+        if process.state == ProcessState::Scheduled {
+            process.next_run = config.next_run_after(SystemTime::now());
+        }
+        if config.uses_cgroup() {
+            match process.cgroup_manager {
+                Some(ref cgroup) => Self::configure_cgroup(cgroup, &process.name, &config),
+                None => {
+                    let cgroup = CGroupManager::with_root(&self.cgroup_root, process.name.clone());
+                    Self::configure_cgroup(&cgroup, &process.name, &config);
+                    if let Some(pid) = process.live_pid().filter(|_| process.state.is_active()) {
+                        let tree = procfs::descendants(pid).into_iter().map(|p| p.pid);
+                        for pid in std::iter::once(pid).chain(tree) {
+                            if let Err(e) = cgroup.add_process(pid) {
+                                tracing::warn!(
+                                    "Failed to move PID {} of {} into its cgroup: {}",
+                                    pid,
+                                    process.name,
+                                    e
+                                );
+                            }
+                        }
+                    }
+                    process.cgroup_manager = Some(cgroup);
+                }
+            }
+        }
+        process.config = config;
+
+        Ok(())
+    }
+
     pub fn update_stats(&mut self) -> Result<()> {
         let _timer = PerfTimer::with_threshold("update_stats", 100);
+
+        for process in self.processes.values_mut() {
+            let oom_kills = process.refresh_oom_kills();
+            if oom_kills > 0 {
+                tracing::warn!(
+                    "OOM killer killed {} process(es) in the cgroup of {}",
+                    oom_kills,
+                    process.name
+                );
+            }
+        }
+
         self.monitor.update_all_stats(self.processes.values_mut())
     }
 
+    /// Add the current resource usage of every running process to its history
+    ///
+    /// Call after [`ProcessManager::update_stats`], at the interval the history should have.
+    pub fn record_stats_samples(&mut self) {
+        let at = SystemTime::now();
+        for process in self.processes.values_mut() {
+            let Some(pid) = process.live_pid().filter(|_| process.state.is_active()) else {
+                continue;
+            };
+
+            let sample = StatsSample {
+                at,
+                cpu_usage: process.stats.tree_cpu_usage,
+                memory_usage: process.stats.tree_memory_usage,
+                threads: procfs::thread_count(pid).unwrap_or(0),
+                fds: procfs::open_fds(pid).unwrap_or(0),
+            };
+            process.history.push(sample, self.stats_retention);
+        }
+    }
+
+    /// Recorded resource usage of a process by ID, or of all instances of a process by name
+    pub fn stats_history(&self, target: &str) -> Result<Vec<ProcessHistory>> {
+        let mut processes: Vec<&ManagedProcess> = match target.parse::<u64>() {
+            Ok(id) => self
+                .processes
+                .get(&ProcessId::new(id))
+                .into_iter()
+                .collect(),
+            Err(_) => self
+                .instances_of(target)
+                .into_iter()
+                .map(|(_, p)| p)
+                .collect(),
+        };
+        if processes.is_empty() {
+            return Err(AdasaError::ProcessNotFound(target.to_string()));
+        }
+        processes.sort_by_key(|p| p.id.as_u64());
+
+        Ok(processes
+            .into_iter()
+            .map(|p| ProcessHistory {
+                id: p.id,
+                name: p.name.clone(),
+                samples: p.history.samples().copied().collect(),
+            })
+            .collect())
+    }
+
     pub fn detect_crashes(&mut self) -> Vec<ProcessId> {
         let _timer = PerfTimer::with_threshold("detect_crashes", 50);
-        let crashed_pids = self.monitor.detect_crashes(self.processes.values_mut());
+
+        // Exit statuses of our own children are exact, so collect those first
+        let mut crashed = self.reap_exited();
+        crashed.extend(self.detect_adopted_exits());
+
+        let crashed_pids = self
+            .monitor
+            .detect_crashes(self.processes.values_mut().filter(|p| !p.config.is_job()));
 
         // Optimize lookup by building a PID->ProcessId map once
         // instead of iterating for each crashed PID
         if crashed_pids.is_empty() {
-            return Vec::new();
+            crashed.retain(|id| !self.schedule_unhealthy_restart(*id));
+            return crashed;
         }
 
         let pid_to_id: HashMap<u32, ProcessId> = self.processes
@@ -217,10 +753,235 @@ impl ProcessManager {
             .map(|(id, p)| (p.stats.pid, *id))
             .collect();
 
-        crashed_pids
+        for id in crashed_pids
             .into_iter()
             .filter_map(|pid| pid_to_id.get(&pid).copied())
-            .collect()
+        {
+            if let Some(process) = self.processes.get_mut(&id) {
+                process.record_exit(None);
+                let event = Self::exited_event(process);
+                self.emit(event);
+            }
+            crashed.push(id);
+        }
+        crashed.retain(|id| !self.schedule_unhealthy_restart(*id));
+        crashed
+    }
+
+    /// Find adopted processes that exited, or whose PID now belongs to another process
+    fn detect_adopted_exits(&mut self) -> Vec<ProcessId> {
+        let mut exited = Vec::new();
+
+        for (id, process) in self.processes.iter_mut() {
+            let Some(start_time) = process.adopted_start_time else {
+                continue;
+            };
+            if !process.state.is_active() || procfs::is_same_process(process.stats.pid, start_time)
+            {
+                continue;
+            }
+
+            tracing::warn!(
+                "Adopted process {} (PID: {}) exited",
+                process.name,
+                process.stats.pid
+            );
+            process.adopted_start_time = None;
+            process.record_exit(None);
+            process.mark_errored();
+            self.monitor.clear_cache(process.stats.pid);
+            exited.push(*id);
+        }
+
+        for id in &exited {
+            let event = Self::exited_event(&self.processes[id]);
+            self.emit(event);
+        }
+
+        exited
+    }
+
+    /// Collect the exit status of processes whose child exited
+    ///
+    /// Returns the processes that exited and have to go through their restart policy.
+    /// A finished run of a scheduled job goes back to waiting for its next run, and a
+    /// one-shot task that exits successfully is completed; neither is returned.
+    fn reap_exited(&mut self) -> Vec<ProcessId> {
+        let mut exited = Vec::new();
+        let mut events = Vec::new();
+
+        for (id, process) in self.processes.iter_mut() {
+            if process.config.is_job() {
+                process
+                    .overlapping_runs
+                    .retain_mut(|run| matches!(run.try_wait(), Ok(None)));
+            }
+
+            if !process.state.is_active() {
+                continue;
+            }
+
+            let pid = process.stats.pid;
+            let Some(exit) = Self::reap(process) else {
+                continue;
+            };
+            self.monitor.clear_cache(pid);
+            events.push(Self::exited_event(process));
+
+            if process.config.schedule.is_none() && (exit.is_failure() || !process.config.oneshot) {
+                exited.push(*id);
+            }
+        }
+
+        for event in events {
+            self.emit(event);
+        }
+
+        exited
+    }
+
+    /// Event describing the most recent exit of a process
+    fn exited_event(process: &ManagedProcess) -> Event {
+        Event::ProcessExited {
+            id: process.id,
+            name: process.name.clone(),
+            exit_code: process.stats.last_exit_code,
+            signal: process.stats.last_exit_signal,
+        }
+    }
+
+    /// Record the exit of the current child, if it exited, and move the process to the next state
+    ///
+    /// # Returns
+    /// * `Some(ExitKind)` - How the child exited
+    /// * `None` - The child is still running, or there is none
+    fn reap(process: &mut ManagedProcess) -> Option<ExitKind> {
+        let status = match process.child.as_mut()?.try_wait() {
+            Ok(Some(status)) => Some(status),
+            Ok(None) => return None,
+            Err(e) => {
+                tracing::warn!("Failed to check status of process {}: {}", process.name, e);
+                None
+            }
+        };
+
+        process.child = None;
+        process.record_exit(status);
+        let exit = process.last_exit();
+
+        let oom_kills = process.refresh_oom_kills();
+        if oom_kills > 0 {
+            tracing::warn!(
+                "Process {} exited after the OOM killer killed {} process(es) in its cgroup",
+                process.name,
+                oom_kills
+            );
+        }
+
+        if process.config.schedule.is_some() {
+            if exit.is_failure() {
+                tracing::warn!("Scheduled job {} failed: {}", process.name, exit);
+            } else {
+                tracing::info!("Scheduled job {} finished", process.name);
+            }
+            process.mark_scheduled();
+        } else if exit.is_failure() {
+            tracing::warn!("Process {} {}", process.name, exit);
+            process.mark_errored();
+        } else if process.config.oneshot {
+            tracing::info!("One-shot task {} completed", process.name);
+            process.mark_completed();
+        } else {
+            tracing::info!("Process {} {}", process.name, exit);
+            process.mark_stopped();
+        }
+
+        Some(exit)
+    }
+
+    /// Start the scheduled jobs whose next run is due
+    ///
+    /// If the previous run of a job is still in progress, the new run is skipped
+    /// when the job sets `no_overlap`, and started alongside it otherwise.
+    ///
+    /// # Returns
+    /// IDs of the jobs that were started
+    pub async fn run_due_jobs(&mut self) -> Vec<ProcessId> {
+        let now = SystemTime::now();
+        let due: Vec<ProcessId> = self
+            .processes
+            .values()
+            .filter(|p| {
+                p.config.schedule.is_some()
+                    && (p.state == ProcessState::Scheduled || p.state.is_active())
+                    && p.next_run.is_some_and(|next_run| next_run <= now)
+            })
+            .map(|p| p.id)
+            .collect();
+
+        let mut started = Vec::with_capacity(due.len());
+        for id in due {
+            match self.run_job(id, now).await {
+                Ok(true) => started.push(id),
+                Ok(false) => {}
+                Err(e) => tracing::error!("Failed to run scheduled job {}: {}", id, e),
+            }
+        }
+
+        started
+    }
+
+    async fn run_job(&mut self, id: ProcessId, now: SystemTime) -> Result<bool> {
+        let process = self
+            .processes
+            .get_mut(&id)
+            .ok_or_else(|| AdasaError::ProcessNotFound(id.to_string()))?;
+
+        process.next_run = process.config.next_run_after(now);
+
+        if process.child.is_some() {
+            if Self::reap(process).is_some() {
+                // `process` borrows the process map, so send on the channel directly
+                let _ = self.events.send(Self::exited_event(process));
+            } else if process.config.no_overlap {
+                tracing::info!(
+                    "Skipping run of scheduled job {}: previous run is still in progress",
+                    process.name
+                );
+                return Ok(false);
+            }
+            if let Some(previous) = process.child.take() {
+                process.overlapping_runs.push(previous);
+            }
+        }
+
+        let config = process.config.clone();
+        let mut spawned = spawn_process_in(&config, process.cgroup_manager.as_ref()).await?;
+        let name = spawned.name.clone();
+        self.attach_output(id, &name, &mut spawned, None);
+
+        let process = self
+            .processes
+            .get_mut(&id)
+            .ok_or_else(|| AdasaError::ProcessNotFound(id.to_string()))?;
+
+        tracing::info!(
+            "Started scheduled job {} (PID: {})",
+            process.name,
+            spawned.pid
+        );
+        process.child = Some(spawned.child);
+        process.stats.record_run_start(spawned.pid);
+        process.health_tracker.reset();
+        process.mark_running();
+        let event = Event::ProcessStarted {
+            id,
+            name: process.name.clone(),
+            pid: spawned.pid,
+        };
+        self.emit(event);
+
+        Ok(true)
     }
 
     pub fn is_alive(&mut self, id: ProcessId) -> bool {
@@ -241,59 +1002,418 @@ impl ProcessManager {
 
         self.stop(id, false).await?;
 
-        let spawned = spawn_process(&config).await?;
+        // A manual restart starts over with a fresh restart history
+        if let Some(process) = self.processes.get_mut(&id) {
+            process.restart_tracker.clear();
+        }
+
+        self.respawn(id, &config, None).await
+    }
+
+    /// Spawn a new child for an existing process and attach output capture
+    ///
+    /// `config` is the configuration to spawn with; the stored configuration is left untouched.
+    async fn respawn(
+        &mut self,
+        id: ProcessId,
+        config: &ProcessConfig,
+        line_tap: Option<UnboundedSender<String>>,
+    ) -> Result<()> {
+        self.refresh_cgroup(id, config);
+        let cgroup = self
+            .processes
+            .get(&id)
+            .and_then(|p| p.cgroup_manager.as_ref());
+        let mut spawned = spawn_process_in(config, cgroup).await?;
         let new_pid = spawned.pid;
 
+        let name = spawned.name.clone();
+        self.attach_output(id, &name, &mut spawned, line_tap);
+
         let process = self
             .processes
             .get_mut(&id)
             .ok_or_else(|| AdasaError::ProcessNotFound(id.to_string()))?;
 
-        process.child = spawned.child;
+        process.child = Some(spawned.child);
+        process.adopted_start_time = None;
         process.stats.record_restart(new_pid);
+        if config.is_job() {
+            process.stats.last_run = process.stats.last_restart;
+        }
+        if config.schedule.is_some() && process.next_run.is_none() {
+            process.next_run = config.next_run_after(SystemTime::now());
+        }
         process.restart_tracker.record_restart();
+        process.health_tracker.reset();
         process.state = ProcessState::Running;
+        let event = Event::ProcessRestarted {
+            id,
+            name: process.name.clone(),
+            pid: new_pid,
+            restarts: process.stats.restarts,
+        };
+        self.emit(event);
 
         Ok(())
     }
 
-    pub async fn try_auto_restart(&mut self, id: ProcessId) -> Result<bool> {
+    /// Create and configure the cgroup of a process, if its configuration needs one
+    ///
+    /// A cgroup that cannot be set up, e.g. without cgroups v2 or without the
+    /// privileges to manage them, is only reported. If it could not be created at
+    /// all, the process runs without one.
+    fn prepare_cgroup(&self, name: &str, config: &ProcessConfig) -> Option<CGroupManager> {
+        if !config.uses_cgroup() {
+            return None;
+        }
+
+        let cgroup = CGroupManager::with_root(&self.cgroup_root, name.to_string());
+        Self::configure_cgroup(&cgroup, name, config);
+        Some(cgroup)
+    }
+
+    fn configure_cgroup(cgroup: &CGroupManager, name: &str, config: &ProcessConfig) {
+        if let Err(e) = cgroup.setup().and_then(|()| cgroup.apply_config(config)) {
+            tracing::warn!("Failed to set up the cgroup of process {}: {}", name, e);
+        }
+    }
+
+    /// Bring the cgroup of a process in line with the configuration it is about to be spawned with
+    fn refresh_cgroup(&mut self, id: ProcessId, config: &ProcessConfig) {
+        let Some(process) = self.processes.get(&id) else {
+            return;
+        };
+
+        let cgroup = match process.cgroup_manager {
+            // Reuse the existing cgroup: replacing it would remove its directory
+            Some(ref cgroup) if config.uses_cgroup() => {
+                Self::configure_cgroup(cgroup, &process.name, config);
+                return;
+            }
+            Some(_) => None,
+            None => self.prepare_cgroup(&process.name, config),
+        };
+
+        if let Some(process) = self.processes.get_mut(&id) {
+            process.cgroup_manager = cgroup;
+        }
+    }
+
+    /// Capture the output of a freshly spawned child into the log directory, if one is set
+    fn attach_output(
+        &self,
+        id: ProcessId,
+        name: &str,
+        spawned: &mut SpawnedProcess,
+        line_tap: Option<UnboundedSender<String>>,
+    ) {
+        if let Some(ref log_dir) = self.log_dir {
+            if let Err(e) = LogManager::capture_output(
+                log_dir,
+                id.as_u64(),
+                name,
+                &mut spawned.child,
+                spawned.owner,
+                line_tap,
+            ) {
+                tracing::warn!("Failed to capture logs for process {}: {}", name, e);
+            }
+        } else if let Some(line_tap) = line_tap {
+            readiness::forward_output(&mut spawned.child, line_tap);
+        }
+    }
+
+    /// Schedule a restart of a process that exited, honouring its restart policy
+    ///
+    /// The process is respawned by [`ProcessManager::run_due_restarts`] once the
+    /// backoff delay of its restart policy has passed.
+    ///
+    /// # Returns
+    /// * `Ok(true)` - The restart was scheduled
+    /// * `Ok(false)` - The restart mode or the restart limit prevented a restart
+    /// * `Err(AdasaError)` - The process does not exist
+    pub fn try_auto_restart(&mut self, id: ProcessId) -> Result<bool> {
         let process = self
             .processes
-            .get(&id)
+            .get_mut(&id)
             .ok_or_else(|| AdasaError::ProcessNotFound(id.to_string()))?;
 
+        if !process.restart_policy.restarts_after(process.last_exit()) {
+            return Ok(false);
+        }
+
         if !process
             .restart_policy
             .should_restart(&process.restart_tracker)
         {
+            tracing::warn!(
+                "Process {} ({}) is crash-looping: {} restarts within {}, giving up",
+                process.name,
+                id,
+                process
+                    .restart_tracker
+                    .count_recent_restarts(process.restart_policy.time_window),
+                format_duration(process.restart_policy.time_window)
+            );
+            self.mark_crash_loop(id);
             return Ok(false);
         }
 
-        let delay = process
-            .restart_policy
-            .calculate_delay(&process.restart_tracker);
+        self.schedule_restart(id);
 
-        tokio::time::sleep(delay).await;
+        Ok(true)
+    }
 
-        let config = process.config.clone();
+    /// Give up restarting a process that crashed too often within its restart window
+    pub fn mark_crash_loop(&mut self, id: ProcessId) {
+        let Some(process) = self.processes.get_mut(&id) else {
+            return;
+        };
 
-        let spawned = spawn_process(&config).await?;
-        let new_pid = spawned.pid;
+        process.mark_crash_loop();
+        let event = Event::CrashLoop {
+            id,
+            name: process.name.clone(),
+            restarts: process
+                .restart_tracker
+                .count_recent_restarts(process.restart_policy.time_window),
+        };
+        self.emit(event);
+    }
 
+    /// Respawn a process from [`ProcessManager::run_due_restarts`] after its backoff delay
+    fn schedule_restart(&mut self, id: ProcessId) {
+        if let Some(process) = self.processes.get(&id) {
+            let delay = process
+                .restart_policy
+                .calculate_delay(&process.restart_tracker);
+            self.pending_restarts
+                .insert(id, tokio::time::Instant::now() + delay);
+        }
+    }
+
+    /// Stop a process that failed its health check so it gets restarted, honouring its restart policy
+    ///
+    /// The process is only sent its stop signal. Once [`ProcessManager::detect_crashes`]
+    /// has seen it exit, [`ProcessManager::run_due_restarts`] respawns it after the
+    /// backoff delay of its restart policy, and kills it if it outlives its stop timeout.
+    ///
+    /// # Returns
+    /// * `Ok(true)` - The restart was scheduled
+    /// * `Ok(false)` - The restart policy prevented a restart; the process keeps running
+    /// * `Err(AdasaError)` - The process could not be signalled
+    pub fn restart_unhealthy(&mut self, id: ProcessId) -> Result<bool> {
         let process = self
             .processes
-            .get_mut(&id)
+            .get(&id)
             .ok_or_else(|| AdasaError::ProcessNotFound(id.to_string()))?;
 
-        process.child = spawned.child;
-        process.stats.record_restart(new_pid);
-        process.restart_tracker.record_restart();
-        process.state = ProcessState::Running;
+        if !process
+            .restart_policy
+            .should_restart(&process.restart_tracker)
+        {
+            return Ok(false);
+        }
+        if self.unhealthy_stops.contains_key(&id) || self.pending_restarts.contains_key(&id) {
+            return Ok(true);
+        }
+
+        if let Some(pid) = process.live_pid() {
+            let stop_signal = Self::parse_signal(&process.config.stop_signal)?;
+            let tree = ProcessTree::capture(
+                pid,
+                process.config.kill_mode,
+                process.cgroup_manager.as_ref(),
+            );
+            tracing::info!(
+                "Stopping unhealthy process {} (PID: {}) with {}",
+                process.name,
+                pid,
+                process.config.stop_signal
+            );
+            tree.signal(stop_signal).map_err(|e| {
+                AdasaError::StopError(
+                    process.name.clone(),
+                    format!("Failed to send {}: {}", process.config.stop_signal, e),
+                )
+            })?;
+        }
+
+        let kill_at = tokio::time::Instant::now() + process.config.stop_timeout;
+        self.unhealthy_stops.insert(id, kill_at);
 
         Ok(true)
     }
 
+    /// Wait out the backoff delay of an unhealthy process that exited after being stopped
+    ///
+    /// Returns false if the process was not stopped by [`ProcessManager::restart_unhealthy`].
+    fn schedule_unhealthy_restart(&mut self, id: ProcessId) -> bool {
+        if self.unhealthy_stops.remove(&id).is_none() {
+            return false;
+        }
+
+        self.schedule_restart(id);
+        true
+    }
+
+    /// Forget a scheduled restart
+    fn cancel_restart(&mut self, id: ProcessId) {
+        self.unhealthy_stops.remove(&id);
+        self.pending_restarts.remove(&id);
+    }
+
+    /// Carry out the scheduled restarts that are due
+    ///
+    /// Unhealthy processes that ignored their stop signal past their stop timeout are
+    /// killed. Processes that exited are respawned once their backoff delay has passed.
+    /// Returns the processes that were respawned.
+    pub async fn run_due_restarts(&mut self) -> Vec<ProcessId> {
+        let now = tokio::time::Instant::now();
+
+        for (id, kill_at) in self.unhealthy_stops.iter_mut() {
+            if *kill_at > now {
+                continue;
+            }
+            let Some(process) = self.processes.get(id) else {
+                continue;
+            };
+            if let Some(pid) = process.live_pid() {
+                tracing::warn!(
+                    "Unhealthy process {} did not exit within {:?}, sending SIGKILL",
+                    process.name,
+                    process.config.stop_timeout
+                );
+                let tree = ProcessTree::capture(
+                    pid,
+                    process.config.kill_mode,
+                    process.cgroup_manager.as_ref(),
+                );
+                let _ = tree.signal(Signal::SIGKILL);
+            }
+            *kill_at = now + KILL_TIMEOUT;
+        }
+
+        let due: Vec<ProcessId> = self
+            .pending_restarts
+            .iter()
+            .filter(|(_, at)| **at <= now)
+            .map(|(id, _)| *id)
+            .collect();
+
+        let mut restarted = Vec::new();
+        for id in due {
+            self.pending_restarts.remove(&id);
+            let Some(process) = self.processes.get(&id) else {
+                continue;
+            };
+            let config = process.config.clone();
+
+            match self.respawn(id, &config, None).await {
+                Ok(()) => restarted.push(id),
+                Err(e) => {
+                    tracing::error!("Failed to restart process {}: {}", id, e);
+                    if let Some(process) = self.processes.get_mut(&id) {
+                        process.mark_errored();
+                    }
+                }
+            }
+        }
+
+        restarted
+    }
+
+    /// Collect health checks that are due and mark them as started
+    ///
+    /// The returned probes can be run without holding a lock on the manager,
+    /// then passed to [`ProcessManager::record_health_results`].
+    pub fn due_health_checks(&mut self) -> Vec<(ProcessId, HealthCheckConfig)> {
+        let mut due = Vec::new();
+
+        for (id, process) in self.processes.iter_mut() {
+            if !process.state.is_active() {
+                continue;
+            }
+
+            if let Some(ref health_check) = process.config.health_check {
+                if process
+                    .health_tracker
+                    .is_due(health_check, process.stats.uptime())
+                {
+                    process.health_tracker.mark_checked();
+                    due.push((*id, health_check.clone()));
+                }
+            }
+        }
+
+        due
+    }
+
+    /// Apply health probe results and return the processes that are now unhealthy
+    pub fn record_health_results(
+        &mut self,
+        results: Vec<(ProcessId, Result<()>)>,
+    ) -> Vec<ProcessId> {
+        let mut unhealthy = Vec::new();
+
+        for (id, result) in results {
+            let Some(process) = self.processes.get_mut(&id) else {
+                continue;
+            };
+
+            // The process may have been stopped or restarted while the probe was running
+            if !process.state.is_active() {
+                continue;
+            }
+
+            let failure_threshold = match process.config.health_check {
+                Some(ref health_check) => health_check.failure_threshold,
+                None => continue,
+            };
+
+            match result {
+                Ok(()) => {
+                    process.health_tracker.record_success();
+                    if process.state == ProcessState::Unhealthy {
+                        tracing::info!("Process {} is healthy again", process.name);
+                        process.mark_running();
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Health check failed for process {}: {}", process.name, e);
+                    if process
+                        .health_tracker
+                        .record_failure(e.to_string(), failure_threshold)
+                    {
+                        if process.state != ProcessState::Unhealthy {
+                            tracing::warn!(
+                                "Process {} marked unhealthy after {} consecutive failed health checks",
+                                process.name,
+                                process.health_tracker.consecutive_failures()
+                            );
+                            process.mark_unhealthy();
+                        }
+                        unhealthy.push(id);
+                    }
+                }
+            }
+        }
+
+        unhealthy
+    }
+
+    /// Run all due health checks and return the processes that are now unhealthy
+    pub async fn run_health_checks(&mut self) -> Vec<ProcessId> {
+        let due = self.due_health_checks();
+        if due.is_empty() {
+            return Vec::new();
+        }
+
+        let results = health::run_probes(due).await;
+        self.record_health_results(results)
+    }
+
     pub fn get_restart_info(&self, id: ProcessId) -> Option<(usize, bool)> {
         self.processes.get(&id).map(|p| {
             let count = p.restart_tracker.restart_count();
@@ -302,80 +1422,384 @@ impl ProcessManager {
         })
     }
 
-    pub async fn rolling_restart(
+    /// Restart all instances of a process one at a time, waiting for each to become ready
+    ///
+    /// `self` stays borrowed for the whole rollout, so a caller that got it from a shared
+    /// lock keeps that lock held. Use [`ProcessManager::rolling_restart_shared`] then.
+    pub async fn rolling_restart(&mut self, name_or_id: &str) -> Result<usize> {
+        self.rolling_restart_batched(name_or_id, None).await
+    }
+
+    /// Restart all instances of a process in batches, gating each batch on readiness
+    ///
+    /// `self` stays borrowed for the whole rollout, so a caller that got it from a shared
+    /// lock keeps that lock held. Use [`ProcessManager::rolling_restart_shared`] then.
+    pub async fn rolling_restart_batched(
         &mut self,
         name_or_id: &str,
-        health_check_delay: Duration,
+        batch: Option<BatchSize>,
+    ) -> Result<usize> {
+        Self::rolling_restart_shared(&RwLock::new(self), name_or_id, batch).await
+    }
+
+    /// Restart all instances of a process in batches, gating each batch on readiness
+    ///
+    /// Each replacement must report ready through its `readiness` check before the
+    /// next batch is restarted. Instances without a readiness check only have to stay
+    /// up for their `rolling_settle`. If a replacement exits or does not become ready in
+    /// time, the rollout stops and is rolled back: the failed batch, and any earlier
+    /// instance whose configuration changed since the rollout started, are restarted
+    /// with the configuration they had before.
+    ///
+    /// `manager` is only locked to stop, spawn and check on instances, so it can be
+    /// used by others while the replacements start up.
+    ///
+    /// # Arguments
+    /// * `manager` - The process manager, or a mutable reference to it
+    /// * `name_or_id` - Base name of the instances, or the ID of one of them
+    /// * `batch` - Instances to restart at once (default: the process `rolling_batch`, or 1)
+    ///
+    /// # Returns
+    /// * `Ok(usize)` - Number of instances restarted
+    /// * `Err(AdasaError)` - The rollout was aborted
+    pub async fn rolling_restart_shared<M: BorrowMut<ProcessManager>>(
+        manager: &RwLock<M>,
+        name_or_id: &str,
+        batch: Option<BatchSize>,
     ) -> Result<usize> {
-        let instances: Vec<ProcessId> = if let Ok(id_num) = name_or_id.parse::<u64>() {
-            let id = ProcessId::new(id_num);
-            if let Some(process) = self.processes.get(&id) {
-                let base_name = &process.name;
-                self.find_all_by_name(base_name)
+        let (instances, previous, batch_size) = {
+            let pm = Self::write(manager).await;
+            let mut instances: Vec<ProcessId> = if let Ok(id_num) = name_or_id.parse::<u64>() {
+                let id = ProcessId::new(id_num);
+                if let Some(process) = pm.processes.get(&id) {
+                    let base_name = &process.name;
+                    pm.find_all_by_name(base_name)
+                        .iter()
+                        .map(|p| p.id)
+                        .collect()
+                } else {
+                    return Err(AdasaError::ProcessNotFound(name_or_id.to_string()));
+                }
+            } else {
+                pm.find_all_by_name(name_or_id)
                     .iter()
                     .map(|p| p.id)
                     .collect()
-            } else {
+            };
+
+            if instances.is_empty() {
                 return Err(AdasaError::ProcessNotFound(name_or_id.to_string()));
             }
-        } else {
-            self.find_all_by_name(name_or_id)
+
+            instances.sort_by_key(|id| id.as_u64());
+
+            let previous: HashMap<ProcessId, ProcessConfig> = instances
                 .iter()
-                .map(|p| p.id)
-                .collect()
+                .filter_map(|id| pm.processes.get(id).map(|p| (*id, p.config.clone())))
+                .collect();
+
+            let batch_size = batch
+                .or_else(|| previous.get(&instances[0]).and_then(|c| c.rolling_batch))
+                .map(|b| b.resolve(instances.len()))
+                .unwrap_or(1);
+
+            (instances, previous, batch_size)
         };
 
-        if instances.is_empty() {
-            return Err(AdasaError::ProcessNotFound(name_or_id.to_string()));
+        let mut completed: Vec<ProcessId> = Vec::with_capacity(instances.len());
+
+        for (batch_idx, chunk) in instances.chunks(batch_size).enumerate() {
+            tracing::info!(
+                "Rolling restart: restarting batch {} of {} ({} instance(s))",
+                batch_idx + 1,
+                instances.len().div_ceil(batch_size),
+                chunk.len()
+            );
+
+            let mut replaced = Vec::with_capacity(chunk.len());
+            let started = Self::write(manager)
+                .await
+                .replace_batch(chunk, &mut replaced)
+                .await;
+            let result = match started {
+                Ok(watches) => Self::await_readiness_shared(manager, watches).await,
+                Err(e) => Err(e),
+            };
+
+            if let Err((failed_id, e)) = result {
+                tracing::warn!(
+                    "Rolling restart aborted: instance {} failed: {}",
+                    failed_id,
+                    e
+                );
+                let rolled_back = Self::write(manager)
+                    .await
+                    .roll_back(&replaced, &completed, &previous)
+                    .await;
+
+                return Err(AdasaError::RestartError(
+                    failed_id.to_string(),
+                    format!(
+                        "{} (rolling restart aborted after {} instance(s), {} rolled back)",
+                        e,
+                        completed.len(),
+                        rolled_back
+                    ),
+                ));
+            }
+
+            completed.extend_from_slice(chunk);
         }
 
-        if instances.len() == 1 {
-            self.restart(instances[0]).await?;
-            return Ok(1);
+        tracing::info!(
+            "Rolling restart completed: {} instances restarted successfully",
+            completed.len()
+        );
+
+        Ok(completed.len())
+    }
+
+    /// Lock a shared process manager for writing
+    async fn write<M: BorrowMut<ProcessManager>>(
+        manager: &RwLock<M>,
+    ) -> RwLockMappedWriteGuard<'_, ProcessManager> {
+        RwLockWriteGuard::map(manager.write().await, |m| m.borrow_mut())
+    }
+
+    /// Restart one batch of instances, returning the watches to wait on for their readiness
+    ///
+    /// Instances that were stopped are pushed to `replaced`, so the caller can roll them back.
+    async fn replace_batch(
+        &mut self,
+        batch: &[ProcessId],
+        replaced: &mut Vec<ProcessId>,
+    ) -> std::result::Result<Vec<(ProcessId, ReadinessWatch)>, (ProcessId, AdasaError)> {
+        let mut waits = Vec::with_capacity(batch.len());
+
+        for &id in batch {
+            let config = self
+                .processes
+                .get(&id)
+                .map(|p| p.config.clone())
+                .ok_or_else(|| (id, AdasaError::ProcessNotFound(id.to_string())))?;
+
+            let mut watch =
+                ReadinessWatch::prepare(config.readiness.as_ref(), config.rolling_settle)
+                    .map_err(|e| (id, e))?;
+            let mut spawn_config = config;
+            if let Some((key, value)) = watch.spawn_env() {
+                spawn_config.env.insert(key, value);
+            }
+
+            self.stop(id, false).await.map_err(|e| (id, e))?;
+            replaced.push(id);
+
+            let line_tap = watch.take_line_tap();
+            self.respawn(id, &spawn_config, line_tap)
+                .await
+                .map_err(|e| (id, e))?;
+
+            waits.push((id, watch));
         }
 
-        let mut restarted_count = 0;
+        Ok(waits)
+    }
 
-        for (idx, instance_id) in instances.iter().enumerate() {
-            println!(
-                "Rolling restart: restarting instance {} of {} (ID: {})",
-                idx + 1,
-                instances.len(),
-                instance_id
-            );
+    /// Wait until freshly spawned processes report ready
+    ///
+    /// `self` stays borrowed for the whole wait, so a caller that got it from a shared
+    /// lock keeps that lock held. Use [`ProcessManager::wait_until_ready_shared`] then.
+    ///
+    /// # Arguments
+    /// * `watches` - Watches returned by [`ProcessManager::spawn_with_readiness`]
+    ///
+    /// # Returns
+    /// * `Ok(())` - Every process is ready
+    /// * `Err(AdasaError)` - A process exited or did not become ready in time
+    pub async fn wait_until_ready(
+        &mut self,
+        watches: Vec<(ProcessId, ReadinessWatch)>,
+    ) -> Result<()> {
+        Self::wait_until_ready_shared(&RwLock::new(self), watches).await
+    }
 
-            self.restart(*instance_id).await?;
+    /// Wait until freshly spawned processes report ready
+    ///
+    /// `manager` is only locked to check whether the processes exited, so it can be
+    /// used by others while they start up.
+    ///
+    /// # Arguments
+    /// * `manager` - The process manager, or a mutable reference to it
+    /// * `watches` - Watches returned by [`ProcessManager::spawn_with_readiness`]
+    ///
+    /// # Returns
+    /// * `Ok(())` - Every process is ready
+    /// * `Err(AdasaError)` - A process exited or did not become ready in time
+    pub async fn wait_until_ready_shared<M: BorrowMut<ProcessManager>>(
+        manager: &RwLock<M>,
+        watches: Vec<(ProcessId, ReadinessWatch)>,
+    ) -> Result<()> {
+        let (id, e) = match Self::await_readiness_shared(manager, watches).await {
+            Ok(()) => return Ok(()),
+            Err(failed) => failed,
+        };
 
-            if idx < instances.len() - 1 {
-                println!(
-                    "Waiting {:?} for health check before restarting next instance...",
-                    health_check_delay
-                );
-                tokio::time::sleep(health_check_delay).await;
+        let name = Self::write(manager)
+            .await
+            .processes
+            .get(&id)
+            .map(|p| p.name.clone())
+            .unwrap_or_else(|| id.to_string());
+        let reason = match e {
+            AdasaError::NotReady(reason) => reason,
+            other => other.to_string(),
+        };
+        Err(AdasaError::NotReady(format!("{}: {}", name, reason)))
+    }
 
-                if !self.is_alive(*instance_id) {
-                    return Err(AdasaError::RestartError(
-                        instance_id.to_string(),
-                        "Instance failed health check after restart".to_string(),
-                    ));
+    /// Wait on readiness watches, only locking `manager` to check whether the processes exited
+    async fn await_readiness_shared<M: BorrowMut<ProcessManager>>(
+        manager: &RwLock<M>,
+        watches: Vec<(ProcessId, ReadinessWatch)>,
+    ) -> std::result::Result<(), (ProcessId, AdasaError)> {
+        let mut pending: HashSet<ProcessId> = watches.iter().map(|(id, _)| *id).collect();
+        let mut oneshots: HashSet<ProcessId> = HashSet::new();
+        let mut waits = JoinSet::new();
+        {
+            let pm = Self::write(manager).await;
+            for (id, watch) in watches {
+                // One-shot tasks are ready once they completed successfully
+                if pm.processes.get(&id).is_some_and(|p| p.config.oneshot) {
+                    oneshots.insert(id);
+                    continue;
                 }
+                waits.spawn(async move { (id, watch.wait().await) });
+            }
+        }
 
-                println!("Health check passed for instance {}", instance_id);
+        let mut exit_poll = tokio::time::interval(READINESS_EXIT_POLL_INTERVAL);
+
+        while !pending.is_empty() {
+            tokio::select! {
+                joined = waits.join_next(), if !waits.is_empty() => match joined {
+                    Some(Ok((id, Ok(())))) => {
+                        if Self::write(manager).await.has_exited(id) {
+                            return Err((id, Self::exited_before_ready()));
+                        }
+                        tracing::info!("Process {} is ready", id);
+                        pending.remove(&id);
+                    }
+                    Some(Ok((id, Err(e)))) => return Err((id, e)),
+                    Some(Err(e)) => {
+                        let id = *pending.iter().next().expect("pending is not empty");
+                        return Err((id, AdasaError::NotReady(format!("readiness task failed: {}", e))));
+                    }
+                    None => break,
+                },
+                _ = exit_poll.tick() => {
+                    let mut pm = Self::write(manager).await;
+                    for &id in &oneshots {
+                        match pm.processes.get_mut(&id).and_then(Self::reap) {
+                            Some(ExitKind::Success) => {
+                                tracing::info!("Process {} completed", id);
+                                pending.remove(&id);
+                            }
+                            Some(exit) => {
+                                return Err((
+                                    id,
+                                    AdasaError::NotReady(format!("one-shot task {}", exit)),
+                                ));
+                            }
+                            None => {}
+                        }
+                    }
+                    oneshots.retain(|id| pending.contains(id));
+
+                    if let Some(id) = pending
+                        .iter()
+                        .copied()
+                        .find(|id| !oneshots.contains(id) && pm.has_exited(*id))
+                    {
+                        return Err((id, Self::exited_before_ready()));
+                    }
+                }
             }
+        }
+
+        Ok(())
+    }
 
-            restarted_count += 1;
+    fn exited_before_ready() -> AdasaError {
+        AdasaError::NotReady("process exited before becoming ready".to_string())
+    }
+
+    /// Check whether the current child of a process has exited
+    fn has_exited(&mut self, id: ProcessId) -> bool {
+        match self.processes.get_mut(&id) {
+            Some(process) => !matches!(
+                process.child.as_mut().map(|child| child.try_wait()),
+                Some(Ok(None))
+            ),
+            None => true,
         }
+    }
 
-        println!(
-            "Rolling restart completed: {} instances restarted successfully",
-            restarted_count
-        );
+    /// Restore instances to their configuration from before a rolling restart
+    ///
+    /// Instances of the failed batch are always restarted. Instances from earlier
+    /// batches are only restarted if their configuration changed during the rollout.
+    ///
+    /// # Returns
+    /// Number of instances that were restarted with their previous configuration
+    async fn roll_back(
+        &mut self,
+        failed_batch: &[ProcessId],
+        completed: &[ProcessId],
+        previous: &HashMap<ProcessId, ProcessConfig>,
+    ) -> usize {
+        let mut rolled_back = 0;
+
+        for &id in completed.iter().chain(failed_batch) {
+            let Some(config) = previous.get(&id) else {
+                continue;
+            };
+
+            let unchanged = self.processes.get(&id).is_some_and(|p| p.config == *config);
+            if unchanged && !failed_batch.contains(&id) {
+                continue;
+            }
+
+            if !self.has_exited(id) {
+                if let Err(e) = self.stop(id, false).await {
+                    tracing::error!("Failed to stop instance {} for rollback: {}", id, e);
+                    continue;
+                }
+            }
+
+            if let Some(process) = self.processes.get_mut(&id) {
+                process.config = config.clone();
+            }
+
+            match self.respawn(id, config, None).await {
+                Ok(()) => {
+                    tracing::info!("Rolled back instance {}", id);
+                    rolled_back += 1;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to roll back instance {}: {}", id, e);
+                    if let Some(process) = self.processes.get_mut(&id) {
+                        process.mark_errored();
+                    }
+                }
+            }
+        }
 
-        Ok(restarted_count)
+        rolled_back
     }
 
+    /// Stop all processes gracefully, dependents before the processes they depend on
     pub async fn stop_all(&mut self) -> Result<()> {
-        let process_ids: Vec<ProcessId> = self.processes.keys().copied().collect();
+        let process_ids = self.shutdown_order();
 
         tracing::info!("Stopping {} processes gracefully", process_ids.len());
 
@@ -388,37 +1812,110 @@ impl ProcessManager {
         Ok(())
     }
 
+    /// Order processes so each one is stopped before anything it depends on
+    ///
+    /// This is the reverse of the startup order. If dependencies form a cycle,
+    /// the remaining processes are stopped in ID order.
+    fn shutdown_order(&self) -> Vec<ProcessId> {
+        let mut remaining: Vec<ProcessId> = self.processes.keys().copied().collect();
+        remaining.sort_by_key(|id| id.as_u64());
+
+        let mut order = Vec::with_capacity(remaining.len());
+
+        while !remaining.is_empty() {
+            let is_needed = |id: &ProcessId| {
+                let name = &self.processes[id].name;
+                remaining.iter().any(|other| {
+                    other != id
+                        && self.processes[other]
+                            .config
+                            .depends_on
+                            .iter()
+                            .any(|dependency| is_instance_of(name, dependency))
+                })
+            };
+
+            let (mut next, blocked): (Vec<ProcessId>, Vec<ProcessId>) =
+                remaining.iter().copied().partition(|id| !is_needed(id));
+
+            if next.is_empty() {
+                next = blocked;
+                remaining = Vec::new();
+            } else {
+                remaining = blocked;
+            }
+
+            order.extend(next);
+        }
+
+        order
+    }
+
+    /// Check whether any instance of the named process is running, or completed for a one-shot task
+    ///
+    /// `name` is the base name from the configuration, so `web` matches `web-0`, `web-1`, ...
+    pub fn is_running(&self, name: &str) -> bool {
+        self.processes.values().any(|p| {
+            is_instance_of(&p.name, name)
+                && (p.state.is_active() || p.state == ProcessState::Completed)
+        })
+    }
+
+    /// Check the usage of every process against its `max_memory` and `max_cpu`
+    ///
+    /// Limits apply to the process and everything it started, so the tree usage is checked.
     pub async fn check_resource_limits(&mut self) -> Vec<(ProcessId, String)> {
         let mut violations = Vec::new();
         let mut actions_needed: Vec<(ProcessId, LimitAction, String)> = Vec::new();
+        let mut events = Vec::new();
 
         for (id, process) in self.processes.iter_mut() {
-            if let Some(max_memory) = process.config.max_memory {
-                if process.stats.memory_usage > max_memory {
+            if let Some(ByteSize(max_memory)) = process.config.max_memory {
+                if process.stats.tree_memory_usage > max_memory {
                     process.stats.record_memory_violation();
                     let msg = format!(
                         "Process {} exceeded memory limit: {} bytes (limit: {} bytes)",
-                        process.name, process.stats.memory_usage, max_memory
+                        process.name, process.stats.tree_memory_usage, max_memory
                     );
                     tracing::warn!("{}", msg);
+                    events.push(Event::LimitViolation {
+                        id: *id,
+                        name: process.name.clone(),
+                        resource: LimitResource::Memory,
+                        usage: process.stats.tree_memory_usage as f64,
+                        limit: max_memory as f64,
+                        action: process.config.limit_action,
+                    });
                     actions_needed.push((*id, process.config.limit_action, msg));
                     continue;
                 }
             }
 
             if let Some(max_cpu) = process.config.max_cpu {
-                if process.stats.cpu_usage > max_cpu as f32 {
+                if process.stats.tree_cpu_usage > max_cpu as f32 {
                     process.stats.record_cpu_violation();
                     let msg = format!(
                         "Process {} exceeded CPU limit: {:.1}% (limit: {}%)",
-                        process.name, process.stats.cpu_usage, max_cpu
+                        process.name, process.stats.tree_cpu_usage, max_cpu
                     );
                     tracing::warn!("{}", msg);
+                    events.push(Event::LimitViolation {
+                        id: *id,
+                        name: process.name.clone(),
+                        resource: LimitResource::Cpu,
+                        usage: process.stats.tree_cpu_usage as f64,
+                        limit: max_cpu as f64,
+                        action: process.config.limit_action,
+                    });
                     actions_needed.push((*id, process.config.limit_action, msg));
                 }
             }
         }
 
+        for event in events {
+            self.emit(event);
+        }
+
         for (id, action, msg) in actions_needed {
             match action {
                 LimitAction::Log => {
@@ -1,6 +1,9 @@
 use super::*;
-use crate::config::LimitAction;
-use std::collections::HashMap;
+use crate::config::diff::{PlanAction, ReloadPlan, RunningProcess};
+use crate::config::{KillMode, ReadinessCheck, ReadinessConfig, RestartMode};
+use crate::ipc::protocol::Event;
+use nix::sys::signal;
+use nix::unistd::Pid;
 use std::path::PathBuf;
 
 fn create_test_config(name: &str) -> ProcessConfig {
@@ -8,17 +11,8 @@ fn create_test_config(name: &str) -> ProcessConfig {
         name: name.to_string(),
         script: PathBuf::from("/bin/sleep"),
         args: vec!["10".to_string()],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
-        autorestart: true,
-        max_restarts: 10,
-        restart_delay_secs: 1,
-        max_memory: None,
-        max_cpu: None,
-        limit_action: LimitAction::Log,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 2,
+        stop_timeout: Duration::from_secs(2),
+        ..Default::default()
     }
 }
 
@@ -211,6 +205,29 @@ async fn test_find_by_name() {
     let _ = manager.stop(id, true).await;
 }
 
+#[tokio::test]
+async fn test_lookup_by_name_ignores_similar_names() {
+    let mut manager = ProcessManager::new();
+    let web = manager.spawn(create_test_config("web")).await.unwrap();
+    let web_api = manager.spawn(create_test_config("web-api")).await.unwrap();
+
+    let histories = manager.stats_history("web").unwrap();
+    assert_eq!(histories.len(), 1);
+    assert_eq!(histories[0].id, web);
+
+    let found: Vec<ProcessId> = manager
+        .find_all_by_name("web")
+        .iter()
+        .map(|p| p.id)
+        .collect();
+    assert_eq!(found, vec![web]);
+    assert_eq!(manager.instances_of("web").len(), 1);
+    assert!(manager.stats_history("web-api").is_ok());
+
+    let _ = manager.stop(web, true).await;
+    let _ = manager.stop(web_api, true).await;
+}
+
 #[tokio::test]
 async fn test_remove_process() {
     let mut manager = ProcessManager::new();
@@ -232,7 +249,7 @@ async fn test_custom_stop_signal() {
 
     let mut config = create_test_config("custom-signal");
     config.stop_signal = "SIGINT".to_string();
-    config.stop_timeout_secs = 2;
+    config.stop_timeout = Duration::from_secs(2);
 
     let id = manager.spawn(config).await.unwrap();
 
@@ -252,7 +269,7 @@ async fn test_stop_with_timeout() {
     let mut manager = ProcessManager::new();
 
     let mut config = create_test_config("timeout-test");
-    config.stop_timeout_secs = 2;
+    config.stop_timeout = Duration::from_secs(2);
 
     let id = manager.spawn(config).await.unwrap();
 
@@ -347,3 +364,812 @@ async fn test_graceful_shutdown_with_different_signals() {
         assert_eq!(process.state, ProcessState::Stopped);
     }
 }
+
+#[tokio::test]
+async fn test_health_check_marks_unhealthy() {
+    use crate::config::{HealthCheckConfig, HealthProbe};
+
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("health-unhealthy");
+    config.health_check = Some(HealthCheckConfig {
+        probe: HealthProbe::Exec {
+            command: PathBuf::from("/bin/false"),
+            args: vec![],
+        },
+        interval: Duration::from_secs(1),
+        timeout: Duration::from_secs(1),
+        failure_threshold: 2,
+    });
+
+    let id = manager.spawn(config).await.unwrap();
+
+    // No probe is due until the process has been up for one interval
+    assert!(manager.due_health_checks().is_empty());
+
+    let failure = || vec![(id, Err(AdasaError::HealthCheckFailed("down".to_string())))];
+
+    // Below the threshold the process stays running
+    assert!(manager.record_health_results(failure()).is_empty());
+    assert_eq!(manager.get_status(id).unwrap().state, ProcessState::Running);
+
+    assert_eq!(manager.record_health_results(failure()), vec![id]);
+    assert_eq!(
+        manager.get_status(id).unwrap().state,
+        ProcessState::Unhealthy
+    );
+
+    // A passing probe brings it back
+    assert!(manager.record_health_results(vec![(id, Ok(()))]).is_empty());
+    assert_eq!(manager.get_status(id).unwrap().state, ProcessState::Running);
+
+    let _ = manager.stop(id, true).await;
+}
+
+/// Run the supervisor steps that carry out unhealthy restarts until the process has a new PID
+async fn wait_for_respawn(manager: &mut ProcessManager, id: ProcessId, old_pid: u32) {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while manager.get_status(id).unwrap().stats.pid == old_pid {
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "process not respawned"
+        );
+        manager.detect_crashes();
+        manager.run_due_restarts().await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_restart_unhealthy() {
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("health-restart");
+    config.restart_delay = Duration::from_secs(0);
+
+    let id = manager.spawn(config).await.unwrap();
+    let old_pid = manager.get_status(id).unwrap().stats.pid;
+
+    // The process is only signalled, and respawned once it has exited
+    assert!(manager.restart_unhealthy(id).unwrap());
+    assert_eq!(manager.get_status(id).unwrap().stats.pid, old_pid);
+    wait_for_respawn(&mut manager, id, old_pid).await;
+
+    let process = manager.get_status(id).unwrap();
+    assert_eq!(process.state, ProcessState::Running);
+    assert_eq!(process.stats.restarts, 1);
+
+    let _ = manager.stop(id, true).await;
+}
+
+#[tokio::test]
+async fn test_restart_unhealthy_kills_after_stop_timeout() {
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("health-restart-kill");
+    config.script = PathBuf::from("/bin/sh");
+    config.args = vec!["-c".to_string(), "trap '' TERM; sleep 10".to_string()];
+    config.restart_delay = Duration::from_secs(0);
+    config.stop_timeout = Duration::from_millis(200);
+
+    let id = manager.spawn(config).await.unwrap();
+    let old_pid = manager.get_status(id).unwrap().stats.pid;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert!(manager.restart_unhealthy(id).unwrap());
+    wait_for_respawn(&mut manager, id, old_pid).await;
+    assert_eq!(manager.get_status(id).unwrap().state, ProcessState::Running);
+
+    let _ = manager.stop(id, true).await;
+}
+
+#[tokio::test]
+async fn test_stop_cancels_unhealthy_restart() {
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("health-restart-cancel");
+    config.restart_delay = Duration::from_secs(0);
+
+    let id = manager.spawn(config).await.unwrap();
+    assert!(manager.restart_unhealthy(id).unwrap());
+    manager.stop(id, false).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    manager.detect_crashes();
+    assert!(manager.run_due_restarts().await.is_empty());
+    assert_eq!(manager.get_status(id).unwrap().state, ProcessState::Stopped);
+}
+
+#[tokio::test]
+async fn test_rolling_restart_waits_for_readiness() {
+    let mut manager = ProcessManager::new();
+    let mut ids = Vec::new();
+
+    for i in 0..4 {
+        let mut config = create_test_config(&format!("ready-app-{}", i));
+        config.script = PathBuf::from("/bin/sh");
+        config.args = vec![
+            "-c".to_string(),
+            "echo booting; echo ready; sleep 10".to_string(),
+        ];
+        config.readiness = Some(ReadinessConfig {
+            check: ReadinessCheck::Log {
+                pattern: "^ready$".to_string(),
+            },
+            timeout: Duration::from_secs(5),
+        });
+        ids.push(manager.spawn(config).await.unwrap());
+    }
+
+    let old_pids: Vec<u32> = ids
+        .iter()
+        .map(|id| manager.get_status(*id).unwrap().stats.pid)
+        .collect();
+
+    let count = manager
+        .rolling_restart_batched("ready-app", Some(BatchSize::Percent(50)))
+        .await
+        .unwrap();
+    assert_eq!(count, 4);
+
+    for (id, old_pid) in ids.iter().zip(old_pids) {
+        let process = manager.get_status(*id).unwrap();
+        assert_eq!(process.state, ProcessState::Running);
+        assert_ne!(process.stats.pid, old_pid);
+    }
+
+    for id in ids {
+        let _ = manager.stop(id, true).await;
+    }
+}
+
+#[tokio::test]
+async fn test_rolling_restart_rolls_back_unready_instance() {
+    let mut manager = ProcessManager::new();
+    let mut ids = Vec::new();
+
+    for i in 0..2 {
+        let mut config = create_test_config(&format!("unready-app-{}", i));
+        config.readiness = Some(ReadinessConfig {
+            check: ReadinessCheck::Exec {
+                command: PathBuf::from("/bin/false"),
+                args: vec![],
+            },
+            timeout: Duration::from_secs(1),
+        });
+        ids.push(manager.spawn(config).await.unwrap());
+    }
+
+    let second_pid = manager.get_status(ids[1]).unwrap().stats.pid;
+
+    let result = manager.rolling_restart("unready-app").await;
+    assert!(matches!(result, Err(AdasaError::RestartError(_, _))));
+
+    // The failed instance was rolled back and is running again
+    let first = manager.get_status(ids[0]).unwrap();
+    assert_eq!(first.state, ProcessState::Running);
+    assert_eq!(first.stats.restarts, 2);
+    assert!(manager.is_alive(ids[0]));
+
+    // The rollout stopped before touching the second instance
+    let second = manager.get_status(ids[1]).unwrap();
+    assert_eq!(second.stats.pid, second_pid);
+    assert_eq!(second.stats.restarts, 0);
+
+    for id in ids {
+        let _ = manager.stop(id, true).await;
+    }
+}
+
+#[tokio::test]
+async fn test_stop_all_stops_dependents_first() {
+    let mut manager = ProcessManager::new();
+
+    let db = manager.spawn(create_test_config("db")).await.unwrap();
+
+    let mut api_config = create_test_config("api-0");
+    api_config.depends_on = vec!["db".to_string()];
+    let api = manager.spawn(api_config).await.unwrap();
+
+    let mut worker_config = create_test_config("worker");
+    worker_config.depends_on = vec!["api".to_string()];
+    let worker = manager.spawn(worker_config).await.unwrap();
+
+    assert!(manager.is_running("db"));
+    assert!(manager.is_running("api"));
+    assert!(!manager.is_running("ap"));
+
+    assert_eq!(manager.shutdown_order(), vec![worker, api, db]);
+
+    manager.stop_all().await.unwrap();
+    assert!(!manager.is_running("db"));
+}
+
+#[tokio::test]
+async fn test_spawn_with_readiness() {
+    let mut manager = ProcessManager::new();
+
+    let mut config = create_test_config("ready-sidecar");
+    config.script = PathBuf::from("/bin/sh");
+    config.args = vec![
+        "-c".to_string(),
+        "sleep 0.2; echo accepting connections; sleep 10".to_string(),
+    ];
+    config.readiness = Some(ReadinessConfig {
+        check: ReadinessCheck::Log {
+            pattern: "accepting connections".to_string(),
+        },
+        timeout: Duration::from_secs(5),
+    });
+
+    let (id, watch) = manager
+        .spawn_with_readiness(config, Duration::ZERO)
+        .await
+        .unwrap();
+    manager.wait_until_ready(vec![(id, watch)]).await.unwrap();
+
+    // A process that exits before it is ready is reported
+    let mut config = create_test_config("exits-early");
+    config.script = PathBuf::from("/bin/true");
+    config.args = vec![];
+    config.readiness = Some(ReadinessConfig {
+        check: ReadinessCheck::Notify,
+        timeout: Duration::from_secs(5),
+    });
+
+    let (early, watch) = manager
+        .spawn_with_readiness(config, Duration::ZERO)
+        .await
+        .unwrap();
+    let result = manager.wait_until_ready(vec![(early, watch)]).await;
+    assert!(matches!(result, Err(AdasaError::NotReady(_))));
+
+    let _ = manager.stop(id, true).await;
+}
+
+#[tokio::test]
+async fn test_scheduled_job_runs_when_due() {
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("scheduled-job");
+    config.script = PathBuf::from("/bin/sh");
+    config.args = vec!["-c".to_string(), "exit 3".to_string()];
+    config.schedule = Some("0 0 * * *".to_string());
+
+    let id = manager.spawn(config).await.unwrap();
+    let process = manager.get_status(id).unwrap();
+    assert_eq!(process.state, ProcessState::Scheduled);
+    assert!(process.child.is_none());
+    assert!(process.next_run.is_some());
+
+    // Nothing is due yet
+    assert!(manager.run_due_jobs().await.is_empty());
+
+    manager.get_mut(id).unwrap().next_run = Some(SystemTime::now());
+    assert_eq!(manager.run_due_jobs().await, vec![id]);
+    assert_eq!(manager.get_status(id).unwrap().state, ProcessState::Running);
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // A finished run is not a crash, even with a non-zero exit code
+    assert!(manager.detect_crashes().is_empty());
+
+    let process = manager.get_status(id).unwrap();
+    assert_eq!(process.state, ProcessState::Scheduled);
+    assert_eq!(process.stats.last_exit_code, Some(3));
+    assert!(process.stats.last_run.is_some());
+    assert!(process.stats.last_run_duration.is_some());
+    assert_eq!(process.stats.restarts, 0);
+    assert!(process.next_run.unwrap() > SystemTime::now());
+}
+
+#[tokio::test]
+async fn test_scheduled_job_no_overlap() {
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("no-overlap-job");
+    config.schedule = Some("0 0 * * *".to_string());
+    config.no_overlap = true;
+
+    let id = manager.spawn(config).await.unwrap();
+
+    manager.get_mut(id).unwrap().next_run = Some(SystemTime::now());
+    assert_eq!(manager.run_due_jobs().await, vec![id]);
+    let first_pid = manager.get_status(id).unwrap().stats.pid;
+
+    // The previous run is still sleeping, so the next one is skipped
+    manager.get_mut(id).unwrap().next_run = Some(SystemTime::now());
+    assert!(manager.run_due_jobs().await.is_empty());
+
+    let process = manager.get_status(id).unwrap();
+    assert_eq!(process.stats.pid, first_pid);
+    assert!(process.overlapping_runs.is_empty());
+
+    manager.stop(id, true).await.unwrap();
+    let process = manager.get_status(id).unwrap();
+    assert_eq!(process.state, ProcessState::Stopped);
+    assert!(process.next_run.is_none());
+}
+
+#[tokio::test]
+async fn test_oneshot_exit_codes() {
+    let mut manager = ProcessManager::new();
+
+    let mut config = create_test_config("oneshot-ok");
+    config.script = PathBuf::from("/bin/sh");
+    config.args = vec!["-c".to_string(), "exit 0".to_string()];
+    config.oneshot = true;
+    let ok_id = manager.spawn(config).await.unwrap();
+
+    let mut config = create_test_config("oneshot-fail");
+    config.script = PathBuf::from("/bin/sh");
+    config.args = vec!["-c".to_string(), "exit 1".to_string()];
+    config.oneshot = true;
+    let fail_id = manager.spawn(config).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(manager.detect_crashes(), vec![fail_id]);
+
+    let process = manager.get_status(ok_id).unwrap();
+    assert_eq!(process.state, ProcessState::Completed);
+    assert_eq!(process.stats.last_exit_code, Some(0));
+
+    let process = manager.get_status(fail_id).unwrap();
+    assert_eq!(process.state, ProcessState::Errored);
+    assert_eq!(process.stats.last_exit_code, Some(1));
+}
+
+#[tokio::test]
+async fn test_oneshot_dependency_ready_on_completion() {
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("migrate");
+    config.script = PathBuf::from("/bin/sh");
+    config.args = vec!["-c".to_string(), "sleep 0.3".to_string()];
+    config.oneshot = true;
+
+    let (id, watch) = manager
+        .spawn_with_readiness(config, Duration::from_millis(50))
+        .await
+        .unwrap();
+    manager.wait_until_ready(vec![(id, watch)]).await.unwrap();
+
+    assert_eq!(
+        manager.get_status(id).unwrap().state,
+        ProcessState::Completed
+    );
+    assert!(manager.is_running("migrate"));
+}
+
+#[tokio::test]
+async fn test_on_failure_does_not_restart_clean_exit() {
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("clean-exit");
+    config.script = PathBuf::from("/bin/sh");
+    config.args = vec!["-c".to_string(), "exit 0".to_string()];
+    config.restart_delay = Duration::from_secs(0);
+    config.restart = Some(RestartMode::OnFailure);
+
+    let id = manager.spawn(config).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(manager.detect_crashes(), vec![id]);
+
+    let process = manager.get_status(id).unwrap();
+    assert_eq!(process.state, ProcessState::Stopped);
+    assert_eq!(process.last_exit(), ExitKind::Success);
+
+    assert!(!manager.try_auto_restart(id).unwrap());
+    assert_eq!(manager.get_status(id).unwrap().stats.restarts, 0);
+}
+
+#[tokio::test]
+async fn test_no_restart_exit_code() {
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("fatal-exit");
+    config.script = PathBuf::from("/bin/sh");
+    config.args = vec!["-c".to_string(), "exit 78".to_string()];
+    config.restart_delay = Duration::from_secs(0);
+    config.no_restart_exit_codes = vec![78];
+
+    let id = manager.spawn(config).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(manager.detect_crashes(), vec![id]);
+
+    let process = manager.get_status(id).unwrap();
+    assert_eq!(process.state, ProcessState::Errored);
+    assert_eq!(process.last_exit(), ExitKind::Failure(78));
+
+    assert!(!manager.try_auto_restart(id).unwrap());
+}
+
+#[tokio::test]
+async fn test_signal_exit_is_restarted() {
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("killed");
+    config.restart_delay = Duration::from_secs(0);
+    config.restart = Some(RestartMode::OnFailure);
+
+    let id = manager.spawn(config).await.unwrap();
+    let pid = manager.get_status(id).unwrap().stats.pid;
+    signal::kill(Pid::from_raw(pid as i32), Signal::SIGKILL).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(manager.detect_crashes(), vec![id]);
+
+    let process = manager.get_status(id).unwrap();
+    assert_eq!(
+        process.last_exit(),
+        ExitKind::Signal(Signal::SIGKILL as i32)
+    );
+    assert_eq!(process.stats.last_exit_code, None);
+
+    assert!(manager.try_auto_restart(id).unwrap());
+    assert_eq!(manager.run_due_restarts().await, vec![id]);
+    assert_eq!(manager.get_status(id).unwrap().state, ProcessState::Running);
+
+    let _ = manager.stop(id, true).await;
+}
+
+#[tokio::test]
+async fn test_crash_restart_waits_out_backoff() {
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("backoff");
+    config.restart_delay = Duration::from_millis(300);
+
+    let id = manager.spawn(config).await.unwrap();
+    let pid = manager.get_status(id).unwrap().stats.pid;
+    signal::kill(Pid::from_raw(pid as i32), Signal::SIGKILL).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(manager.detect_crashes(), vec![id]);
+
+    // Scheduling returns at once; the respawn waits for a later call once the delay passed
+    assert!(manager.try_auto_restart(id).unwrap());
+    assert!(manager.run_due_restarts().await.is_empty());
+    assert_ne!(manager.get_status(id).unwrap().state, ProcessState::Running);
+
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert_eq!(manager.run_due_restarts().await, vec![id]);
+    assert_eq!(manager.get_status(id).unwrap().state, ProcessState::Running);
+
+    // Stopping a process drops its pending restart
+    let pid = manager.get_status(id).unwrap().stats.pid;
+    signal::kill(Pid::from_raw(pid as i32), Signal::SIGKILL).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(manager.detect_crashes(), vec![id]);
+    assert!(manager.try_auto_restart(id).unwrap());
+    manager.stop(id, true).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert!(manager.run_due_restarts().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_crash_loop_after_restart_limit() {
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("crash-loop");
+    config.script = PathBuf::from("/bin/false");
+    config.args = vec![];
+    config.restart_delay = Duration::from_secs(0);
+    config.max_restarts = 2;
+    config.min_uptime = Duration::from_secs(10);
+
+    let id = manager.spawn(config).await.unwrap();
+    let mut events = manager.subscribe();
+
+    for _ in 0..2 {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(manager.detect_crashes(), vec![id]);
+        assert!(manager.try_auto_restart(id).unwrap());
+        assert_eq!(manager.run_due_restarts().await, vec![id]);
+    }
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(manager.detect_crashes(), vec![id]);
+    assert!(!manager.try_auto_restart(id).unwrap());
+
+    let process = manager.get_status(id).unwrap();
+    assert_eq!(process.state, ProcessState::CrashLoop);
+    assert_eq!(process.stats.restarts, 2);
+
+    let crash_loop = std::iter::from_fn(|| events.try_recv().ok()).last();
+    assert_eq!(
+        crash_loop,
+        Some(Event::CrashLoop {
+            id,
+            name: "crash-loop".to_string(),
+            restarts: 2
+        })
+    );
+
+    // A crash-looping process is not picked up again
+    assert!(manager.detect_crashes().is_empty());
+}
+
+#[tokio::test]
+async fn test_min_uptime_resets_restart_history() {
+    let mut manager = ProcessManager::new();
+    let mut config = create_test_config("stable");
+    config.restart_delay = Duration::from_secs(0);
+    config.min_uptime = Duration::from_secs(0);
+
+    let id = manager.spawn(config).await.unwrap();
+    let process = manager.get_mut(id).unwrap();
+    process.restart_tracker.record_restart();
+    let pid = process.stats.pid;
+
+    signal::kill(Pid::from_raw(pid as i32), Signal::SIGKILL).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(manager.detect_crashes(), vec![id]);
+    let process = manager.get_status(id).unwrap();
+    assert_eq!(process.restart_tracker.restart_count(), 0);
+}
+
+#[tokio::test]
+async fn test_lifecycle_events() {
+    let mut manager = ProcessManager::new();
+    let mut events = manager.subscribe();
+    let mut config = create_test_config("evented");
+    config.restart_delay = Duration::from_secs(0);
+
+    let id = manager.spawn(config).await.unwrap();
+    let pid = manager.get_status(id).unwrap().stats.pid;
+    assert_eq!(
+        events.try_recv().unwrap(),
+        Event::ProcessStarted {
+            id,
+            name: "evented".to_string(),
+            pid
+        }
+    );
+
+    signal::kill(Pid::from_raw(pid as i32), Signal::SIGKILL).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(manager.detect_crashes(), vec![id]);
+    assert_eq!(
+        events.try_recv().unwrap(),
+        Event::ProcessExited {
+            id,
+            name: "evented".to_string(),
+            exit_code: None,
+            signal: Some(Signal::SIGKILL as i32)
+        }
+    );
+
+    assert!(manager.try_auto_restart(id).unwrap());
+    assert_eq!(manager.run_due_restarts().await, vec![id]);
+    assert!(matches!(
+        events.try_recv().unwrap(),
+        Event::ProcessRestarted { restarts: 1, .. }
+    ));
+
+    let _ = manager.stop(id, true).await;
+    assert!(matches!(
+        events.try_recv().unwrap(),
+        Event::ProcessExited { .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_restore_keeps_ids_and_state() {
+    let mut manager = ProcessManager::new();
+
+    manager
+        .restore(
+            ProcessId::new(7),
+            create_test_config("restored-running"),
+            ProcessState::Running,
+        )
+        .await
+        .unwrap();
+    manager
+        .restore(
+            ProcessId::new(3),
+            create_test_config("restored-stopped"),
+            ProcessState::Stopped,
+        )
+        .await
+        .unwrap();
+    manager
+        .restore(
+            ProcessId::new(4),
+            create_test_config("restored-errored"),
+            ProcessState::Errored,
+        )
+        .await
+        .unwrap();
+
+    let running = manager.get_status(ProcessId::new(7)).unwrap();
+    assert_eq!(running.state, ProcessState::Running);
+    assert!(running.child.is_some());
+
+    let stopped = manager.get_status(ProcessId::new(3)).unwrap();
+    assert_eq!(stopped.state, ProcessState::Stopped);
+    assert!(stopped.child.is_none());
+    assert_eq!(
+        manager.get_status(ProcessId::new(4)).unwrap().state,
+        ProcessState::Errored
+    );
+
+    // Restored IDs are never handed out again
+    let result = manager
+        .restore(
+            ProcessId::new(3),
+            create_test_config("other"),
+            ProcessState::Running,
+        )
+        .await;
+    assert!(matches!(result, Err(AdasaError::ProcessAlreadyExists(_))));
+    let new_id = manager.spawn(create_test_config("fresh")).await.unwrap();
+    assert_eq!(new_id.as_u64(), 8);
+
+    // A stopped process can be started again with a restart
+    manager.restart(ProcessId::new(3)).await.unwrap();
+    assert_eq!(
+        manager.get_status(ProcessId::new(3)).unwrap().state,
+        ProcessState::Running
+    );
+
+    manager.stop_all().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_restore_stopped_process_by_restart_mode() {
+    let mut manager = ProcessManager::new();
+
+    for (id, name, mode) in [
+        (1, "always", Some(RestartMode::Always)),
+        (2, "unless-stopped", Some(RestartMode::UnlessStopped)),
+        (3, "default", None),
+        (4, "on-failure", Some(RestartMode::OnFailure)),
+    ] {
+        let config = ProcessConfig {
+            restart: mode,
+            ..create_test_config(name)
+        };
+        manager
+            .restore(ProcessId::new(id), config, ProcessState::Stopped)
+            .await
+            .unwrap();
+    }
+
+    // Only `always` brings a stopped process back with the daemon
+    let always = manager.get_status(ProcessId::new(1)).unwrap();
+    assert_eq!(always.state, ProcessState::Running);
+    assert!(always.child.is_some());
+
+    for id in 2..=4 {
+        let process = manager.get_status(ProcessId::new(id)).unwrap();
+        assert_eq!(process.state, ProcessState::Stopped, "{}", process.name);
+        assert!(process.child.is_none());
+    }
+
+    manager.stop_all().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_adopt_running_process() {
+    let mut manager = ProcessManager::new();
+    let mut orphan = std::process::Command::new("/bin/sleep")
+        .arg("10")
+        .spawn()
+        .unwrap();
+    let pid = orphan.id();
+    let start_time = procfs::start_time(pid).unwrap();
+
+    // A reused PID is not mistaken for the original process
+    let result = manager.adopt(
+        ProcessId::new(5),
+        create_test_config("adopted"),
+        pid,
+        start_time + 1,
+    );
+    assert!(matches!(result, Err(AdasaError::ProcessNotFound(_))));
+
+    manager
+        .adopt(
+            ProcessId::new(5),
+            create_test_config("adopted"),
+            pid,
+            start_time,
+        )
+        .unwrap();
+
+    let adopted = manager.get_status(ProcessId::new(5)).unwrap();
+    assert_eq!(adopted.state, ProcessState::Running);
+    assert!(adopted.child.is_none());
+    assert_eq!(adopted.live_pid(), Some(pid));
+    assert!(manager.detect_crashes().is_empty());
+
+    manager.stop(ProcessId::new(5), false).await.unwrap();
+    assert_eq!(
+        manager.get_status(ProcessId::new(5)).unwrap().state,
+        ProcessState::Stopped
+    );
+    assert!(!procfs::is_same_process(pid, start_time));
+    assert!(orphan.wait().is_ok());
+}
+
+#[tokio::test]
+async fn test_stop_kills_process_tree() {
+    let mut manager = ProcessManager::new();
+
+    // One descendant stays in the group, one starts its own session, one ignores SIGTERM
+    let mut config = create_test_config("tree");
+    config.script = PathBuf::from("/bin/sh");
+    config.args = vec![
+        "-c".to_string(),
+        "sleep 30 & setsid sleep 30 & sh -c 'trap \"\" TERM; sleep 30' & wait".to_string(),
+    ];
+    config.stop_timeout = Duration::from_secs(1);
+
+    let id = manager.spawn(config).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let pid = manager.get_status(id).unwrap().stats.pid;
+    let descendants = procfs::descendants(pid);
+    assert!(descendants.len() >= 3, "{:?}", descendants);
+
+    manager.stop(id, false).await.unwrap();
+    assert!(descendants.iter().all(|p| !p.is_alive()));
+}
+
+#[tokio::test]
+async fn test_kill_mode_process_leaves_descendants() {
+    let mut manager = ProcessManager::new();
+
+    let mut config = create_test_config("main-only");
+    config.script = PathBuf::from("/bin/sh");
+    config.args = vec!["-c".to_string(), "sleep 30 & wait".to_string()];
+    config.kill_mode = KillMode::Process;
+
+    let id = manager.spawn(config).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let pid = manager.get_status(id).unwrap().stats.pid;
+    let descendants = procfs::descendants(pid);
+    assert_eq!(descendants.len(), 1);
+
+    manager.stop(id, false).await.unwrap();
+    assert!(descendants[0].is_alive());
+
+    let _ = signal::kill(Pid::from_raw(descendants[0].pid as i32), Signal::SIGKILL);
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_reload_moves_process_into_new_cgroup() {
+    // A directory with a `cgroup.controllers` file stands in for the cgroup v2 hierarchy
+    let hierarchy = tempfile::tempdir().unwrap();
+    std::fs::write(hierarchy.path().join("cgroup.controllers"), "cpu memory").unwrap();
+
+    let mut manager = ProcessManager::new();
+    manager.set_cgroup_root(hierarchy.path().join("adasa"));
+    let config = create_test_config("cgroup-reload");
+    let id = manager.spawn(config.clone()).await.unwrap();
+    assert!(manager.get_status(id).unwrap().cgroup_manager.is_none());
+
+    let mut reloaded = config.clone();
+    reloaded.max_cpu = Some(50);
+    let running = RunningProcess {
+        config,
+        instances: 1,
+        from_file: true,
+    };
+    let plan = ReloadPlan::compute(std::slice::from_ref(&reloaded), &[running]);
+    assert_eq!(plan.entries[0].action, PlanAction::Restart);
+
+    manager.update_config(id, reloaded).unwrap();
+    let process = manager.get_status(id).unwrap();
+    let cgroup = process
+        .cgroup_manager
+        .as_ref()
+        .expect("cgroup created on update");
+    let read = |file: &str| std::fs::read_to_string(cgroup.path().join(file)).unwrap();
+    assert_eq!(read("cpu.max"), "50000 100000");
+    assert_eq!(read("cgroup.procs"), process.stats.pid.to_string());
+
+    let path = cgroup.path().to_path_buf();
+
+    // The restart from the plan spawns into the same cgroup
+    manager.restart(id).await.unwrap();
+    let process = manager.get_status(id).unwrap();
+    assert_eq!(process.cgroup_manager.as_ref().unwrap().path(), path);
+
+    let _ = manager.stop(id, true).await;
+}
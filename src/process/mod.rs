@@ -1,15 +1,26 @@
+pub mod health;
+pub mod history;
+pub mod instance;
 pub mod limits;
 mod manager;
 pub mod monitor;
+pub mod procfs;
+pub mod readiness;
 pub mod restart;
 pub mod spawner;
 pub mod supervisor;
+pub mod tree;
 mod types;
 
+pub use health::HealthTracker;
+pub use history::StatsHistory;
+pub use instance::{is_instance_of, split_instance};
 pub use limits::{cgroup::CGroupManager, ResourceLimits};
 pub use manager::ProcessManager;
 pub use monitor::ProcessMonitor;
-pub use restart::{BackoffStrategy, RestartPolicy, RestartTracker};
+pub use readiness::ReadinessWatch;
+pub use restart::{BackoffStrategy, ExitKind, RestartPolicy, RestartTracker};
 pub use spawner::{spawn_process, SpawnedProcess};
 pub use supervisor::{ProcessSupervisor, SupervisorConfig};
+pub use tree::ProcessTree;
 pub use types::{ManagedProcess, ProcessState, ProcessStats};
@@ -1,15 +1,25 @@
 use crate::error::Result;
-use crate::process::{ManagedProcess, ProcessState};
+use crate::process::limits::cgroup::CGroupManager;
+use crate::process::ManagedProcess;
 use std::collections::HashMap;
 use std::time::Instant;
 use sysinfo::{Pid, ProcessRefreshKind, System, RefreshKind};
 
+/// CPU time a cgroup had used at some point, to compute its CPU usage from the next reading
+#[derive(Debug, Clone, Copy)]
+struct CgroupCpuSample {
+    usage_usec: u64,
+    at: Instant,
+}
+
 /// Process monitor for collecting resource usage statistics (optimized)
 pub struct ProcessMonitor {
     /// System information collector
     system: System,
     /// Cache of previous CPU measurements for accurate calculation
     cpu_cache: HashMap<u32, f32>,
+    /// Previous CPU time of the cgroups, by PID of the main process
+    cgroup_cpu_cache: HashMap<u32, CgroupCpuSample>,
     /// Last refresh time to avoid excessive polling
     last_refresh: Option<Instant>,
     /// Minimum interval between full system refreshes (milliseconds)
@@ -36,6 +46,7 @@ impl ProcessMonitor {
         Self {
             system,
             cpu_cache: HashMap::with_capacity(64), // Pre-allocate for typical workload
+            cgroup_cpu_cache: HashMap::new(),
             last_refresh: None,
             refresh_interval_ms,
         }
@@ -51,6 +62,9 @@ impl ProcessMonitor {
 
     /// Update statistics for a single managed process (optimized)
     ///
+    /// Sets both the usage of the main process and of its whole tree; see
+    /// [`ProcessMonitor::update_all_stats`].
+    ///
     /// # Arguments
     /// * `process` - The managed process to update
     ///
@@ -58,46 +72,27 @@ impl ProcessMonitor {
     /// * `Ok(())` - Statistics updated successfully
     /// * `Err(AdasaError)` - Failed to update statistics
     pub fn update_process_stats(&mut self, process: &mut ManagedProcess) -> Result<()> {
-        let pid = process.stats.pid;
-        let sys_pid = Pid::from_u32(pid);
-
         // Only refresh if enough time has passed (rate limiting)
         if self.should_refresh() {
-            // Refresh only CPU and memory for this specific process
-            self.system.refresh_processes_specifics(
-                sysinfo::ProcessesToUpdate::Some(&[sys_pid]),
-                true,
-                ProcessRefreshKind::new()
-                    .with_cpu()
-                    .with_memory(),
-            );
-            self.last_refresh = Some(Instant::now());
+            self.refresh_all();
         }
 
-        // Get process information from sysinfo
-        if let Some(sys_process) = self.system.process(sys_pid) {
-            // Update CPU usage
-            let cpu_usage = sys_process.cpu_usage();
-            process.stats.cpu_usage = cpu_usage;
-            
-            // Use entry API to avoid double lookup
-            *self.cpu_cache.entry(pid).or_insert(0.0) = cpu_usage;
-
-            // Update memory usage (in bytes)
-            process.stats.memory_usage = sys_process.memory();
-
-            // Process is still running
-            Ok(())
-        } else {
+        let children = self.children();
+        if !self.apply_usage(process, &children) {
             // Process not found in system - it has crashed or exited
             process.mark_errored();
-            self.cpu_cache.remove(&pid);
-            Ok(())
+            self.clear_cache(process.stats.pid);
         }
+        Ok(())
     }
 
     /// Update statistics for multiple managed processes (optimized batch operation)
     ///
+    /// The usage of the main process is kept apart from the usage of the whole tree:
+    /// the main process and everything it started. For a process with a cgroup the
+    /// tree usage is read from the cgroup, which also covers descendants that were
+    /// reparented; otherwise it is summed over the current descendants.
+    ///
     /// # Arguments
     /// * `processes` - Iterator of mutable references to managed processes
     ///
@@ -112,45 +107,122 @@ impl ProcessMonitor {
             return Ok(());
         }
 
-        // Collect PIDs of running processes to minimize allocations
-        let running_pids: Vec<Pid> = processes
-            .filter_map(|p| {
-                if p.state == ProcessState::Running {
-                    Some(Pid::from_u32(p.stats.pid))
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        if running_pids.is_empty() {
+        let running: Vec<&mut ManagedProcess> = processes.filter(|p| p.state.is_active()).collect();
+        if running.is_empty() {
             return Ok(());
         }
 
-        // Batch refresh only the specific processes we're monitoring
-        // This is much more efficient than refreshing all system processes
+        self.refresh_all();
+
+        // Processes that are gone are left to crash detection
+        let children = self.children();
+        for process in running {
+            self.apply_usage(process, &children);
+        }
+
+        Ok(())
+    }
+
+    /// Refresh CPU and memory of every process on the system
+    ///
+    /// The descendants of the managed processes are not known in advance, so
+    /// everything is refreshed in one pass over `/proc`.
+    fn refresh_all(&mut self) {
         self.system.refresh_processes_specifics(
-            sysinfo::ProcessesToUpdate::Some(&running_pids),
+            sysinfo::ProcessesToUpdate::All,
             true,
-            ProcessRefreshKind::new()
-                .with_cpu()
-                .with_memory(),
+            ProcessRefreshKind::new().with_cpu().with_memory(),
         );
-        
         self.last_refresh = Some(Instant::now());
+    }
 
-        // Update stats from cached system data (no additional syscalls)
-        for pid in running_pids {
-            if let Some(sys_process) = self.system.process(pid) {
-                let pid_u32 = pid.as_u32();
-                let cpu_usage = sys_process.cpu_usage();
-                
-                // Update cache
-                *self.cpu_cache.entry(pid_u32).or_insert(0.0) = cpu_usage;
+    /// Children of every process from the last refresh, leaving out threads
+    fn children(&self) -> HashMap<Pid, Vec<Pid>> {
+        let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+        for (pid, process) in self.system.processes() {
+            if process.thread_kind().is_some() {
+                continue;
+            }
+            if let Some(parent) = process.parent() {
+                children.entry(parent).or_default().push(*pid);
             }
         }
+        children
+    }
 
-        Ok(())
+    /// Set the usage of a process and of its tree from the last refresh
+    ///
+    /// Returns `false` if the main process no longer exists.
+    fn apply_usage(
+        &mut self,
+        process: &mut ManagedProcess,
+        children: &HashMap<Pid, Vec<Pid>>,
+    ) -> bool {
+        let pid = process.stats.pid;
+        let Some(sys_process) = self.system.process(Pid::from_u32(pid)) else {
+            return false;
+        };
+
+        let cpu_usage = sys_process.cpu_usage();
+        process.stats.cpu_usage = cpu_usage;
+        process.stats.memory_usage = sys_process.memory();
+        *self.cpu_cache.entry(pid).or_insert(0.0) = cpu_usage;
+
+        let cgroup_usage = process
+            .cgroup_manager
+            .as_ref()
+            .and_then(|cgroup| self.cgroup_usage(pid, cgroup));
+        let (tree_cpu_usage, tree_memory_usage) =
+            cgroup_usage.unwrap_or_else(|| self.tree_usage(Pid::from_u32(pid), children));
+        process.stats.tree_cpu_usage = tree_cpu_usage;
+        process.stats.tree_memory_usage = tree_memory_usage;
+
+        true
+    }
+
+    /// CPU and memory usage of a cgroup, or `None` if its controllers are not enabled
+    ///
+    /// CPU usage is computed from the CPU time used since the previous reading, so
+    /// the first reading after a start is 0.
+    fn cgroup_usage(&mut self, pid: u32, cgroup: &CGroupManager) -> Option<(f32, u64)> {
+        let memory = cgroup.memory_current()?;
+        let sample = CgroupCpuSample {
+            usage_usec: cgroup.cpu_usage_usec()?,
+            at: Instant::now(),
+        };
+
+        let cpu = match self.cgroup_cpu_cache.insert(pid, sample) {
+            Some(previous) if sample.usage_usec >= previous.usage_usec => {
+                let elapsed = sample.at.duration_since(previous.at).as_micros();
+                if elapsed == 0 {
+                    0.0
+                } else {
+                    (sample.usage_usec - previous.usage_usec) as f32 / elapsed as f32 * 100.0
+                }
+            }
+            _ => 0.0,
+        };
+
+        Some((cpu, memory))
+    }
+
+    /// CPU and resident memory summed over a process and its descendants
+    fn tree_usage(&self, root: Pid, children: &HashMap<Pid, Vec<Pid>>) -> (f32, u64) {
+        let mut cpu = 0.0;
+        let mut memory = 0;
+        let mut pending = vec![root];
+
+        while let Some(pid) = pending.pop() {
+            if let Some(process) = self.system.process(pid) {
+                cpu += process.cpu_usage();
+                memory += process.memory();
+            }
+            if let Some(pids) = children.get(&pid) {
+                pending.extend(pids);
+            }
+        }
+
+        (cpu, memory)
     }
 
     /// Check if a process is still alive in the system (optimized)
@@ -192,7 +264,7 @@ impl ProcessMonitor {
         let mut running_processes: Vec<(&mut ManagedProcess, Pid)> = Vec::with_capacity(16);
         
         for process in processes {
-            if process.state == ProcessState::Running {
+            if process.state.is_active() {
                 let pid = process.stats.pid;
                 running_processes.push((process, Pid::from_u32(pid)));
             }
@@ -229,6 +301,7 @@ impl ProcessMonitor {
     /// * `pid` - Process ID to clear from cache
     pub fn clear_cache(&mut self, pid: u32) {
         self.cpu_cache.remove(&pid);
+        self.cgroup_cpu_cache.remove(&pid);
     }
 }
 
@@ -243,28 +316,18 @@ mod tests {
     use super::*;
     use crate::config::ProcessConfig;
     use crate::ipc::protocol::ProcessId;
-    use crate::process::ProcessStats;
-    use std::collections::HashMap;
+    use crate::process::{ProcessState, ProcessStats};
     use std::path::PathBuf;
+    use std::time::Duration;
     use tokio::process::Command;
 
     fn create_test_config(name: &str) -> ProcessConfig {
-        use crate::config::LimitAction;
         ProcessConfig {
             name: name.to_string(),
             script: PathBuf::from("/bin/sleep"),
             args: vec!["10".to_string()],
-            cwd: None,
-            env: HashMap::new(),
-            instances: 1,
-            autorestart: true,
-            max_restarts: 10,
-            restart_delay_secs: 1,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: LimitAction::Log,
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 2,
+            stop_timeout: Duration::from_secs(2),
+            ..Default::default()
         }
     }
 
@@ -319,15 +382,21 @@ mod tests {
             name: "test-monitor".to_string(),
             config: config.clone(),
             state: ProcessState::Running,
-            child,
+            child: Some(child),
+            overlapping_runs: Vec::new(),
+            next_run: None,
             stats: ProcessStats::new(pid),
             restart_policy: crate::process::RestartPolicy::from_config(
                 config.autorestart,
                 config.max_restarts,
-                config.restart_delay_secs,
+                config.restart_delay,
             ),
             restart_tracker: crate::process::RestartTracker::new(),
+            health_tracker: crate::process::HealthTracker::new(),
             cgroup_manager: None,
+            adopted_start_time: None,
+            history: Default::default(),
+            config_file: None,
         };
 
         // Update stats
@@ -339,7 +408,58 @@ mod tests {
         assert!(process.stats.memory_usage > 0);
 
         // Cleanup
-        let _ = process.child.kill().await;
+        let _ = process.child.as_mut().unwrap().kill().await;
+    }
+
+    #[tokio::test]
+    async fn test_update_process_stats_covers_tree() {
+        let mut monitor = ProcessMonitor::new();
+
+        let child = Command::new("/bin/sh")
+            .args(["-c", "sleep 10 & sleep 10 & wait"])
+            .process_group(0)
+            .spawn()
+            .expect("Failed to spawn process");
+        let pid = child.id().expect("Failed to get PID");
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+
+        let config = create_test_config("test-tree");
+        let mut process = ManagedProcess {
+            id: ProcessId::new(1),
+            name: "test-tree".to_string(),
+            config: config.clone(),
+            state: ProcessState::Running,
+            child: Some(child),
+            overlapping_runs: Vec::new(),
+            next_run: None,
+            stats: ProcessStats::new(pid),
+            restart_policy: crate::process::RestartPolicy::from_config(
+                config.autorestart,
+                config.max_restarts,
+                config.restart_delay,
+            ),
+            restart_tracker: crate::process::RestartTracker::new(),
+            health_tracker: crate::process::HealthTracker::new(),
+            cgroup_manager: None,
+            adopted_start_time: None,
+            history: Default::default(),
+            config_file: None,
+        };
+
+        let mut processes = [&mut process];
+        monitor
+            .update_all_stats(processes.iter_mut().map(|p| &mut **p))
+            .unwrap();
+
+        // The two sleeps count toward the tree, not toward the shell itself
+        assert!(process.stats.memory_usage > 0);
+        assert!(process.stats.tree_memory_usage > process.stats.memory_usage);
+
+        let _ = nix::sys::signal::killpg(
+            nix::unistd::Pid::from_raw(pid as i32),
+            nix::sys::signal::Signal::SIGKILL,
+        );
+        let _ = process.child.as_mut().unwrap().kill().await;
     }
 
     #[tokio::test]
@@ -361,23 +481,29 @@ mod tests {
             name: "test-crash".to_string(),
             config: config.clone(),
             state: ProcessState::Running,
-            child,
+            child: Some(child),
+            overlapping_runs: Vec::new(),
+            next_run: None,
             stats: ProcessStats::new(pid),
             restart_policy: crate::process::RestartPolicy::from_config(
                 config.autorestart,
                 config.max_restarts,
-                config.restart_delay_secs,
+                config.restart_delay,
             ),
             restart_tracker: crate::process::RestartTracker::new(),
+            health_tracker: crate::process::HealthTracker::new(),
             cgroup_manager: None,
+            adopted_start_time: None,
+            history: Default::default(),
+            config_file: None,
         };
 
         // Wait for process to exit
-        let _ = process.child.wait().await;
+        let _ = process.child.as_mut().unwrap().wait().await;
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
         // Detect crashes
-        let mut processes = vec![&mut process];
+        let mut processes = [&mut process];
         let crashed = monitor.detect_crashes(processes.iter_mut().map(|p| &mut **p));
 
         // Process should be detected as crashed
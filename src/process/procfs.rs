@@ -0,0 +1,212 @@
+// Process identity from /proc, used to adopt processes left running by an earlier daemon
+// and to find the descendants of a process when stopping it, plus the thread and file
+// descriptor counts recorded in the stats history
+
+use nix::unistd::{sysconf, SysconfVar};
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+/// Fields of `/proc/<pid>/stat` this module needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stat {
+    state: char,
+    ppid: u32,
+    pgrp: u32,
+    threads: u32,
+    /// Time the process started, in clock ticks after boot
+    start_time: u64,
+}
+
+/// Parse the contents of `/proc/<pid>/stat`
+///
+/// The command name in field 2 is wrapped in parentheses and may itself contain
+/// spaces or parentheses, so the remaining fields are read after the last `)`.
+fn parse_stat(contents: &str) -> Option<Stat> {
+    let rest = &contents[contents.rfind(')')? + 1..];
+    let mut fields = rest.split_whitespace();
+
+    // Fields after the command name start at field 3 (state); num_threads is field 20
+    // and starttime is field 22
+    let state = fields.next()?.chars().next()?;
+    let ppid = fields.next()?.parse().ok()?;
+    let pgrp = fields.next()?.parse().ok()?;
+    let threads = fields.nth(14)?.parse().ok()?;
+    let start_time = fields.nth(1)?.parse().ok()?;
+
+    Some(Stat {
+        state,
+        ppid,
+        pgrp,
+        threads,
+        start_time,
+    })
+}
+
+fn read_stat(pid: u32) -> Option<Stat> {
+    parse_stat(&fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+}
+
+/// Start time of a live process, in clock ticks after boot
+///
+/// Together with the PID this identifies a process even after the PID is reused.
+/// Returns `None` if the process does not exist or has already exited.
+pub fn start_time(pid: u32) -> Option<u64> {
+    read_stat(pid)
+        .filter(|stat| !matches!(stat.state, 'Z' | 'X'))
+        .map(|stat| stat.start_time)
+}
+
+/// Number of threads of a live process
+pub fn thread_count(pid: u32) -> Option<u32> {
+    read_stat(pid).map(|stat| stat.threads)
+}
+
+/// Number of open file descriptors of a process
+///
+/// Returns `None` if the process does not exist or belongs to another user.
+pub fn open_fds(pid: u32) -> Option<u32> {
+    let entries = fs::read_dir(format!("/proc/{}/fd", pid)).ok()?;
+    Some(entries.count() as u32)
+}
+
+/// Check whether `pid` still belongs to the process that started at `start_time`
+pub fn is_same_process(pid: u32, start_time: u64) -> bool {
+    self::start_time(pid) == Some(start_time)
+}
+
+/// A live process, identified by its PID and start time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProcessIdentity {
+    pub pid: u32,
+    pub start_time: u64,
+}
+
+impl ProcessIdentity {
+    /// Check whether the process is still running under the same PID
+    pub fn is_alive(&self) -> bool {
+        is_same_process(self.pid, self.start_time)
+    }
+}
+
+/// All live processes, with their parent and process group
+fn live_processes() -> Vec<(ProcessIdentity, Stat)> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| {
+            let stat = read_stat(pid).filter(|stat| !matches!(stat.state, 'Z' | 'X'))?;
+            let identity = ProcessIdentity {
+                pid,
+                start_time: stat.start_time,
+            };
+            Some((identity, stat))
+        })
+        .collect()
+}
+
+/// Live descendants of `pid`: its children, their children, and so on
+pub fn descendants(pid: u32) -> Vec<ProcessIdentity> {
+    let processes = live_processes();
+    let mut found = Vec::new();
+    let mut parents = vec![pid];
+
+    while let Some(parent) = parents.pop() {
+        for (identity, stat) in &processes {
+            if stat.ppid == parent && identity.pid != pid && !found.contains(identity) {
+                found.push(*identity);
+                parents.push(identity.pid);
+            }
+        }
+    }
+
+    found
+}
+
+/// Live members of the process group `pgid`
+pub fn group_members(pgid: u32) -> Vec<ProcessIdentity> {
+    live_processes()
+        .into_iter()
+        .filter(|(_, stat)| stat.pgrp == pgid)
+        .map(|(identity, _)| identity)
+        .collect()
+}
+
+/// Wall-clock time at which a process with the given start time was started
+pub fn started_at(start_time: u64) -> Option<SystemTime> {
+    let ticks_per_sec = sysconf(SysconfVar::CLK_TCK).ok()??;
+    let boot_time = fs::read_to_string("/proc/stat")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+
+    let since_boot = Duration::from_secs_f64(start_time as f64 / ticks_per_sec as f64);
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(boot_time) + since_boot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stat() {
+        let contents = "1234 (my (weird) app) S 1 1234 1234 0 -1 4194560 100 0 0 0 \
+                        5 3 0 0 20 0 1 0 987654 12345678 300 18446744073709551615";
+        assert_eq!(
+            parse_stat(contents),
+            Some(Stat {
+                state: 'S',
+                ppid: 1,
+                pgrp: 1234,
+                threads: 1,
+                start_time: 987654
+            })
+        );
+        assert_eq!(parse_stat("1234 (truncated"), None);
+    }
+
+    #[test]
+    fn test_start_time_of_own_process() {
+        let pid = std::process::id();
+        let start = start_time(pid).unwrap();
+
+        assert!(is_same_process(pid, start));
+        assert!(!is_same_process(pid, start + 1));
+
+        let started = started_at(start).unwrap();
+        assert!(started <= SystemTime::now());
+
+        assert!(thread_count(pid).unwrap() >= 1);
+        assert!(open_fds(pid).unwrap() >= 3);
+    }
+
+    #[test]
+    fn test_descendants_and_group_members() {
+        let mut child = std::process::Command::new("/bin/sh")
+            .args(["-c", "sleep 10 & wait"])
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        std::thread::sleep(Duration::from_millis(200));
+
+        let descendants = descendants(pid);
+        assert_eq!(descendants.len(), 1);
+        assert!(descendants[0].is_alive());
+
+        // The shell stays in our process group, as does its background child
+        let members = group_members(nix::unistd::getpgrp().as_raw() as u32);
+        assert!(members.iter().any(|p| p.pid == pid));
+        assert!(members.contains(&descendants[0]));
+
+        let _ = nix::sys::signal::kill(
+            nix::unistd::Pid::from_raw(descendants[0].pid as i32),
+            nix::sys::signal::Signal::SIGKILL,
+        );
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,316 @@
+use crate::config::{HealthProbe, ReadinessCheck, ReadinessConfig};
+use crate::error::{AdasaError, Result};
+use crate::process::health;
+use regex::Regex;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::net::UnixDatagram;
+use tokio::process::Child;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// Environment variable used to hand the notify socket to the child
+pub const NOTIFY_SOCKET_ENV: &str = "NOTIFY_SOCKET";
+
+/// Time between readiness probe attempts
+const PROBE_RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Upper bound for a single readiness probe attempt
+const PROBE_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+static NOTIFY_SOCKET_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Waits for a freshly spawned process to report that it is ready
+///
+/// A watch is prepared before the process is spawned so that nothing the
+/// process emits while starting up can be missed.
+pub struct ReadinessWatch {
+    signal: ReadySignal,
+    timeout: Duration,
+    line_tap: Option<UnboundedSender<String>>,
+}
+
+enum ReadySignal {
+    /// Retry a probe until it succeeds
+    Probe(HealthProbe),
+    /// Wait for an output line matching the pattern
+    Log {
+        pattern: Regex,
+        lines: UnboundedReceiver<String>,
+    },
+    /// Wait for `READY=1` on a datagram socket
+    Notify { socket: UnixDatagram, path: PathBuf },
+    /// No readiness check configured: the process only has to stay up
+    Delay(Duration),
+}
+
+impl ReadinessWatch {
+    /// Prepare a watch for the next spawn of a process
+    ///
+    /// # Arguments
+    /// * `config` - Readiness configuration of the process, if any
+    /// * `fallback_delay` - How long a process without a readiness check must stay up
+    pub fn prepare(config: Option<&ReadinessConfig>, fallback_delay: Duration) -> Result<Self> {
+        let Some(config) = config else {
+            return Ok(Self {
+                signal: ReadySignal::Delay(fallback_delay),
+                timeout: fallback_delay,
+                line_tap: None,
+            });
+        };
+
+        let mut line_tap = None;
+        let signal = match &config.check {
+            ReadinessCheck::Log { pattern } => {
+                let pattern = Regex::new(pattern).map_err(|e| {
+                    AdasaError::InvalidConfig(format!(
+                        "Invalid readiness pattern '{}': {}",
+                        pattern, e
+                    ))
+                })?;
+                let (tx, rx) = mpsc::unbounded_channel();
+                line_tap = Some(tx);
+                ReadySignal::Log { pattern, lines: rx }
+            }
+            ReadinessCheck::Notify => {
+                let path = std::env::temp_dir().join(format!(
+                    "adasa-notify-{}-{}.sock",
+                    std::process::id(),
+                    NOTIFY_SOCKET_COUNTER.fetch_add(1, Ordering::Relaxed)
+                ));
+                let _ = std::fs::remove_file(&path);
+                let socket = UnixDatagram::bind(&path).map_err(|e| {
+                    AdasaError::NotReady(format!(
+                        "failed to bind notify socket {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                ReadySignal::Notify { socket, path }
+            }
+            check => ReadySignal::Probe(
+                check
+                    .probe()
+                    .expect("http, tcp and exec readiness checks map to probes"),
+            ),
+        };
+
+        Ok(Self {
+            signal,
+            timeout: config.timeout,
+            line_tap,
+        })
+    }
+
+    /// Environment variable to add to the spawned process, if the watch needs one
+    pub fn spawn_env(&self) -> Option<(String, String)> {
+        match &self.signal {
+            ReadySignal::Notify { path, .. } => Some((
+                NOTIFY_SOCKET_ENV.to_string(),
+                path.to_string_lossy().into_owned(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Take the channel that should receive the process output lines, if the watch needs them
+    pub fn take_line_tap(&mut self) -> Option<UnboundedSender<String>> {
+        self.line_tap.take()
+    }
+
+    /// Wait until the process reports ready or the readiness timeout expires
+    ///
+    /// This only watches for the readiness signal. The caller is responsible
+    /// for noticing that the process exited in the meantime.
+    pub async fn wait(mut self) -> Result<()> {
+        self.line_tap = None;
+
+        if let ReadySignal::Delay(delay) = self.signal {
+            tokio::time::sleep(delay).await;
+            return Ok(());
+        }
+
+        let timeout = self.timeout;
+        match tokio::time::timeout(timeout, self.wait_for_signal()).await {
+            Ok(result) => result,
+            Err(_) => Err(AdasaError::NotReady(format!(
+                "no readiness signal within {:?}",
+                timeout
+            ))),
+        }
+    }
+
+    async fn wait_for_signal(&mut self) -> Result<()> {
+        match &mut self.signal {
+            ReadySignal::Probe(probe) => loop {
+                match tokio::time::timeout(PROBE_ATTEMPT_TIMEOUT, health::probe(probe)).await {
+                    Ok(Ok(())) => return Ok(()),
+                    Ok(Err(e)) => tracing::debug!("Readiness probe not passing yet: {}", e),
+                    Err(_) => tracing::debug!("Readiness probe attempt timed out"),
+                }
+                tokio::time::sleep(PROBE_RETRY_INTERVAL).await;
+            },
+            ReadySignal::Log { pattern, lines } => {
+                while let Some(line) = lines.recv().await {
+                    if pattern.is_match(line.trim_end()) {
+                        return Ok(());
+                    }
+                }
+                Err(AdasaError::NotReady(format!(
+                    "output closed before a line matched '{}'",
+                    pattern
+                )))
+            }
+            ReadySignal::Notify { socket, .. } => {
+                let mut buf = vec![0u8; 4096];
+                loop {
+                    let len = socket.recv(&mut buf).await.map_err(|e| {
+                        AdasaError::NotReady(format!("failed to read notify socket: {}", e))
+                    })?;
+                    let message = String::from_utf8_lossy(&buf[..len]);
+                    if message.lines().any(|line| line.trim() == "READY=1") {
+                        return Ok(());
+                    }
+                }
+            }
+            ReadySignal::Delay(_) => Ok(()),
+        }
+    }
+}
+
+impl Drop for ReadinessWatch {
+    fn drop(&mut self) {
+        if let ReadySignal::Notify { path, .. } = &self.signal {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Forward the output of a process to `line_tap` without writing it anywhere
+///
+/// Used when log capture is not configured but a readiness check needs to see
+/// the output. The pipes keep being drained after readiness so the process
+/// never blocks on a full pipe.
+pub fn forward_output(child: &mut Child, line_tap: UnboundedSender<String>) {
+    if let Some(stdout) = child.stdout.take() {
+        tokio::spawn(forward_lines(stdout, Some(line_tap.clone())));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        tokio::spawn(forward_lines(stderr, Some(line_tap)));
+    }
+}
+
+async fn forward_lines<R: AsyncRead + Unpin>(
+    reader: R,
+    mut line_tap: Option<UnboundedSender<String>>,
+) {
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+
+    while let Ok(n) = reader.read_line(&mut line).await {
+        if n == 0 {
+            break;
+        }
+        if let Some(ref tap) = line_tap {
+            if tap.send(line.clone()).is_err() {
+                line_tap = None;
+            }
+        }
+        line.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn readiness(check: ReadinessCheck, timeout_secs: u64) -> ReadinessConfig {
+        ReadinessConfig {
+            check,
+            timeout: Duration::from_secs(timeout_secs),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_delay() {
+        let watch = ReadinessWatch::prepare(None, Duration::from_millis(50)).unwrap();
+        assert!(watch.spawn_env().is_none());
+        assert!(watch.wait().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_exec_readiness() {
+        let config = readiness(
+            ReadinessCheck::Exec {
+                command: PathBuf::from("/bin/true"),
+                args: vec![],
+            },
+            2,
+        );
+        let watch = ReadinessWatch::prepare(Some(&config), Duration::ZERO).unwrap();
+        assert!(watch.wait().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_probe_readiness_times_out() {
+        let config = readiness(
+            ReadinessCheck::Exec {
+                command: PathBuf::from("/bin/false"),
+                args: vec![],
+            },
+            1,
+        );
+        let watch = ReadinessWatch::prepare(Some(&config), Duration::ZERO).unwrap();
+        let err = watch.wait().await.unwrap_err();
+        assert!(matches!(err, AdasaError::NotReady(_)));
+    }
+
+    #[tokio::test]
+    async fn test_log_readiness() {
+        let config = readiness(
+            ReadinessCheck::Log {
+                pattern: r"listening on port \d+".to_string(),
+            },
+            2,
+        );
+        let mut watch = ReadinessWatch::prepare(Some(&config), Duration::ZERO).unwrap();
+        let tap = watch.take_line_tap().unwrap();
+
+        tap.send("starting up\n".to_string()).unwrap();
+        tap.send("listening on port 8080\n".to_string()).unwrap();
+
+        assert!(watch.wait().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_log_readiness_output_closed() {
+        let config = readiness(
+            ReadinessCheck::Log {
+                pattern: "ready".to_string(),
+            },
+            2,
+        );
+        let mut watch = ReadinessWatch::prepare(Some(&config), Duration::ZERO).unwrap();
+        let tap = watch.take_line_tap().unwrap();
+        tap.send("still booting\n".to_string()).unwrap();
+        drop(tap);
+
+        assert!(watch.wait().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_notify_readiness() {
+        let config = readiness(ReadinessCheck::Notify, 2);
+        let watch = ReadinessWatch::prepare(Some(&config), Duration::ZERO).unwrap();
+        let (key, path) = watch.spawn_env().unwrap();
+        assert_eq!(key, NOTIFY_SOCKET_ENV);
+
+        let sender = std::os::unix::net::UnixDatagram::unbound().unwrap();
+        sender.send_to(b"STATUS=starting\n", &path).unwrap();
+        sender.send_to(b"STATUS=up\nREADY=1\n", &path).unwrap();
+
+        assert!(watch.wait().await.is_ok());
+        assert!(!std::path::Path::new(&path).exists());
+    }
+}
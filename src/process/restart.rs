@@ -1,18 +1,63 @@
+use crate::config::{BackoffConfig, ProcessConfig, RestartMode};
 use std::time::{Duration, SystemTime};
 
+/// How a process exited, as far as restarting it is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitKind {
+    /// Exited with code 0 or one of the configured success codes
+    Success,
+    /// Exited with any other code
+    Failure(i32),
+    /// Killed by a signal
+    Signal(i32),
+    /// The exit status could not be collected
+    Unknown,
+}
+
+impl ExitKind {
+    /// Check whether the exit counts as a failure
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, ExitKind::Success)
+    }
+}
+
+impl std::fmt::Display for ExitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExitKind::Success => write!(f, "exited successfully"),
+            ExitKind::Failure(code) => write!(f, "exited with code {}", code),
+            ExitKind::Signal(signal) => match nix::sys::signal::Signal::try_from(*signal) {
+                Ok(name) => write!(f, "killed by {}", name),
+                Err(_) => write!(f, "killed by signal {}", signal),
+            },
+            ExitKind::Unknown => write!(f, "exited with unknown status"),
+        }
+    }
+}
+
 /// Restart policy configuration
 #[derive(Debug, Clone)]
 pub struct RestartPolicy {
     /// Whether automatic restart is enabled
     pub enabled: bool,
+    /// Which exits lead to a restart
+    pub mode: RestartMode,
+    /// Exit codes besides 0 that count as a successful exit
+    pub success_exit_codes: Vec<i32>,
+    /// Exit codes after which the process is never restarted
+    pub no_restart_exit_codes: Vec<i32>,
     /// Maximum number of restarts within the time window
     pub max_restarts: usize,
-    /// Time window for counting restarts (in seconds)
-    pub time_window_secs: u64,
-    /// Initial delay before first restart (in seconds)
-    pub initial_delay_secs: u64,
+    /// Time window for counting restarts
+    pub time_window: Duration,
+    /// Initial delay before first restart
+    pub initial_delay: Duration,
     /// Backoff strategy to use
     pub backoff_strategy: BackoffStrategy,
+    /// Random spread applied to each delay, as a fraction of it
+    pub jitter: f64,
+    /// Runs at least this long reset the restart history
+    pub min_uptime: Duration,
 }
 
 impl RestartPolicy {
@@ -20,24 +65,98 @@ impl RestartPolicy {
     pub fn new() -> Self {
         Self {
             enabled: true,
+            mode: RestartMode::UnlessStopped,
+            success_exit_codes: Vec::new(),
+            no_restart_exit_codes: Vec::new(),
             max_restarts: 10,
-            time_window_secs: 60,
-            initial_delay_secs: 1,
-            backoff_strategy: BackoffStrategy::Exponential { max_delay_secs: 60 },
+            time_window: Duration::from_secs(60),
+            initial_delay: Duration::from_secs(1),
+            backoff_strategy: BackoffStrategy::Exponential {
+                max_delay: Duration::from_secs(60),
+            },
+            jitter: 0.0,
+            min_uptime: Duration::from_secs(1),
         }
     }
 
     /// Create a restart policy from configuration values
-    pub fn from_config(enabled: bool, max_restarts: usize, restart_delay_secs: u64) -> Self {
+    pub fn from_config(enabled: bool, max_restarts: usize, restart_delay: Duration) -> Self {
         Self {
             enabled,
+            mode: if enabled {
+                RestartMode::UnlessStopped
+            } else {
+                RestartMode::Never
+            },
+            success_exit_codes: Vec::new(),
+            no_restart_exit_codes: Vec::new(),
             max_restarts,
-            time_window_secs: 60,
-            initial_delay_secs: restart_delay_secs,
-            backoff_strategy: BackoffStrategy::Exponential { max_delay_secs: 60 },
+            time_window: Duration::from_secs(60),
+            initial_delay: restart_delay,
+            backoff_strategy: BackoffStrategy::Exponential {
+                max_delay: Duration::from_secs(60),
+            },
+            jitter: 0.0,
+            min_uptime: Duration::from_secs(1),
         }
     }
 
+    /// Create a restart policy from a process configuration
+    pub fn from_process_config(config: &ProcessConfig) -> Self {
+        let mode = config.restart_mode();
+
+        Self {
+            enabled: mode != RestartMode::Never,
+            mode,
+            success_exit_codes: config.success_exit_codes.clone(),
+            no_restart_exit_codes: config.no_restart_exit_codes.clone(),
+            max_restarts: config.max_restarts,
+            time_window: config.restart_window,
+            initial_delay: config.restart_delay,
+            backoff_strategy: BackoffStrategy::from(&config.backoff),
+            jitter: config.backoff.jitter(),
+            min_uptime: config.min_uptime,
+        }
+    }
+
+    /// Classify an exit from its exit code, or the signal that killed the process
+    pub fn classify_exit(&self, code: Option<i32>, signal: Option<i32>) -> ExitKind {
+        match (code, signal) {
+            (Some(code), _) if code == 0 || self.success_exit_codes.contains(&code) => {
+                ExitKind::Success
+            }
+            (Some(code), _) => ExitKind::Failure(code),
+            (None, Some(signal)) => ExitKind::Signal(signal),
+            (None, None) => ExitKind::Unknown,
+        }
+    }
+
+    /// Check whether the restart mode and exit codes call for a restart after this exit
+    ///
+    /// This does not look at the restart history; see [`RestartPolicy::should_restart`].
+    pub fn restarts_after(&self, exit: ExitKind) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        if let ExitKind::Failure(code) = exit {
+            if self.no_restart_exit_codes.contains(&code) {
+                return false;
+            }
+        }
+
+        match self.mode {
+            RestartMode::Always | RestartMode::UnlessStopped => true,
+            RestartMode::OnFailure => exit.is_failure(),
+            RestartMode::Never => false,
+        }
+    }
+
+    /// Check if a process that exited this way should be restarted now
+    pub fn should_restart_after(&self, exit: ExitKind, tracker: &RestartTracker) -> bool {
+        self.restarts_after(exit) && self.should_restart(tracker)
+    }
+
     /// Check if restart should be attempted based on restart history
     pub fn should_restart(&self, tracker: &RestartTracker) -> bool {
         if !self.enabled {
@@ -45,15 +164,31 @@ impl RestartPolicy {
         }
 
         // Check if we've exceeded max restarts in the time window
-        let recent_restarts = tracker.count_recent_restarts(self.time_window_secs);
+        let recent_restarts = tracker.count_recent_restarts(self.time_window);
         recent_restarts < self.max_restarts
     }
 
     /// Calculate the delay before the next restart attempt
+    ///
+    /// The backoff grows with the restarts within the time window, so it drops back
+    /// once earlier restarts fall out of the window.
     pub fn calculate_delay(&self, tracker: &RestartTracker) -> Duration {
-        let restart_count = tracker.restart_count();
-        self.backoff_strategy
-            .calculate_delay(self.initial_delay_secs, restart_count)
+        let restart_count = tracker.count_recent_restarts(self.time_window);
+        let delay = self
+            .backoff_strategy
+            .calculate_delay(self.initial_delay, restart_count);
+
+        if self.jitter > 0.0 {
+            // Spread the delay uniformly over [1 - jitter, 1 + jitter] of its value
+            delay.mul_f64(1.0 + self.jitter * (fastrand::f64() * 2.0 - 1.0))
+        } else {
+            delay
+        }
+    }
+
+    /// Minimum run time that resets the restart history
+    pub fn min_uptime(&self) -> Duration {
+        self.min_uptime
     }
 }
 
@@ -69,25 +204,35 @@ pub enum BackoffStrategy {
     /// Fixed delay between restarts
     Fixed,
     /// Exponential backoff with maximum delay
-    Exponential { max_delay_secs: u64 },
+    Exponential { max_delay: Duration },
 }
 
 impl BackoffStrategy {
     /// Calculate the delay for a given restart attempt
-    pub fn calculate_delay(&self, initial_delay_secs: u64, restart_count: usize) -> Duration {
+    pub fn calculate_delay(&self, initial_delay: Duration, restart_count: usize) -> Duration {
         match self {
-            BackoffStrategy::Fixed => Duration::from_secs(initial_delay_secs),
-            BackoffStrategy::Exponential { max_delay_secs } => {
+            BackoffStrategy::Fixed => initial_delay,
+            BackoffStrategy::Exponential { max_delay } => {
                 // Exponential backoff: delay = initial * 2^restart_count
-                let delay_secs = initial_delay_secs
-                    .saturating_mul(2_u64.saturating_pow(restart_count as u32))
-                    .min(*max_delay_secs);
-                Duration::from_secs(delay_secs)
+                initial_delay
+                    .saturating_mul(2_u32.saturating_pow(restart_count as u32))
+                    .min(*max_delay)
             }
         }
     }
 }
 
+impl From<&BackoffConfig> for BackoffStrategy {
+    fn from(config: &BackoffConfig) -> Self {
+        match config {
+            BackoffConfig::Fixed { .. } => BackoffStrategy::Fixed,
+            BackoffConfig::Exponential { max_delay, .. } => BackoffStrategy::Exponential {
+                max_delay: *max_delay,
+            },
+        }
+    }
+}
+
 /// Tracks restart history for a process
 #[derive(Debug, Clone)]
 pub struct RestartTracker {
@@ -113,10 +258,9 @@ impl RestartTracker {
         self.restart_times.len()
     }
 
-    /// Count restarts within the specified time window (in seconds)
-    pub fn count_recent_restarts(&self, window_secs: u64) -> usize {
+    /// Count restarts within the specified time window
+    pub fn count_recent_restarts(&self, window: Duration) -> usize {
         let now = SystemTime::now();
-        let window = Duration::from_secs(window_secs);
 
         self.restart_times
             .iter()
@@ -139,9 +283,8 @@ impl RestartTracker {
     }
 
     /// Remove restart records older than the specified window
-    pub fn prune_old_restarts(&mut self, window_secs: u64) {
+    pub fn prune_old_restarts(&mut self, window: Duration) {
         let now = SystemTime::now();
-        let window = Duration::from_secs(window_secs);
 
         self.restart_times.retain(|&time| {
             now.duration_since(time)
@@ -166,29 +309,31 @@ mod tests {
     fn test_restart_policy_new() {
         let policy = RestartPolicy::new();
         assert!(policy.enabled);
+        assert_eq!(policy.mode, RestartMode::UnlessStopped);
         assert_eq!(policy.max_restarts, 10);
-        assert_eq!(policy.time_window_secs, 60);
-        assert_eq!(policy.initial_delay_secs, 1);
+        assert_eq!(policy.time_window, Duration::from_secs(60));
+        assert_eq!(policy.initial_delay, Duration::from_secs(1));
     }
 
     #[test]
     fn test_restart_policy_from_config() {
-        let policy = RestartPolicy::from_config(true, 5, 2);
+        let policy = RestartPolicy::from_config(true, 5, Duration::from_secs(2));
         assert!(policy.enabled);
+        assert_eq!(policy.mode, ProcessConfig::default().restart_mode());
         assert_eq!(policy.max_restarts, 5);
-        assert_eq!(policy.initial_delay_secs, 2);
+        assert_eq!(policy.initial_delay, Duration::from_secs(2));
     }
 
     #[test]
     fn test_restart_policy_disabled() {
-        let policy = RestartPolicy::from_config(false, 10, 1);
+        let policy = RestartPolicy::from_config(false, 10, Duration::from_secs(1));
         let tracker = RestartTracker::new();
         assert!(!policy.should_restart(&tracker));
     }
 
     #[test]
     fn test_restart_policy_should_restart() {
-        let policy = RestartPolicy::from_config(true, 3, 1);
+        let policy = RestartPolicy::from_config(true, 3, Duration::from_secs(1));
         let mut tracker = RestartTracker::new();
 
         // Should allow restarts under the limit
@@ -205,30 +350,144 @@ mod tests {
         assert!(!policy.should_restart(&tracker));
     }
 
+    #[test]
+    fn test_classify_exit() {
+        let mut policy = RestartPolicy::new();
+        policy.success_exit_codes = vec![3];
+
+        assert_eq!(policy.classify_exit(Some(0), None), ExitKind::Success);
+        assert_eq!(policy.classify_exit(Some(3), None), ExitKind::Success);
+        assert_eq!(policy.classify_exit(Some(1), None), ExitKind::Failure(1));
+        assert_eq!(policy.classify_exit(None, Some(9)), ExitKind::Signal(9));
+        assert_eq!(policy.classify_exit(None, None), ExitKind::Unknown);
+
+        assert_eq!(ExitKind::Failure(1).to_string(), "exited with code 1");
+        assert_eq!(ExitKind::Signal(9).to_string(), "killed by SIGKILL");
+    }
+
+    #[test]
+    fn test_restart_modes() {
+        let mut policy = RestartPolicy::new();
+        let exits = [
+            ExitKind::Success,
+            ExitKind::Failure(1),
+            ExitKind::Signal(9),
+            ExitKind::Unknown,
+        ];
+
+        for mode in [RestartMode::Always, RestartMode::UnlessStopped] {
+            policy.mode = mode;
+            assert!(exits.iter().all(|exit| policy.restarts_after(*exit)));
+        }
+
+        policy.mode = RestartMode::OnFailure;
+        assert!(!policy.restarts_after(ExitKind::Success));
+        assert!(policy.restarts_after(ExitKind::Failure(1)));
+        assert!(policy.restarts_after(ExitKind::Signal(9)));
+        assert!(policy.restarts_after(ExitKind::Unknown));
+
+        policy.mode = RestartMode::Never;
+        assert!(exits.iter().all(|exit| !policy.restarts_after(*exit)));
+    }
+
+    #[test]
+    fn test_no_restart_exit_codes() {
+        let mut policy = RestartPolicy::new();
+        policy.no_restart_exit_codes = vec![78];
+
+        assert!(!policy.restarts_after(ExitKind::Failure(78)));
+        assert!(policy.restarts_after(ExitKind::Failure(1)));
+
+        let tracker = RestartTracker::new();
+        assert!(!policy.should_restart_after(ExitKind::Failure(78), &tracker));
+        assert!(policy.should_restart_after(ExitKind::Failure(1), &tracker));
+    }
+
+    #[test]
+    fn test_restart_policy_from_process_config() {
+        let config: ProcessConfig = toml::from_str(
+            r#"
+            name = "worker"
+            script = "/bin/true"
+            restart_delay_secs = 2
+            min_uptime_secs = 30
+            restart_window_secs = 300
+            backoff = { type = "fixed", jitter = 0.5 }
+        "#,
+        )
+        .unwrap();
+
+        let policy = RestartPolicy::from_process_config(&config);
+        assert_eq!(policy.mode, RestartMode::UnlessStopped);
+        assert_eq!(policy.time_window, Duration::from_secs(300));
+        assert_eq!(policy.min_uptime(), Duration::from_secs(30));
+        assert_eq!(policy.backoff_strategy, BackoffStrategy::Fixed);
+
+        // Jitter keeps the delay within 50% of the 2 second base
+        let tracker = RestartTracker::new();
+        for _ in 0..20 {
+            let delay = policy.calculate_delay(&tracker);
+            assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(3));
+        }
+    }
+
     #[test]
     fn test_backoff_fixed() {
         let strategy = BackoffStrategy::Fixed;
-        assert_eq!(strategy.calculate_delay(5, 0), Duration::from_secs(5));
-        assert_eq!(strategy.calculate_delay(5, 1), Duration::from_secs(5));
-        assert_eq!(strategy.calculate_delay(5, 10), Duration::from_secs(5));
+        assert_eq!(
+            strategy.calculate_delay(Duration::from_secs(5), 0),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            strategy.calculate_delay(Duration::from_secs(5), 1),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            strategy.calculate_delay(Duration::from_secs(5), 10),
+            Duration::from_secs(5)
+        );
     }
 
     #[test]
     fn test_backoff_exponential() {
-        let strategy = BackoffStrategy::Exponential { max_delay_secs: 60 };
+        let strategy = BackoffStrategy::Exponential {
+            max_delay: Duration::from_secs(60),
+        };
 
         // 1 * 2^0 = 1
-        assert_eq!(strategy.calculate_delay(1, 0), Duration::from_secs(1));
+        assert_eq!(
+            strategy.calculate_delay(Duration::from_secs(1), 0),
+            Duration::from_secs(1)
+        );
         // 1 * 2^1 = 2
-        assert_eq!(strategy.calculate_delay(1, 1), Duration::from_secs(2));
+        assert_eq!(
+            strategy.calculate_delay(Duration::from_secs(1), 1),
+            Duration::from_secs(2)
+        );
         // 1 * 2^2 = 4
-        assert_eq!(strategy.calculate_delay(1, 2), Duration::from_secs(4));
+        assert_eq!(
+            strategy.calculate_delay(Duration::from_secs(1), 2),
+            Duration::from_secs(4)
+        );
         // 1 * 2^3 = 8
-        assert_eq!(strategy.calculate_delay(1, 3), Duration::from_secs(8));
+        assert_eq!(
+            strategy.calculate_delay(Duration::from_secs(1), 3),
+            Duration::from_secs(8)
+        );
         // 1 * 2^6 = 64, but capped at 60
-        assert_eq!(strategy.calculate_delay(1, 6), Duration::from_secs(60));
+        assert_eq!(
+            strategy.calculate_delay(Duration::from_secs(1), 6),
+            Duration::from_secs(60)
+        );
         // 1 * 2^10 = 1024, but capped at 60
-        assert_eq!(strategy.calculate_delay(1, 10), Duration::from_secs(60));
+        assert_eq!(
+            strategy.calculate_delay(Duration::from_secs(1), 10),
+            Duration::from_secs(60)
+        );
+
+        // Sub-second delays keep their precision
+        let initial = Duration::from_millis(250);
+        assert_eq!(strategy.calculate_delay(initial, 2), Duration::from_secs(1));
     }
 
     #[test]
@@ -261,10 +520,10 @@ mod tests {
         tracker.record_restart();
 
         // All restarts should be within 1 second
-        assert_eq!(tracker.count_recent_restarts(1), 3);
+        assert_eq!(tracker.count_recent_restarts(Duration::from_secs(1)), 3);
 
         // All restarts should be within 10 seconds
-        assert_eq!(tracker.count_recent_restarts(10), 3);
+        assert_eq!(tracker.count_recent_restarts(Duration::from_secs(10)), 3);
     }
 
     #[test]
@@ -290,13 +549,13 @@ mod tests {
         tracker.record_restart();
 
         // Prune with a very short window (should remove old ones)
-        tracker.prune_old_restarts(0);
+        tracker.prune_old_restarts(Duration::ZERO);
         assert_eq!(tracker.restart_count(), 0);
     }
 
     #[test]
     fn test_calculate_delay_integration() {
-        let policy = RestartPolicy::from_config(true, 10, 1);
+        let policy = RestartPolicy::from_config(true, 10, Duration::from_secs(1));
         let mut tracker = RestartTracker::new();
 
         // First restart: 1 second
@@ -310,4 +569,20 @@ mod tests {
         // Third restart: 4 seconds
         assert_eq!(policy.calculate_delay(&tracker), Duration::from_secs(4));
     }
+
+    #[test]
+    fn test_calculate_delay_drops_after_window() {
+        let mut policy = RestartPolicy::from_config(true, 10, Duration::from_secs(1));
+        policy.time_window = Duration::from_millis(100);
+        let mut tracker = RestartTracker::new();
+
+        tracker.record_restart();
+        tracker.record_restart();
+        assert_eq!(policy.calculate_delay(&tracker), Duration::from_secs(4));
+
+        // The restarts left the window, but the history was not reset
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(tracker.restart_count(), 2);
+        assert_eq!(policy.calculate_delay(&tracker), Duration::from_secs(1));
+    }
 }
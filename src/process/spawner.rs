@@ -1,5 +1,11 @@
-use crate::config::ProcessConfig;
+use crate::config::{Credentials, ProcessConfig};
 use crate::error::{AdasaError, Result};
+use crate::process::limits::{cgroup::CGroupManager, ResourceLimits};
+use nix::libc;
+use nix::unistd::{setgid, setgroups, setuid, Gid, Uid};
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::unix::ffi::OsStringExt;
 use std::process::Stdio;
 use tokio::process::{Child, Command};
 
@@ -14,6 +20,9 @@ pub struct SpawnedProcess {
 
     /// Process name from configuration
     pub name: String,
+
+    /// UID and GID the process runs as, if `user` or `group` is configured
+    pub owner: Option<(u32, u32)>,
 }
 
 /// Spawn a process based on the provided configuration
@@ -24,6 +33,9 @@ pub struct SpawnedProcess {
 /// - Environment variables
 /// - Command-line arguments
 /// - Stdout/stderr pipe capture
+/// - A new process group led by the process
+/// - Resource limits, nice level, I/O priority and CPU affinity
+/// - User, group and supplementary groups
 ///
 /// # Arguments
 /// * `config` - Process configuration containing all spawn settings
@@ -32,6 +44,22 @@ pub struct SpawnedProcess {
 /// * `Ok(SpawnedProcess)` - Successfully spawned process with metadata
 /// * `Err(AdasaError)` - Failed to spawn process
 pub async fn spawn_process(config: &ProcessConfig) -> Result<SpawnedProcess> {
+    spawn_process_in(config, None).await
+}
+
+/// Spawn a process inside a cgroup
+///
+/// The child moves itself into the cgroup before exec, so the cgroup limits apply
+/// from its first instruction and cover everything it starts. The cgroup must
+/// already be set up; if it does not exist, the process is spawned without it.
+///
+/// # Arguments
+/// * `config` - Process configuration containing all spawn settings
+/// * `cgroup` - Cgroup to start the process in, if any
+pub async fn spawn_process_in(
+    config: &ProcessConfig,
+    cgroup: Option<&CGroupManager>,
+) -> Result<SpawnedProcess> {
     // Validate that the script exists and is executable
     if !config.script.exists() {
         return Err(AdasaError::SpawnError(format!(
@@ -64,6 +92,36 @@ pub async fn spawn_process(config: &ProcessConfig) -> Result<SpawnedProcess> {
     command.stdout(Stdio::piped());
     command.stderr(Stdio::piped());
 
+    // Run the process in its own process group, so it and its descendants can be
+    // signalled together without reaching the daemon
+    command.process_group(0);
+
+    // The cgroup is joined first, while the child still has the privileges to write to it.
+    // A cgroup that could not be created is skipped; its setup already reported why.
+    if let Some(cgroup) = cgroup.filter(|cgroup| cgroup.exists()) {
+        let procs_file = CString::new(cgroup.procs_file().into_os_string().into_vec())
+            .map_err(|e| AdasaError::SpawnError(format!("Invalid cgroup path: {}", e)))?;
+        // SAFETY: join_cgroup only makes async-signal-safe system calls
+        unsafe {
+            command.pre_exec(move || join_cgroup(&procs_file));
+        }
+    }
+
+    // Limits are applied first, while the child may still have the privileges to raise them
+    let limits = ResourceLimits::from_config(config);
+    if limits.has_spawn_limits() {
+        // SAFETY: apply_before_exec only makes async-signal-safe system calls
+        unsafe {
+            command.pre_exec(move || Ok(limits.apply_before_exec()?));
+        }
+    }
+
+    let credentials = config.credentials()?;
+    let owner = credentials.as_ref().map(|c| (c.uid, c.gid));
+    if let Some(credentials) = credentials {
+        apply_credentials(&mut command, config, credentials)?;
+    }
+
     // Spawn the process
     let child = command.spawn().map_err(|e| {
         AdasaError::SpawnError(format!("Failed to spawn process '{}': {}", config.name, e))
@@ -78,33 +136,92 @@ pub async fn spawn_process(config: &ProcessConfig) -> Result<SpawnedProcess> {
         child,
         pid,
         name: config.name.clone(),
+        owner,
     })
 }
 
+/// Move the calling process into the cgroup whose `cgroup.procs` file is `procs_file`
+///
+/// Runs between fork and exec, so it does not allocate.
+fn join_cgroup(procs_file: &CStr) -> io::Result<()> {
+    // SAFETY: `procs_file` is a valid C string and the descriptor is closed before returning
+    unsafe {
+        let fd = libc::open(procs_file.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // Writing 0 moves the writing process itself
+        let written = libc::write(fd, b"0".as_ptr().cast(), 1);
+        let result = if written < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        };
+        libc::close(fd);
+        result
+    }
+}
+
+/// Switch the child to the configured user and groups before exec
+fn apply_credentials(
+    command: &mut Command,
+    config: &ProcessConfig,
+    credentials: Credentials,
+) -> Result<()> {
+    let is_root = Uid::effective().is_root();
+    if !is_root && !config.supplementary_groups.is_empty() {
+        return Err(AdasaError::SpawnError(format!(
+            "Process '{}' sets supplementary_groups, which requires the daemon to run as root",
+            config.name
+        )));
+    }
+
+    // Give the process the environment a login as that user would have
+    if let Some((ref name, ref home)) = credentials.account {
+        for (key, value) in [("USER", name.as_str()), ("LOGNAME", name.as_str())] {
+            if !config.env.contains_key(key) {
+                command.env(key, value);
+            }
+        }
+        if !config.env.contains_key("HOME") {
+            command.env("HOME", home);
+        }
+    }
+
+    let uid = Uid::from_raw(credentials.uid);
+    let gid = Gid::from_raw(credentials.gid);
+    // Only root can change its supplementary groups, and it has to do so before dropping privileges
+    let groups: Option<Vec<Gid>> = credentials
+        .groups
+        .filter(|_| is_root)
+        .map(|groups| groups.into_iter().map(Gid::from_raw).collect());
+
+    // SAFETY: the closure only makes async-signal-safe system calls and does not allocate
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(ref groups) = groups {
+                setgroups(groups)?;
+            }
+            setgid(gid)?;
+            setuid(uid)?;
+            Ok(())
+        });
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
     use std::path::PathBuf;
     use tempfile::TempDir;
 
     fn create_test_config(name: &str, script: PathBuf) -> ProcessConfig {
-        use crate::config::LimitAction;
         ProcessConfig {
             name: name.to_string(),
             script,
-            args: vec![],
-            cwd: None,
-            env: HashMap::new(),
-            instances: 1,
-            autorestart: true,
-            max_restarts: 10,
-            restart_delay_secs: 1,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: LimitAction::Log,
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 10,
+            ..Default::default()
         }
     }
 
@@ -198,4 +315,95 @@ mod tests {
             _ => panic!("Expected SpawnError"),
         }
     }
+
+    #[tokio::test]
+    async fn test_spawn_as_user() {
+        if !Uid::effective().is_root() {
+            return;
+        }
+
+        let mut config = create_test_config("test-user", PathBuf::from("/bin/sh"));
+        config.args = vec![
+            "-c".to_string(),
+            "echo $(id -u) $(id -g) $(id -G)".to_string(),
+        ];
+        config.user = Some("65534".to_string());
+        config.group = Some("65534".to_string());
+        config.supplementary_groups = vec!["65534".to_string()];
+
+        let spawned = spawn_process(&config).await.unwrap();
+        assert_eq!(spawned.owner, Some((65534, 65534)));
+
+        let output = spawned.child.wait_with_output().await.unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            "65534 65534 65534"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_spawn_with_limits_and_scheduling() {
+        use crate::config::{IoniceClass, IoniceConfig, RlimitAmount, RlimitValue};
+
+        let mut config = create_test_config("test-limits", PathBuf::from("/bin/sh"));
+        config.args = vec![
+            "-c".to_string(),
+            "ulimit -Sn; ulimit -Hn; ulimit -c; nice; ionice; grep Cpus_allowed_list /proc/self/status"
+                .to_string(),
+        ];
+        config.limits.nofile = Some(RlimitValue::Split {
+            soft: RlimitAmount(1024),
+            hard: RlimitAmount(2048),
+        });
+        config.limits.core = Some(RlimitValue::Both(RlimitAmount(0)));
+        config.nice = Some(5);
+        config.ionice = Some(IoniceConfig {
+            class: IoniceClass::Idle,
+            level: 4,
+        });
+        config.cpu_affinity = vec![0];
+
+        let spawned = spawn_process(&config).await.unwrap();
+        let output = spawned.child.wait_with_output().await.unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines: Vec<&str> = stdout.lines().collect();
+
+        assert_eq!(&lines[..5], ["1024", "2048", "0", "5", "idle"]);
+        assert!(lines[5].ends_with("\t0"), "{}", lines[5]);
+    }
+
+    #[tokio::test]
+    async fn test_spawn_in_cgroup() {
+        // A directory with a `cgroup.controllers` file stands in for the cgroup v2 hierarchy
+        let hierarchy = TempDir::new().unwrap();
+        std::fs::write(hierarchy.path().join("cgroup.controllers"), "").unwrap();
+        let cgroup = CGroupManager::with_root(hierarchy.path(), "test-cgroup".to_string());
+
+        // Without the cgroup directory the process starts outside of it
+        let config = create_test_config("test-cgroup", PathBuf::from("/bin/true"));
+        let spawned = spawn_process_in(&config, Some(&cgroup)).await.unwrap();
+        assert!(spawned
+            .child
+            .wait_with_output()
+            .await
+            .unwrap()
+            .status
+            .success());
+        assert!(!cgroup.procs_file().exists());
+
+        // cgroupfs creates `cgroup.procs` along with the cgroup
+        cgroup.setup().unwrap();
+        std::fs::write(cgroup.procs_file(), "").unwrap();
+        let spawned = spawn_process_in(&config, Some(&cgroup)).await.unwrap();
+        assert!(spawned
+            .child
+            .wait_with_output()
+            .await
+            .unwrap()
+            .status
+            .success());
+
+        // The child wrote "0" to move itself into the cgroup before exec
+        assert_eq!(std::fs::read_to_string(cgroup.procs_file()).unwrap(), "0");
+    }
 }
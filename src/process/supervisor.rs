@@ -107,6 +107,30 @@ impl ProcessSupervisor {
             }
         }
 
+        // Respawn processes that waited out their backoff delay
+        let restarted = manager.run_due_restarts().await;
+        if !restarted.is_empty() {
+            debug!("Restarted {} process(es)", restarted.len());
+        }
+
+        // Start scheduled jobs that are due
+        let started = manager.run_due_jobs().await;
+        if !started.is_empty() {
+            debug!("Started {} scheduled job(s)", started.len());
+        }
+
+        // Run active health checks (catches processes that hang without exiting)
+        let unhealthy_ids = manager.run_health_checks().await;
+
+        for process_id in unhealthy_ids {
+            if let Err(e) = self.handle_unhealthy(manager, process_id).await {
+                error!(
+                    "Failed to handle failed health check for process {}: {}",
+                    process_id, e
+                );
+            }
+        }
+
         // Update process statistics (for processes that are still running)
         manager.update_stats()?;
 
@@ -156,12 +180,22 @@ impl ProcessSupervisor {
 
         let process_name = process.name.clone();
         let restart_count = process.restart_tracker.restart_count();
+        let exit = process.last_exit();
 
         info!(
-            "Process '{}' (id: {}) crashed (restart count: {})",
-            process_name, process_id, restart_count
+            "Process '{}' (id: {}) {} (restart count: {})",
+            process_name, process_id, exit, restart_count
         );
 
+        // Check if the restart mode and exit codes call for a restart at all
+        if !process.restart_policy.restarts_after(exit) {
+            info!(
+                "Process '{}' (id: {}) not restarted (restart mode: {})",
+                process_name, process_id, process.restart_policy.mode
+            );
+            return Ok(());
+        }
+
         // Check if restart should be attempted
         let (_, should_restart) = manager
             .get_restart_info(process_id)
@@ -172,6 +206,7 @@ impl ProcessSupervisor {
                 "Process '{}' (id: {}) has exceeded restart limit, not restarting",
                 process_name, process_id
             );
+            manager.mark_crash_loop(process_id);
             return Err(AdasaError::RestartLimitExceeded(process_name));
         }
 
@@ -183,11 +218,11 @@ impl ProcessSupervisor {
             process_name, process_id
         );
 
-        // Attempt restart (try_auto_restart handles backoff delay internally)
-        match manager.try_auto_restart(process_id).await {
+        // The restart is carried out by run_due_restarts once the backoff delay has passed
+        match manager.try_auto_restart(process_id) {
             Ok(true) => {
                 info!(
-                    "Successfully restarted process '{}' (id: {})",
+                    "Scheduled restart of process '{}' (id: {})",
                     process_name, process_id
                 );
                 Ok(())
@@ -211,6 +246,70 @@ impl ProcessSupervisor {
         }
     }
 
+    /// Handle a process that failed its health check
+    ///
+    /// This stops the process, which is restarted on a later check once it has exited.
+    /// If the policy does not allow a restart, the process is left running in the
+    /// unhealthy state.
+    ///
+    /// # Arguments
+    /// * `manager` - Process manager
+    /// * `process_id` - ID of the unhealthy process
+    ///
+    /// # Returns
+    /// * `Ok(())` - Unhealthy process handled successfully
+    /// * `Err(AdasaError)` - Error handling the unhealthy process
+    async fn handle_unhealthy(
+        &mut self,
+        manager: &mut ProcessManager,
+        process_id: ProcessId,
+    ) -> Result<()> {
+        if self.restarting.contains(&process_id) {
+            debug!("Process {} is already being restarted", process_id);
+            return Ok(());
+        }
+
+        let process = manager
+            .get_status(process_id)
+            .ok_or_else(|| AdasaError::ProcessNotFound(process_id.to_string()))?;
+
+        let process_name = process.name.clone();
+
+        info!(
+            "Process '{}' (id: {}) is unhealthy: {}",
+            process_name,
+            process_id,
+            process
+                .health_tracker
+                .last_error()
+                .unwrap_or("unknown error")
+        );
+
+        self.restarting.insert(process_id);
+
+        match manager.restart_unhealthy(process_id) {
+            Ok(true) => {
+                info!(
+                    "Stopping unhealthy process '{}' (id: {}) for a restart",
+                    process_name, process_id
+                );
+                Ok(())
+            }
+            Ok(false) => {
+                warn!(
+                    "Unhealthy process '{}' (id: {}) has exceeded restart limit, not restarting",
+                    process_name, process_id
+                );
+                self.restarting.remove(&process_id);
+                Ok(())
+            }
+            Err(e) => {
+                self.restarting.remove(&process_id);
+                Err(e)
+            }
+        }
+    }
+
     /// Clean up processes that are no longer in restarting state
     ///
     /// This removes process IDs from the restarting set if they are now running
@@ -272,26 +371,19 @@ impl ProcessSupervisor {
 mod tests {
     use super::*;
     use crate::config::ProcessConfig;
-    use std::collections::HashMap;
     use std::path::PathBuf;
 
     fn create_test_config(name: &str, autorestart: bool, max_restarts: usize) -> ProcessConfig {
-        use crate::config::LimitAction;
         ProcessConfig {
             name: name.to_string(),
             script: PathBuf::from("/bin/sh"),
             args: vec!["-c".to_string(), "exit 1".to_string()],
-            cwd: None,
-            env: HashMap::new(),
             instances: 1,
             autorestart,
             max_restarts,
-            restart_delay_secs: 0, // No delay for faster tests
-            max_memory: None,
-            max_cpu: None,
-            limit_action: LimitAction::Log,
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 2,
+            restart_delay: Duration::from_secs(0), // No delay for faster tests
+            stop_timeout: Duration::from_secs(2),
+            ..Default::default()
         }
     }
 
@@ -319,17 +411,9 @@ mod tests {
             name: "crash-test".to_string(),
             script: PathBuf::from("/bin/sleep"),
             args: vec!["10".to_string()],
-            cwd: None,
-            env: HashMap::new(),
-            instances: 1,
-            autorestart: true,
-            max_restarts: 10,
-            restart_delay_secs: 0,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: crate::config::LimitAction::Log,
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 2,
+            restart_delay: Duration::from_secs(0),
+            stop_timeout: Duration::from_secs(2),
+            ..Default::default()
         };
         let id = manager.spawn(config).await.unwrap();
 
@@ -339,8 +423,8 @@ mod tests {
 
         // Kill the process to simulate a crash
         let process = manager.get_mut(id).unwrap();
-        let _ = process.child.kill().await;
-        let _ = process.child.wait().await;
+        let _ = process.child.as_mut().unwrap().kill().await;
+        let _ = process.child.as_mut().unwrap().wait().await;
 
         // Wait for system to update
         tokio::time::sleep(Duration::from_millis(300)).await;
@@ -372,17 +456,10 @@ mod tests {
             name: "limited-restart".to_string(),
             script: PathBuf::from("/bin/sleep"),
             args: vec!["10".to_string()],
-            cwd: None,
-            env: HashMap::new(),
-            instances: 1,
-            autorestart: true,
             max_restarts: 2,
-            restart_delay_secs: 0,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: crate::config::LimitAction::Log,
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 2,
+            restart_delay: Duration::from_secs(0),
+            stop_timeout: Duration::from_secs(2),
+            ..Default::default()
         };
         let id = manager.spawn(config).await.unwrap();
 
@@ -390,8 +467,8 @@ mod tests {
         for i in 0..3 {
             // Kill the process to simulate a crash
             if let Some(process) = manager.get_mut(id) {
-                let _ = process.child.kill().await;
-                let _ = process.child.wait().await;
+                let _ = process.child.as_mut().unwrap().kill().await;
+                let _ = process.child.as_mut().unwrap().wait().await;
             }
 
             // Wait for system to update
@@ -410,13 +487,13 @@ mod tests {
                 // The health check itself succeeds, but the restart is not attempted
                 assert!(result.is_ok());
                 let process = manager.get_status(id).unwrap();
-                assert_eq!(process.state, ProcessState::Errored);
+                assert_eq!(process.state, ProcessState::CrashLoop);
             }
         }
 
-        // After exceeding limit, process should remain in errored state
+        // After exceeding limit, process should remain in the crash-loop state
         let process = manager.get_status(id).unwrap();
-        assert_eq!(process.state, ProcessState::Errored);
+        assert_eq!(process.state, ProcessState::CrashLoop);
     }
 
     #[tokio::test]
@@ -430,7 +507,7 @@ mod tests {
 
         // Get the process and wait for it to exit
         let process = manager.get_mut(id).unwrap();
-        let _ = process.child.wait().await;
+        let _ = process.child.as_mut().unwrap().wait().await;
 
         // Wait for system to update
         tokio::time::sleep(Duration::from_millis(100)).await;
@@ -457,7 +534,7 @@ mod tests {
 
         // Get the process and wait for it to exit
         let process = manager.get_mut(id).unwrap();
-        let _ = process.child.wait().await;
+        let _ = process.child.as_mut().unwrap().wait().await;
 
         // Wait for system to update
         tokio::time::sleep(Duration::from_millis(100)).await;
@@ -469,6 +546,50 @@ mod tests {
         assert!(!supervisor.is_restarting(id));
     }
 
+    #[tokio::test]
+    async fn test_supervisor_restarts_unhealthy_process() {
+        use crate::config::{HealthCheckConfig, HealthProbe};
+
+        let mut manager = ProcessManager::new();
+        let mut supervisor = ProcessSupervisor::with_defaults();
+
+        // A long-running process whose health check always fails
+        let mut config = create_test_config("hung-process", true, 10);
+        config.script = PathBuf::from("/bin/sleep");
+        config.args = vec!["10".to_string()];
+        config.health_check = Some(HealthCheckConfig {
+            probe: HealthProbe::Exec {
+                command: PathBuf::from("/bin/false"),
+                args: vec![],
+            },
+            interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(1),
+            failure_threshold: 1,
+        });
+        let id = manager.spawn(config).await.unwrap();
+        let old_pid = manager.get_status(id).unwrap().stats.pid;
+
+        // Wait until the first probe is due
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        // The first check stops the process, the next one respawns it once it has exited
+        let result = supervisor.trigger_check(&mut manager).await;
+        assert!(result.is_ok());
+        assert!(supervisor.is_restarting(id));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let result = supervisor.trigger_check(&mut manager).await;
+        assert!(result.is_ok());
+        assert!(!supervisor.is_restarting(id));
+
+        let process = manager.get_status(id).unwrap();
+        assert_eq!(process.state, ProcessState::Running);
+        assert_eq!(process.stats.restarts, 1);
+        assert_ne!(process.stats.pid, old_pid);
+
+        let _ = manager.stop(id, true).await;
+    }
+
     #[tokio::test]
     async fn test_supervisor_cleanup_restarting() {
         let supervisor = ProcessSupervisor::with_defaults();
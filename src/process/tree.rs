@@ -0,0 +1,94 @@
+// The set of processes that is stopped together with a managed process
+
+use crate::config::KillMode;
+use crate::process::limits::cgroup::CGroupManager;
+use crate::process::procfs::{self, ProcessIdentity};
+use nix::errno::Errno;
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+
+/// A managed process and the processes it started
+///
+/// Descendants are recorded when the tree is captured, before anything is
+/// signalled, so a descendant that left the process group or was reparented
+/// after its parent exited is still found.
+pub struct ProcessTree<'a> {
+    pid: u32,
+    mode: KillMode,
+    descendants: Vec<ProcessIdentity>,
+    cgroup: Option<&'a CGroupManager>,
+}
+
+impl<'a> ProcessTree<'a> {
+    /// Record the tree below `pid`
+    ///
+    /// # Arguments
+    /// * `pid` - PID of the main process, which leads its own process group
+    /// * `mode` - Which processes are signalled together with the main process
+    /// * `cgroup` - Cgroup of the process, only used with [`KillMode::Cgroup`]
+    pub fn capture(pid: u32, mode: KillMode, cgroup: Option<&'a CGroupManager>) -> Self {
+        Self {
+            pid,
+            mode,
+            descendants: procfs::descendants(pid),
+            cgroup: cgroup.filter(|_| mode == KillMode::Cgroup),
+        }
+    }
+
+    pub fn mode(&self) -> KillMode {
+        self.mode
+    }
+
+    /// Send `signal` to the main process and, unless the kill mode is `process`, to the rest of the tree
+    ///
+    /// Each process receives the signal once. Only a failure to signal the main
+    /// process is reported.
+    pub fn signal(&self, signal: Signal) -> nix::Result<()> {
+        let main = Pid::from_raw(self.pid as i32);
+        if self.mode == KillMode::Process {
+            return signal::kill(main, signal);
+        }
+
+        // A process adopted from a daemon that did not give it its own group has no group to signal
+        let group: Vec<u32> = procfs::group_members(self.pid)
+            .iter()
+            .map(|p| p.pid)
+            .collect();
+        let result = match signal::killpg(main, signal) {
+            Err(Errno::ESRCH) => signal::kill(main, signal),
+            result => result,
+        };
+
+        for pid in self.survivors() {
+            if !group.contains(&pid) {
+                let _ = signal::kill(Pid::from_raw(pid as i32), signal);
+            }
+        }
+
+        result
+    }
+
+    /// Processes of the tree, other than the main process, that are still running
+    ///
+    /// With [`KillMode::Process`] these are only the recorded descendants.
+    pub fn survivors(&self) -> Vec<u32> {
+        let mut pids: Vec<u32> = self
+            .descendants
+            .iter()
+            .filter(|p| p.is_alive())
+            .map(|p| p.pid)
+            .collect();
+
+        if self.mode != KillMode::Process {
+            pids.extend(procfs::group_members(self.pid).iter().map(|p| p.pid));
+        }
+        if let Some(cgroup) = self.cgroup {
+            pids.extend(cgroup.procs());
+        }
+
+        pids.retain(|&pid| pid != self.pid);
+        pids.sort_unstable();
+        pids.dedup();
+        pids
+    }
+}
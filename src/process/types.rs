@@ -1,8 +1,13 @@
 use crate::config::ProcessConfig;
 use crate::ipc::protocol::ProcessId;
-use crate::process::restart::{RestartPolicy, RestartTracker};
+use crate::process::health::HealthTracker;
+use crate::process::history::StatsHistory;
+use crate::process::restart::{ExitKind, RestartPolicy, RestartTracker};
 use crate::process::spawner::SpawnedProcess;
 use serde::{Deserialize, Serialize};
+use std::os::unix::process::ExitStatusExt;
+use std::path::PathBuf;
+use std::process::ExitStatus;
 use std::time::{Duration, SystemTime};
 use tokio::process::Child;
 
@@ -13,6 +18,21 @@ pub enum ProcessState {
     Stopping,
     Stopped,
     Errored,
+    /// Still running, but failing its active health check
+    Unhealthy,
+    /// Scheduled job waiting for its next run
+    Scheduled,
+    /// One-shot task that ran to completion
+    Completed,
+    /// Crashed too often within the restart window and is no longer restarted
+    CrashLoop,
+}
+
+impl ProcessState {
+    /// Check whether the process is expected to have a live child
+    pub fn is_active(&self) -> bool {
+        matches!(self, ProcessState::Running | ProcessState::Unhealthy)
+    }
 }
 
 impl std::fmt::Display for ProcessState {
@@ -23,6 +43,10 @@ impl std::fmt::Display for ProcessState {
             ProcessState::Stopping => write!(f, "stopping"),
             ProcessState::Stopped => write!(f, "stopped"),
             ProcessState::Errored => write!(f, "errored"),
+            ProcessState::Unhealthy => write!(f, "unhealthy"),
+            ProcessState::Scheduled => write!(f, "scheduled"),
+            ProcessState::Completed => write!(f, "completed"),
+            ProcessState::CrashLoop => write!(f, "crash-loop"),
         }
     }
 }
@@ -32,11 +56,35 @@ pub struct ProcessStats {
     pub pid: u32,
     pub started_at: SystemTime,
     pub restarts: usize,
+    /// CPU usage of the main process, in percent of one core
     pub cpu_usage: f32,
+    /// Resident memory of the main process in bytes
     pub memory_usage: u64,
+    /// CPU usage of the main process and everything it started, in percent of one core
+    ///
+    /// Read from the cgroup of the process if it has one, otherwise summed over its descendants.
+    #[serde(default)]
+    pub tree_cpu_usage: f32,
+    /// Memory of the main process and everything it started in bytes
+    ///
+    /// Read from `memory.current` of the cgroup if the process has one, which includes
+    /// page cache, otherwise the resident memory summed over its descendants.
+    #[serde(default)]
+    pub tree_memory_usage: u64,
     pub last_restart: Option<SystemTime>,
     pub memory_violations: usize,
     pub cpu_violations: usize,
+    /// Exit code of the most recent exit (None if killed by a signal or unknown)
+    pub last_exit_code: Option<i32>,
+    /// Signal that killed the process on its most recent exit
+    pub last_exit_signal: Option<i32>,
+    /// When the most recent run of a job started
+    pub last_run: Option<SystemTime>,
+    /// How long the most recent finished run took
+    pub last_run_duration: Option<Duration>,
+    /// Processes of the cgroup that the kernel OOM killer has killed
+    #[serde(default)]
+    pub oom_kills: u64,
 }
 
 impl ProcessStats {
@@ -47,9 +95,16 @@ impl ProcessStats {
             restarts: 0,
             cpu_usage: 0.0,
             memory_usage: 0,
+            tree_cpu_usage: 0.0,
+            tree_memory_usage: 0,
             last_restart: None,
             memory_violations: 0,
             cpu_violations: 0,
+            last_exit_code: None,
+            last_exit_signal: None,
+            last_run: None,
+            last_run_duration: None,
+            oom_kills: 0,
         }
     }
 
@@ -64,8 +119,32 @@ impl ProcessStats {
         self.last_restart = Some(SystemTime::now());
         self.started_at = SystemTime::now();
         self.pid = new_pid;
+        self.clear_usage();
+    }
+
+    /// Record the start of a job run (not counted as a restart)
+    pub fn record_run_start(&mut self, new_pid: u32) {
+        let now = SystemTime::now();
+        self.started_at = now;
+        self.last_run = Some(now);
+        self.pid = new_pid;
+        self.clear_usage();
+    }
+
+    /// Record the exit of the current child, or `None` if its exit status is unknown
+    pub fn record_exit(&mut self, status: Option<ExitStatus>) {
+        self.last_exit_code = status.and_then(|s| s.code());
+        self.last_exit_signal = status.and_then(|s| s.signal());
+        self.last_run_duration = Some(self.uptime());
+        self.clear_usage();
+    }
+
+    /// Reset the resource usage, which is only meaningful while a process runs
+    fn clear_usage(&mut self) {
         self.cpu_usage = 0.0;
         self.memory_usage = 0;
+        self.tree_cpu_usage = 0.0;
+        self.tree_memory_usage = 0;
     }
 
     pub fn record_memory_violation(&mut self) {
@@ -83,11 +162,26 @@ pub struct ManagedProcess {
     pub name: String,
     pub config: ProcessConfig,
     pub state: ProcessState,
-    pub child: Child,
+    /// Current child, or `None` while a scheduled job is waiting for its next run
+    pub child: Option<Child>,
+    /// Earlier runs of a scheduled job that were still running when the next run started
+    pub overlapping_runs: Vec<Child>,
+    /// When a scheduled job runs next
+    pub next_run: Option<SystemTime>,
     pub stats: ProcessStats,
     pub restart_policy: RestartPolicy,
     pub restart_tracker: RestartTracker,
+    pub health_tracker: HealthTracker,
     pub cgroup_manager: Option<crate::process::limits::cgroup::CGroupManager>,
+    /// Start time (clock ticks after boot) of a process adopted from an earlier daemon
+    ///
+    /// An adopted process is not a child of this daemon, so `child` is `None`
+    /// while it runs.
+    pub adopted_start_time: Option<u64>,
+    /// Resource usage recorded while the process ran
+    pub history: StatsHistory,
+    /// Config file the process was loaded from, which a reload of that file may remove it from
+    pub config_file: Option<PathBuf>,
 }
 
 impl ManagedProcess {
@@ -97,33 +191,112 @@ impl ManagedProcess {
         config: ProcessConfig,
         spawned: SpawnedProcess,
     ) -> Self {
-        let restart_policy = RestartPolicy::from_config(
-            config.autorestart,
-            config.max_restarts,
-            config.restart_delay_secs,
-        );
-
-        let cgroup_manager = if config.max_cpu.is_some() {
-            Some(crate::process::limits::cgroup::CGroupManager::new(
-                name.clone(),
-            ))
-        } else {
-            None
-        };
+        let restart_policy = RestartPolicy::from_process_config(&config);
+
+        let mut stats = ProcessStats::new(spawned.pid);
+        if config.oneshot {
+            stats.last_run = Some(stats.started_at);
+        }
 
         Self {
             id,
             name,
             config,
             state: ProcessState::Starting,
-            child: spawned.child,
-            stats: ProcessStats::new(spawned.pid),
+            child: Some(spawned.child),
+            overlapping_runs: Vec::new(),
+            next_run: None,
+            stats,
             restart_policy,
             restart_tracker: RestartTracker::new(),
-            cgroup_manager,
+            health_tracker: HealthTracker::new(),
+            cgroup_manager: None,
+            adopted_start_time: None,
+            history: StatsHistory::default(),
+            config_file: None,
         }
     }
 
+    /// Create a scheduled job that has not run yet
+    pub fn scheduled(id: ProcessId, name: String, config: ProcessConfig) -> Self {
+        let restart_policy = RestartPolicy::from_process_config(&config);
+        let next_run = config.next_run_after(SystemTime::now());
+
+        Self {
+            id,
+            name,
+            config,
+            state: ProcessState::Scheduled,
+            child: None,
+            overlapping_runs: Vec::new(),
+            next_run,
+            stats: ProcessStats::new(0),
+            restart_policy,
+            restart_tracker: RestartTracker::new(),
+            health_tracker: HealthTracker::new(),
+            cgroup_manager: None,
+            adopted_start_time: None,
+            history: StatsHistory::default(),
+            config_file: None,
+        }
+    }
+
+    /// Create a process that is registered without a child, e.g. one restored as stopped
+    pub fn inactive(
+        id: ProcessId,
+        name: String,
+        config: ProcessConfig,
+        state: ProcessState,
+    ) -> Self {
+        Self {
+            state,
+            next_run: None,
+            ..Self::scheduled(id, name, config)
+        }
+    }
+
+    /// PID of the running process, whether it is our child or was adopted
+    pub fn live_pid(&self) -> Option<u32> {
+        (self.child.is_some() || self.adopted_start_time.is_some()).then_some(self.stats.pid)
+    }
+
+    /// Pick up OOM kills the kernel recorded in the cgroup of the process
+    ///
+    /// Returns how many processes of the cgroup were OOM-killed since the last check.
+    pub fn refresh_oom_kills(&mut self) -> u64 {
+        let Some(ref cgroup) = self.cgroup_manager else {
+            return 0;
+        };
+
+        let total = cgroup.oom_kills();
+        let new = total.saturating_sub(self.stats.oom_kills);
+        self.stats.oom_kills = self.stats.oom_kills.max(total);
+        new
+    }
+
+    /// Record the exit of the current child
+    ///
+    /// A run that lasted at least the `min_uptime` of the restart policy counts as
+    /// stable and resets the restart history, and with it the backoff delay.
+    pub fn record_exit(&mut self, status: Option<ExitStatus>) {
+        self.stats.record_exit(status);
+
+        let min_uptime = self.restart_policy.min_uptime();
+        if self
+            .stats
+            .last_run_duration
+            .is_some_and(|uptime| uptime >= min_uptime)
+        {
+            self.restart_tracker.clear();
+        }
+    }
+
+    /// Classify the most recent exit of the process according to its restart policy
+    pub fn last_exit(&self) -> ExitKind {
+        self.restart_policy
+            .classify_exit(self.stats.last_exit_code, self.stats.last_exit_signal)
+    }
+
     pub fn mark_running(&mut self) {
         self.state = ProcessState::Running;
     }
@@ -139,4 +312,20 @@ impl ManagedProcess {
     pub(crate) fn mark_errored(&mut self) {
         self.state = ProcessState::Errored;
     }
+
+    pub(crate) fn mark_unhealthy(&mut self) {
+        self.state = ProcessState::Unhealthy;
+    }
+
+    pub(crate) fn mark_scheduled(&mut self) {
+        self.state = ProcessState::Scheduled;
+    }
+
+    pub(crate) fn mark_completed(&mut self) {
+        self.state = ProcessState::Completed;
+    }
+
+    pub(crate) fn mark_crash_loop(&mut self) {
+        self.state = ProcessState::CrashLoop;
+    }
 }
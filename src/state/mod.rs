@@ -1,31 +1,45 @@
 // State module - Persistent storage for process state
 
+use crate::config::ProcessConfig;
 use crate::error::{AdasaError, Result};
 use crate::ipc::protocol::{ProcessId, ProcessState, ProcessStats};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::Value;
 use std::fs::{self, File};
 use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 /// Version of the state file format
-const STATE_VERSION: &str = "1.0.0";
+const STATE_VERSION: &str = "2.0.0";
+
+/// Config fields that version 1.0.0 stored directly on each process
+const V1_CONFIG_FIELDS: &[&str] = &[
+    "script",
+    "args",
+    "cwd",
+    "env",
+    "autorestart",
+    "restart",
+    "max_restarts",
+    "instances",
+];
 
 /// Persistent state for a single process
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PersistedProcess {
     pub id: ProcessId,
     pub name: String,
-    pub script: PathBuf,
-    pub args: Vec<String>,
-    pub cwd: Option<PathBuf>,
-    pub env: HashMap<String, String>,
+    pub config: ProcessConfig,
     pub state: ProcessState,
     pub stats: ProcessStats,
-    pub autorestart: bool,
-    pub max_restarts: usize,
-    pub instances: usize,
+    /// Start time of `stats.pid` from `/proc/<pid>/stat`, used to re-adopt the process
+    /// if it is still running when the daemon starts again
+    #[serde(default)]
+    pub start_time: Option<u64>,
+    /// Config file the process was loaded from, if any
+    #[serde(default)]
+    pub config_file: Option<PathBuf>,
 }
 
 /// Complete daemon state that gets persisted to disk
@@ -46,9 +60,27 @@ impl DaemonState {
         }
     }
 
+    /// Parse a state file, upgrading it from an older format version first
+    ///
+    /// Each migration step brings the raw JSON up by one version, so files
+    /// written by any earlier release keep loading. Versions without a
+    /// migration are left as they are and rejected by [`DaemonState::validate`].
+    pub fn from_json(mut value: Value) -> Result<Self> {
+        while let Some("1.0.0") = value.get("version").and_then(Value::as_str) {
+            migrate_v1(&mut value)?;
+        }
+
+        let state: DaemonState = serde_json::from_value(value).map_err(|e| {
+            AdasaError::StateLoadError(format!("Failed to parse state file: {}", e))
+        })?;
+        state.validate()?;
+
+        Ok(state)
+    }
+
     /// Validate the state structure
     pub fn validate(&self) -> Result<()> {
-        // Check version compatibility
+        // Check version compatibility (older versions are migrated by `from_json`)
         if self.version != STATE_VERSION {
             return Err(AdasaError::StateCorruption(format!(
                 "Incompatible state version: expected {}, found {}",
@@ -88,6 +120,41 @@ impl Default for DaemonState {
     }
 }
 
+/// Upgrade a 1.0.0 state file to 2.0.0
+///
+/// Version 1.0.0 kept only a few config fields next to the process state; the
+/// rest of the config was lost. Those fields move into a full `config` object,
+/// and everything that was not stored falls back to its config default.
+fn migrate_v1(value: &mut Value) -> Result<()> {
+    let corrupt =
+        |what: &str| AdasaError::StateCorruption(format!("Invalid 1.0.0 state: {}", what));
+
+    let processes = value
+        .get_mut("processes")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| corrupt("missing process list"))?;
+
+    for process in processes {
+        let process = process
+            .as_object_mut()
+            .ok_or_else(|| corrupt("process entry is not an object"))?;
+
+        let mut config = serde_json::Map::new();
+        if let Some(name) = process.get("name") {
+            config.insert("name".to_string(), name.clone());
+        }
+        for field in V1_CONFIG_FIELDS {
+            if let Some(field_value) = process.remove(*field) {
+                config.insert(field.to_string(), field_value);
+            }
+        }
+        process.insert("config".to_string(), Value::Object(config));
+    }
+
+    value["version"] = Value::from("2.0.0");
+    Ok(())
+}
+
 /// State store handles persistence of daemon state to disk
 pub struct StateStore {
     path: PathBuf,
@@ -115,14 +182,12 @@ impl StateStore {
         let reader = BufReader::new(file);
 
         // Deserialize JSON
-        let state: DaemonState = serde_json::from_reader(reader).map_err(|e| {
+        let value: Value = serde_json::from_reader(reader).map_err(|e| {
             AdasaError::StateLoadError(format!("Failed to parse state file: {}", e))
         })?;
 
-        // Validate the loaded state
-        state.validate()?;
-
-        Ok(state)
+        // Migrate older formats and validate the loaded state
+        DaemonState::from_json(value)
     }
 
     /// Save state to disk with atomic writes
@@ -182,20 +247,92 @@ impl StateStore {
     }
 }
 
+/// Snapshot used by `adasa save` and `adasa resurrect` when no name is given
+pub const DEFAULT_SNAPSHOT: &str = "dump";
+
+/// Named snapshots of the daemon state, stored as one file per snapshot in a directory
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    /// Create a snapshot store in the given directory
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Get the state store that reads and writes the named snapshot
+    ///
+    /// Names may contain letters, digits, `-`, `_` and `.`, and may not start with `.`.
+    pub fn store(&self, name: &str) -> Result<StateStore> {
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(AdasaError::StateError(format!(
+                "Invalid snapshot name '{}': use letters, digits, '-', '_' and '.'",
+                name
+            )));
+        }
+
+        Ok(StateStore::new(self.dir.join(format!("{}.json", name))))
+    }
+
+    /// Names of all saved snapshots, sorted
+    pub fn list(&self) -> Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let entries = fs::read_dir(&self.dir).map_err(|e| {
+            AdasaError::StateError(format!("Failed to read snapshot directory: {}", e))
+        })?;
+
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                path.file_stem()?.to_str().map(str::to_string)
+            })
+            .collect();
+        names.sort();
+
+        Ok(names)
+    }
+
+    /// Get the directory the snapshots are stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::units::ByteSize;
     use std::time::Duration;
     use tempfile::TempDir;
 
     fn create_test_process(id: u64, name: &str) -> PersistedProcess {
+        let config: ProcessConfig = serde_json::from_value(serde_json::json!({
+            "name": name,
+            "script": "/usr/bin/test",
+            "args": ["arg1"],
+            "cwd": "/tmp",
+        }))
+        .unwrap();
+
         PersistedProcess {
             id: ProcessId::new(id),
             name: name.to_string(),
-            script: PathBuf::from("/usr/bin/test"),
-            args: vec!["arg1".to_string()],
-            cwd: Some(PathBuf::from("/tmp")),
-            env: HashMap::new(),
+            config,
             state: ProcessState::Running,
             stats: ProcessStats {
                 pid: Some(1234),
@@ -203,11 +340,18 @@ mod tests {
                 restarts: 0,
                 cpu_usage: 1.5,
                 memory_usage: 1024 * 1024,
+                tree_cpu_usage: 1.5,
+                tree_memory_usage: 1024 * 1024,
                 last_restart: None,
+                last_exit_code: None,
+                last_exit_signal: None,
+                last_run: None,
+                last_run_duration: None,
+                next_run: None,
+                oom_kills: 0,
             },
-            autorestart: true,
-            max_restarts: 10,
-            instances: 1,
+            start_time: None,
+            config_file: None,
         }
     }
 
@@ -298,6 +442,77 @@ mod tests {
         assert_eq!(loaded_state.processes[1].name, "test2");
     }
 
+    #[test]
+    fn test_state_store_preserves_full_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = StateStore::new(temp_dir.path().join("state.json"));
+
+        let mut process = create_test_process(1, "limited");
+        process.config.max_memory = Some(ByteSize(256 * 1024 * 1024));
+        process.config.max_cpu = Some(50);
+        process.config.limit_action = crate::config::LimitAction::Restart;
+        process.config.stop_signal = "SIGINT".to_string();
+        process.config.stop_timeout = Duration::from_secs(30);
+        process.config.restart_delay = Duration::from_millis(500);
+
+        let mut state = DaemonState::new();
+        state.processes.push(process.clone());
+        store.save(&state).unwrap();
+
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.processes[0].config, process.config);
+
+        // Sizes and durations are stored in their readable form
+        let saved = fs::read_to_string(temp_dir.path().join("state.json")).unwrap();
+        assert!(saved.contains(r#""max_memory": "256MiB""#));
+        assert!(saved.contains(r#""restart_delay": "500ms""#));
+    }
+
+    #[test]
+    fn test_state_store_migrates_v1() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_path = temp_dir.path().join("state.json");
+        let v1 = r#"{
+            "version": "1.0.0",
+            "processes": [{
+                "id": 3,
+                "name": "api",
+                "script": "/usr/bin/api",
+                "args": ["--port", "8080"],
+                "cwd": null,
+                "env": {"MODE": "prod"},
+                "state": "Running",
+                "stats": {
+                    "pid": 42,
+                    "uptime": {"secs": 10, "nanos": 0},
+                    "restarts": 2,
+                    "cpu_usage": 0.0,
+                    "memory_usage": 0,
+                    "last_restart": null
+                },
+                "autorestart": false,
+                "max_restarts": 4,
+                "instances": 1
+            }],
+            "last_updated": {"secs_since_epoch": 1700000000, "nanos_since_epoch": 0}
+        }"#;
+        fs::write(&state_path, v1).unwrap();
+
+        let state = StateStore::new(&state_path).load().unwrap();
+        assert_eq!(state.version, STATE_VERSION);
+
+        let process = &state.processes[0];
+        assert_eq!(process.id, ProcessId::new(3));
+        assert_eq!(process.config.name, "api");
+        assert_eq!(process.config.script, PathBuf::from("/usr/bin/api"));
+        assert_eq!(process.config.args, vec!["--port", "8080"]);
+        assert_eq!(process.config.env.get("MODE").unwrap(), "prod");
+        assert!(!process.config.autorestart);
+        assert_eq!(process.config.max_restarts, 4);
+        assert_eq!(process.config.stop_signal, "SIGTERM");
+        assert_eq!(process.stats.restarts, 2);
+    }
+
     #[test]
     fn test_state_store_atomic_write() {
         let temp_dir = TempDir::new().unwrap();
@@ -355,6 +570,30 @@ mod tests {
         assert!(state_path.parent().unwrap().exists());
     }
 
+    #[test]
+    fn test_snapshot_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let snapshots = SnapshotStore::new(temp_dir.path().join("dumps"));
+        assert!(snapshots.list().unwrap().is_empty());
+
+        let mut state = DaemonState::new();
+        state.processes.push(create_test_process(1, "test1"));
+        snapshots.store("nightly").unwrap().save(&state).unwrap();
+        snapshots
+            .store(DEFAULT_SNAPSHOT)
+            .unwrap()
+            .save(&DaemonState::new())
+            .unwrap();
+
+        assert_eq!(snapshots.list().unwrap(), vec!["dump", "nightly"]);
+        let loaded = snapshots.store("nightly").unwrap().load().unwrap();
+        assert_eq!(loaded.processes[0].name, "test1");
+
+        for name in ["", "../escape", ".hidden", "a/b"] {
+            assert!(snapshots.store(name).is_err(), "{:?}", name);
+        }
+    }
+
     #[test]
     fn test_state_store_path() {
         let state_path = PathBuf::from("/tmp/test_state.json");
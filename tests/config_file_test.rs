@@ -1,9 +1,10 @@
 // Integration test for configuration file support
 
+use adasa::config::units::ByteSize;
 use adasa::config::ProcessConfig;
-use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 use tempfile::TempDir;
 
 #[test]
@@ -31,11 +32,11 @@ fn test_load_toml_config_single_process() {
     assert_eq!(configs[0].script, PathBuf::from("/bin/echo"));
     assert_eq!(configs[0].args, vec!["hello", "world"]);
     assert_eq!(configs[0].instances, 2);
-    assert_eq!(configs[0].autorestart, true);
+    assert!(configs[0].autorestart);
     assert_eq!(configs[0].max_restarts, 5);
-    assert_eq!(configs[0].restart_delay_secs, 2);
+    assert_eq!(configs[0].restart_delay, Duration::from_secs(2));
     assert_eq!(configs[0].stop_signal, "SIGTERM");
-    assert_eq!(configs[0].stop_timeout_secs, 15);
+    assert_eq!(configs[0].stop_timeout, Duration::from_secs(15));
 }
 
 #[test]
@@ -70,7 +71,7 @@ fn test_load_toml_config_multiple_processes() {
     
     assert_eq!(configs[1].name, "worker");
     assert_eq!(configs[1].instances, 2);
-    assert_eq!(configs[1].max_memory, Some(536870912));
+    assert_eq!(configs[1].max_memory, Some(ByteSize(536870912)));
 }
 
 #[test]
@@ -267,7 +268,7 @@ fn test_config_with_resource_limits() {
 
     let configs = ProcessConfig::from_file(&config_path).unwrap();
     assert_eq!(configs.len(), 1);
-    assert_eq!(configs[0].max_memory, Some(268435456));
+    assert_eq!(configs[0].max_memory, Some(ByteSize(268435456)));
     assert_eq!(configs[0].max_cpu, Some(75));
     assert_eq!(configs[0].limit_action, adasa::config::LimitAction::Restart);
 }
@@ -303,11 +304,11 @@ fn test_config_defaults() {
     
     let config = &configs[0];
     assert_eq!(config.instances, 1);
-    assert_eq!(config.autorestart, true);
+    assert!(config.autorestart);
     assert_eq!(config.max_restarts, 10);
-    assert_eq!(config.restart_delay_secs, 1);
+    assert_eq!(config.restart_delay, Duration::from_secs(1));
     assert_eq!(config.stop_signal, "SIGTERM");
-    assert_eq!(config.stop_timeout_secs, 10);
+    assert_eq!(config.stop_timeout, Duration::from_secs(10));
     assert_eq!(config.limit_action, adasa::config::LimitAction::Log);
 }
 
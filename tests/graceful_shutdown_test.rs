@@ -1,6 +1,5 @@
 use adasa::config::ProcessConfig;
 use adasa::process::{ProcessManager, ProcessState};
-use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -9,17 +8,8 @@ fn create_test_config(name: &str) -> ProcessConfig {
         name: name.to_string(),
         script: PathBuf::from("/bin/sleep"),
         args: vec!["30".to_string()],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
-        autorestart: true,
-        max_restarts: 10,
-        restart_delay_secs: 1,
-        max_memory: None,
-        max_cpu: None,
-        limit_action: adasa::config::LimitAction::Log,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 5,
+        stop_timeout: Duration::from_secs(5),
+        ..Default::default()
     }
 }
 
@@ -75,14 +65,14 @@ async fn test_graceful_shutdown_with_custom_timeout() {
 
     // Spawn a process with custom timeout
     let mut config = create_test_config("timeout-test");
-    config.stop_timeout_secs = 3;
+    config.stop_timeout = Duration::from_secs(3);
 
     let id = manager.spawn(config).await.unwrap();
 
     // Verify process is running
     let process = manager.get_status(id).unwrap();
     assert_eq!(process.state, ProcessState::Running);
-    assert_eq!(process.config.stop_timeout_secs, 3);
+    assert_eq!(process.config.stop_timeout, Duration::from_secs(3));
 
     // Stop gracefully
     let start = std::time::Instant::now();
@@ -105,7 +95,7 @@ async fn test_force_kill_bypasses_graceful_shutdown() {
 
     // Spawn a process with a long timeout
     let mut config = create_test_config("force-kill-test");
-    config.stop_timeout_secs = 10; // Long timeout
+    config.stop_timeout = Duration::from_secs(10); // Long timeout
 
     let id = manager.spawn(config).await.unwrap();
 
@@ -1,6 +1,8 @@
 // Integration test for IPC protocol serialization/deserialization
 
-use adasa::ipc::{Command, ProcessId, ProcessState, Request, Response, ResponseData, StartOptions};
+use adasa::ipc::{
+    Command, Event, ProcessId, ProcessState, Request, Response, ResponseData, StartOptions,
+};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -53,6 +55,33 @@ fn test_response_serialization() {
     assert!(deserialized.result.is_ok());
 }
 
+#[test]
+fn test_event_serialization() {
+    let event = Event::ProcessExited {
+        id: ProcessId::new(3),
+        name: "worker".to_string(),
+        exit_code: None,
+        signal: Some(9),
+    };
+
+    // Events are tagged so clients can dispatch on the "event" field
+    let json = serde_json::to_value(&event).expect("Failed to serialize event");
+    assert_eq!(json["event"], "process_exited");
+    assert_eq!(json["id"], 3);
+    assert_eq!(json["signal"], 9);
+
+    let response = Response::success(7, ResponseData::Event(event.clone()));
+    let json = serde_json::to_string(&response).expect("Failed to serialize");
+    let deserialized: Response = serde_json::from_str(&json).expect("Failed to deserialize");
+    match deserialized.result {
+        Ok(ResponseData::Event(received)) => assert_eq!(received, event),
+        other => panic!("Expected an event, got {:?}", other),
+    }
+
+    let json = serde_json::to_value(Event::DaemonShuttingDown).unwrap();
+    assert_eq!(json["event"], "daemon_shutting_down");
+}
+
 #[test]
 fn test_process_state_display() {
     assert_eq!(ProcessState::Starting.to_string(), "starting");
@@ -1,8 +1,8 @@
 // Integration tests for IPC server
 
 use adasa::ipc::{
-    Command, IpcClient, IpcServer, ProcessId, ProcessInfo, ProcessState, ProcessStats, Response,
-    ResponseData,
+    Command, IpcClient, IpcServer, ProcessId, ProcessInfo, ProcessState, ProcessStats, Reply,
+    Response, ResponseData,
 };
 use std::thread;
 use std::time::Duration;
@@ -153,3 +153,46 @@ fn test_server_multiple_connections() {
     // Wait for server thread to finish
     server_thread.join().expect("Server thread panicked");
 }
+
+#[tokio::test]
+async fn test_server_streams_replies() {
+    let socket_path = "/tmp/test_adasa_stream.sock";
+
+    let mut server = IpcServer::with_socket_path(socket_path);
+    server.start().expect("Failed to start server");
+
+    let server_task = tokio::spawn(async move {
+        let _ = server
+            .run(|_command| async {
+                let (tx, rx) = tokio::sync::mpsc::channel(4);
+                tokio::spawn(async move {
+                    for i in 1..=2 {
+                        let line = format!("line {}", i);
+                        let _ = tx.send(ResponseData::Logs(vec![line])).await;
+                    }
+                });
+
+                Ok(Reply::Stream {
+                    first: Response::success(0, ResponseData::Logs(vec!["line 0".to_string()])),
+                    updates: rx,
+                })
+            })
+            .await;
+    });
+
+    let client = IpcClient::with_socket_path(socket_path);
+    let mut lines = Vec::new();
+    client
+        .stream_command(Command::List, |response| {
+            if let Ok(ResponseData::Logs(batch)) = response.result {
+                lines.extend(batch);
+            }
+        })
+        .await
+        .expect("Failed to stream command");
+
+    // The stream ends once the daemon drops the sender
+    assert_eq!(lines, vec!["line 0", "line 1", "line 2"]);
+
+    server_task.abort();
+}
@@ -1,7 +1,8 @@
+use adasa::config::units::ByteSize;
 use adasa::config::{LimitAction, ProcessConfig};
 use adasa::process::{ProcessManager, ResourceLimits};
-use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[tokio::test]
 async fn test_resource_limits_creation() {
@@ -19,22 +20,15 @@ async fn test_process_config_with_resource_limits() {
         name: "test-app".to_string(),
         script: PathBuf::from("/bin/echo"),
         args: vec!["hello".to_string()],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
-        autorestart: true,
-        max_restarts: 10,
-        restart_delay_secs: 1,
-        max_memory: Some(1024 * 1024 * 512), // 512MB
-        max_cpu: Some(75),                   // 75%
+        max_memory: Some(ByteSize(1024 * 1024 * 512)), // 512MB
+        max_cpu: Some(75),                             // 75%
         limit_action: LimitAction::Restart,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 10,
+        ..Default::default()
     };
 
     // Validate configuration
     assert!(config.validate().is_ok());
-    assert_eq!(config.max_memory, Some(1024 * 1024 * 512));
+    assert_eq!(config.max_memory, Some(ByteSize(1024 * 1024 * 512)));
     assert_eq!(config.max_cpu, Some(75));
     assert_eq!(config.limit_action, LimitAction::Restart);
 }
@@ -44,18 +38,8 @@ async fn test_invalid_cpu_limit() {
     let config = ProcessConfig {
         name: "test-app".to_string(),
         script: PathBuf::from("/bin/echo"),
-        args: vec![],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
-        autorestart: true,
-        max_restarts: 10,
-        restart_delay_secs: 1,
-        max_memory: None,
         max_cpu: Some(150), // Invalid: > 100
-        limit_action: LimitAction::Log,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 10,
+        ..Default::default()
     };
 
     // Should fail validation
@@ -67,18 +51,8 @@ async fn test_zero_cpu_limit() {
     let config = ProcessConfig {
         name: "test-app".to_string(),
         script: PathBuf::from("/bin/echo"),
-        args: vec![],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
-        autorestart: true,
-        max_restarts: 10,
-        restart_delay_secs: 1,
-        max_memory: None,
         max_cpu: Some(0), // Invalid: must be at least 1
-        limit_action: LimitAction::Log,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 10,
+        ..Default::default()
     };
 
     // Should fail validation
@@ -126,17 +100,10 @@ async fn test_spawn_process_with_cpu_limit() {
         name: "cpu-limited-test".to_string(),
         script: PathBuf::from("/bin/sleep"),
         args: vec!["5".to_string()],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
         autorestart: false,
-        max_restarts: 10,
-        restart_delay_secs: 1,
-        max_memory: None,
         max_cpu: Some(50), // 50% CPU limit
-        limit_action: LimitAction::Log,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 2,
+        stop_timeout: Duration::from_secs(2),
+        ..Default::default()
     };
 
     let result = manager.spawn(config).await;
@@ -160,17 +127,9 @@ async fn test_spawn_process_without_cpu_limit() {
         name: "no-cpu-limit-test".to_string(),
         script: PathBuf::from("/bin/sleep"),
         args: vec!["5".to_string()],
-        cwd: None,
-        env: HashMap::new(),
-        instances: 1,
         autorestart: false,
-        max_restarts: 10,
-        restart_delay_secs: 1,
-        max_memory: None,
-        max_cpu: None, // No CPU limit
-        limit_action: LimitAction::Log,
-        stop_signal: "SIGTERM".to_string(),
-        stop_timeout_secs: 2,
+        stop_timeout: Duration::from_secs(2),
+        ..Default::default()
     };
 
     let result = manager.spawn(config).await;
@@ -1,9 +1,9 @@
 use adasa::config::ProcessConfig;
-use adasa::ipc::protocol::{Command, ProcessId, RestartOptions};
 use adasa::process::ProcessManager;
-use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::RwLock;
 
 #[tokio::test]
 async fn test_rolling_restart_integration() {
@@ -16,17 +16,9 @@ async fn test_rolling_restart_integration() {
             name: format!("web-server-{}", i),
             script: PathBuf::from("/bin/sleep"),
             args: vec!["30".to_string()],
-            cwd: None,
-            env: HashMap::new(),
-            instances: 1,
-            autorestart: true,
-            max_restarts: 10,
-            restart_delay_secs: 1,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: adasa::config::LimitAction::Log,
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 5,
+            stop_timeout: Duration::from_secs(5),
+            rolling_settle: Duration::from_millis(500),
+            ..Default::default()
         };
 
         let id = manager.spawn(config).await.unwrap();
@@ -46,10 +38,7 @@ async fn test_rolling_restart_integration() {
         .collect();
 
     // Perform rolling restart
-    let health_check_delay = Duration::from_millis(500);
-    let result = manager
-        .rolling_restart("web-server", health_check_delay)
-        .await;
+    let result = manager.rolling_restart("web-server").await;
 
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), 3);
@@ -83,17 +72,8 @@ async fn test_rolling_restart_maintains_availability() {
             name: format!("api-server-{}", i),
             script: PathBuf::from("/bin/sleep"),
             args: vec!["30".to_string()],
-            cwd: None,
-            env: HashMap::new(),
-            instances: 1,
-            autorestart: true,
-            max_restarts: 10,
-            restart_delay_secs: 1,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: adasa::config::LimitAction::Log,
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 5,
+            stop_timeout: Duration::from_secs(5),
+            ..Default::default()
         };
 
         let id = manager.spawn(config).await.unwrap();
@@ -101,9 +81,7 @@ async fn test_rolling_restart_maintains_availability() {
     }
 
     // Start rolling restart in background
-    let health_check_delay = Duration::from_millis(300);
     let restart_task = tokio::spawn({
-        let mut manager_clone = ProcessManager::new();
         // Note: In a real scenario, we'd share the manager properly
         // For this test, we're just verifying the logic
         async move {
@@ -148,17 +126,9 @@ async fn test_rolling_restart_with_failing_health_check() {
             name: format!("failing-app-{}", i),
             script: PathBuf::from("/bin/sleep"),
             args: vec!["10".to_string()],
-            cwd: None,
-            env: HashMap::new(),
-            instances: 1,
-            autorestart: true,
-            max_restarts: 10,
-            restart_delay_secs: 1,
-            max_memory: None,
-            max_cpu: None,
-            limit_action: adasa::config::LimitAction::Log,
-            stop_signal: "SIGTERM".to_string(),
-            stop_timeout_secs: 2,
+            stop_timeout: Duration::from_secs(2),
+            rolling_settle: Duration::from_millis(200),
+            ..Default::default()
         };
 
         let id = manager.spawn(config).await.unwrap();
@@ -170,10 +140,7 @@ async fn test_rolling_restart_with_failing_health_check() {
     manager.stop(first_id, true).await.unwrap();
 
     // Try rolling restart - it should fail health check
-    let health_check_delay = Duration::from_millis(200);
-    let result = manager
-        .rolling_restart("failing-app", health_check_delay)
-        .await;
+    let result = manager.rolling_restart("failing-app").await;
 
     // Should fail because the first instance is not alive
     assert!(result.is_err());
@@ -183,3 +150,42 @@ async fn test_rolling_restart_with_failing_health_check() {
         let _ = manager.stop(id, true).await;
     }
 }
+
+#[tokio::test]
+async fn test_rolling_restart_shared_releases_lock() {
+    let manager = Arc::new(RwLock::new(ProcessManager::new()));
+
+    let mut instance_ids = Vec::new();
+    for i in 0..2 {
+        let config = ProcessConfig {
+            name: format!("shared-app-{}", i),
+            script: PathBuf::from("/bin/sleep"),
+            args: vec!["30".to_string()],
+            stop_timeout: Duration::from_secs(2),
+            rolling_settle: Duration::from_millis(500),
+            ..Default::default()
+        };
+        instance_ids.push(manager.write().await.spawn(config).await.unwrap());
+    }
+
+    let rollout = tokio::spawn({
+        let manager = Arc::clone(&manager);
+        async move { ProcessManager::rolling_restart_shared(&manager, "shared-app", None).await }
+    });
+
+    // While the replacements settle, the manager can still be locked by others
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let listed = tokio::time::timeout(Duration::from_millis(100), async {
+        manager.write().await.list().len()
+    })
+    .await;
+    assert_eq!(listed.ok(), Some(2));
+
+    assert_eq!(rollout.await.unwrap().unwrap(), 2);
+
+    let mut manager = manager.write().await;
+    for id in instance_ids {
+        assert_eq!(manager.get_status(id).unwrap().stats.restarts, 1);
+        let _ = manager.stop(id, true).await;
+    }
+}